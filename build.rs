@@ -17,11 +17,17 @@
 // <https://www.mozilla.org/en-US/MPL/2.0/>.
 
 fn main() {
+    // Let rustc know about our platform aliases.
+    println!("cargo:rustc-check-cfg=cfg(android, apple, win32, free_unix, default_platform)");
+
     // Define specific platforms.
     cfg_aliases::cfg_aliases! {
         android: { target_os = "android" },
         apple: { any(target_os = "ios", target_os = "macos") },
         win32: { target_os = "windows" },
-        free_unix: { all(unix, not(apple), not(android)) }
+        free_unix: { all(unix, not(apple), not(android)) },
+
+        // Platforms that we can't read the theme of, which fall back to the default theme.
+        default_platform: { any(not(feature = "std"), not(any(free_unix, win32))) }
     }
 }
@@ -46,33 +46,108 @@ impl Color {
 
     /// Darken a color by a factor.
     pub(crate) const fn darken(self, percent: u8) -> Self {
-        macro_rules! t {
-            ($e:expr) => {{
-                (($e as u16 * percent as u16) / 100) as u8
-            }};
-        }
+        self.scale(percent as f32 / 100.0)
+    }
 
+    /// Multiply the color channels by a factor, leaving alpha untouched.
+    pub(crate) const fn scale(self, factor: f32) -> Self {
         let [r, g, b, a] = self.0;
-        Self::new(t!(r), t!(g), t!(b), a)
+        Self::new(
+            channel(r as f32 * factor),
+            channel(g as f32 * factor),
+            channel(b as f32 * factor),
+            a,
+        )
     }
 
     /// Mix two colors by a factor.
     pub(crate) const fn mix(self, other: Self, percent: u8) -> Self {
+        self.mix_by(other, percent as f32 / 100.0)
+    }
+
+    /// Mix two colors, where a `factor` of zero is this color and one is the other color.
+    ///
+    /// This is the same as GTK 4's `mix()`, which interpolates the alpha channel and mixes the
+    /// premultiplied color channels.
+    pub(crate) const fn mix_by(self, other: Self, factor: f32) -> Self {
+        let [r, g, b, a] = self.0;
+        let [or, og, ob, oa] = other.0;
+        let (a, oa) = (a as f32, oa as f32);
+
+        let alpha = a + (oa - a) * factor;
+        if alpha <= 0.0 {
+            return Self::new(0, 0, 0, 0);
+        }
+
         macro_rules! t {
             ($e:expr, $o:expr) => {{
-                let e = $e as u16;
-                let o = $o as u16;
-                let p = percent as u16;
-                let result = e + ((o - e) * p) / 100;
-                result as u8
+                let e = $e as f32 * a;
+                let o = $o as f32 * oa;
+                channel((e + (o - e) * factor) / alpha)
             }};
         }
 
+        Self::new(t!(r, or), t!(g, og), t!(b, ob), channel(alpha))
+    }
+
+    /// Mix two colors without premultiplying them, where a `factor` of zero is this color and one
+    /// is the other color.
+    ///
+    /// This is the same as GTK 3's `mix()`, which interpolates every channel on its own.
+    pub(crate) const fn mix_straight(self, other: Self, factor: f32) -> Self {
         let [r, g, b, a] = self.0;
         let [or, og, ob, oa] = other.0;
+
+        macro_rules! t {
+            ($e:expr, $o:expr) => {{
+                let e = $e as f32;
+                channel(e + ($o as f32 - e) * factor)
+            }};
+        }
+
         Self::new(t!(r, or), t!(g, og), t!(b, ob), t!(a, oa))
     }
 
+    /// Multiply the alpha channel by a factor.
+    ///
+    /// This is the same as GTK's `alpha()`.
+    pub(crate) const fn scale_alpha(self, factor: f32) -> Self {
+        let [r, g, b, a] = self.0;
+        Self::new(r, g, b, channel(a as f32 * factor))
+    }
+
+    /// Multiply the lightness and saturation of a color by a factor.
+    ///
+    /// This is the same as GTK's `shade()`; `lighter()` and `darker()` are shades by `1.3` and
+    /// `0.7` respectively.
+    pub(crate) fn shade(self, factor: f32) -> Self {
+        self.map_lightness(|lightness, saturation| (lightness * factor, saturation * factor))
+    }
+
+    /// Adjust the lightness and saturation of a color, in the same HLS space that GTK uses.
+    ///
+    /// The results of `f` are clamped between zero and one.
+    pub(crate) fn map_lightness(self, f: impl FnOnce(f32, f32) -> (f32, f32)) -> Self {
+        let [r, g, b, a] = self.0;
+        let (hue, lightness, saturation) =
+            rgb_to_hls(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+
+        let (lightness, saturation) = f(lightness, saturation);
+        let (r, g, b) = hls_to_rgb(hue, lightness.clamp(0.0, 1.0), saturation.clamp(0.0, 1.0));
+        Self::new(
+            channel(r * 255.0),
+            channel(g * 255.0),
+            channel(b * 255.0),
+            a,
+        )
+    }
+
+    /// Get the perceived brightness of the color, between zero and one.
+    pub(crate) fn luminance(self) -> f32 {
+        let [r, g, b, _] = self.0;
+        (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+    }
+
     /// Convert into a 4-tuple.
     pub fn into_tuple(self) -> (u8, u8, u8, u8) {
         let [r, g, b, a] = self.0;
@@ -105,6 +180,78 @@ impl Color {
     }
 }
 
+/// Round a channel value to the nearest integer, saturating at the ends of the range.
+const fn channel(value: f32) -> u8 {
+    (value + 0.5) as u8
+}
+
+/// Convert RGB to hue, lightness and saturation, the way GTK does.
+fn rgb_to_hls(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+
+    if max == min {
+        return (0.0, lightness, 0.0);
+    }
+
+    let delta = max - min;
+    let saturation = if lightness <= 0.5 {
+        delta / (max + min)
+    } else {
+        delta / (2.0 - max - min)
+    };
+
+    let mut hue = if r == max {
+        (g - b) / delta
+    } else if g == max {
+        2.0 + (b - r) / delta
+    } else {
+        4.0 + (r - g) / delta
+    } * 60.0;
+
+    if hue < 0.0 {
+        hue += 360.0;
+    }
+
+    (hue, lightness, saturation)
+}
+
+/// Convert hue, lightness and saturation back to RGB, the way GTK does.
+fn hls_to_rgb(hue: f32, lightness: f32, saturation: f32) -> (f32, f32, f32) {
+    if saturation == 0.0 {
+        return (lightness, lightness, lightness);
+    }
+
+    let m2 = if lightness <= 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let m1 = 2.0 * lightness - m2;
+
+    let value = |mut hue: f32| {
+        while hue >= 360.0 {
+            hue -= 360.0;
+        }
+        while hue < 0.0 {
+            hue += 360.0;
+        }
+
+        if hue < 60.0 {
+            m1 + (m2 - m1) * hue / 60.0
+        } else if hue < 180.0 {
+            m2
+        } else if hue < 240.0 {
+            m1 + (m2 - m1) * (240.0 - hue) / 60.0
+        } else {
+            m1
+        }
+    };
+
+    (value(hue + 120.0), value(hue), value(hue - 120.0))
+}
+
 /// Parse a hex string to a `u8` at compile time.
 ///
 /// Takes the bytes of a hex string and returns the value of the hex string.
@@ -127,3 +274,80 @@ const fn const_parse_hex(hex: &[u8]) -> u8 {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The expected values follow GTK's double precision math in `gtkhsla.c` and
+    // `gtkcsscolorvalue.c`, rounded to bytes like `gdk_rgba_to_string()` does.
+
+    const BLUE: Color = Color::hex("#3584e4");
+
+    #[test]
+    fn shade() {
+        assert_eq!(BLUE.shade(0.8), Color::new(44, 106, 181, 255));
+        assert_eq!(BLUE.shade(1.3), Color::new(111, 176, 255, 255));
+        assert_eq!(BLUE.shade(0.7), Color::new(46, 93, 151, 255));
+        assert_eq!(
+            Color::hex("#f6f5f4").shade(1.1),
+            Color::new(255, 255, 255, 255)
+        );
+        assert_eq!(
+            Color::hex("#353535").shade(0.9),
+            Color::new(48, 48, 48, 255)
+        );
+
+        // The alpha channel is left alone.
+        assert_eq!(Color::new(53, 132, 228, 77).shade(0.8).a(), 77);
+    }
+
+    #[test]
+    fn alpha() {
+        assert_eq!(BLUE.scale_alpha(0.3), Color::new(53, 132, 228, 77));
+        assert_eq!(BLUE.scale_alpha(0.0), Color::new(53, 132, 228, 0));
+    }
+
+    #[test]
+    fn mix() {
+        let red = Color::new(255, 0, 0, 128);
+        let blue = Color::new(0, 0, 255, 255);
+
+        // GTK 3 mixes the channels as they are.
+        assert_eq!(red.mix_straight(blue, 0.5), Color::new(128, 0, 128, 192));
+
+        // GTK 4 weighs them by their alpha.
+        assert_eq!(red.mix_by(blue, 0.5), Color::new(85, 0, 170, 192));
+
+        let white = Color::new(255, 255, 255, 51);
+        let black = Color::new(0, 0, 0, 204);
+        assert_eq!(
+            white.mix_straight(black, 0.25),
+            Color::new(191, 191, 191, 89)
+        );
+        assert_eq!(white.mix_by(black, 0.25), Color::new(109, 109, 109, 89));
+
+        // Opaque colors mix the same either way.
+        let opaque = Color::new(114, 169, 236, 255);
+        assert_eq!(BLUE.mix_straight(Color::hex("#ffffff"), 0.3), opaque);
+        assert_eq!(BLUE.mix_by(Color::hex("#ffffff"), 0.3), opaque);
+
+        let clear = Color::new(255, 0, 0, 0);
+        assert_eq!(
+            clear.mix_by(Color::new(0, 0, 255, 0), 0.5),
+            Color::new(0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn hls_round_trip() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let color = Color::new(r, g, b, 255);
+                    assert_eq!(color.map_lightness(|l, s| (l, s)), color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! The environment that themes are loaded from.
+
+use crate::util::HashMap;
+
+use alloc::string::String;
+use std::path::{Path, PathBuf};
+
+/// The environment that a theme is loaded from.
+///
+/// By default, this is the environment of the running process: its environment variables, the
+/// root filesystem and the session bus. Each part can be replaced, which makes it possible to
+/// load themes out of a directory tree that was set up for testing.
+///
+/// Loading never runs other programs, since settings databases like dconf's are read directly, so
+/// there is no process runner to replace.
+#[derive(Debug, Clone, Default)]
+pub struct LoadContext {
+    /// The environment variables, if they aren't the process's.
+    env: Option<HashMap<String, String>>,
+
+    /// The directory that absolute paths are relative to, if it isn't the root.
+    sysroot: Option<PathBuf>,
+
+    /// The address of the session bus, if it isn't the one in the environment.
+    bus_address: Option<String>,
+
+    /// The version of GTK whose stylesheets are preferred, if it was set.
+    gtk_version: Option<GtkVersion>,
+}
+
+/// A major version of GTK.
+///
+/// GTK themes ship a stylesheet for each version that they support in its own directory, like
+/// `gtk-3.0` and `gtk-4.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GtkVersion {
+    /// GTK 3, in `gtk-3.0`.
+    Gtk3,
+
+    /// GTK 4, in `gtk-4.0`.
+    Gtk4,
+}
+
+impl LoadContext {
+    /// Create a context for the environment of the running process.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use these environment variables instead of the process's.
+    ///
+    /// Variables that aren't in the list are treated as unset.
+    pub fn set_env<K, V>(&mut self, vars: impl IntoIterator<Item = (K, V)>) -> &mut Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env = Some(
+            vars.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Read files out of this directory, as if it were the root of the filesystem.
+    ///
+    /// Paths in the environment variables, like `XDG_DATA_DIRS` or `HOME`, are inside of the
+    /// directory as well.
+    pub fn set_sysroot(&mut self, sysroot: impl Into<PathBuf>) -> &mut Self {
+        self.sysroot = Some(sysroot.into());
+        self
+    }
+
+    /// Connect to the session bus at this address, like `unix:path=/run/user/1000/bus`.
+    ///
+    /// Without one, the address is taken from `DBUS_SESSION_BUS_ADDRESS`. If the environment
+    /// variables were replaced and don't have it, the session bus isn't used at all.
+    pub fn set_bus_address(&mut self, address: impl Into<String>) -> &mut Self {
+        self.bus_address = Some(address.into());
+        self
+    }
+
+    /// Prefer the stylesheets and settings of this version of GTK when a theme has more than one.
+    ///
+    /// Without one, GTK 3 is preferred. The other version is still used for themes that only
+    /// support it.
+    pub fn set_gtk_version(&mut self, version: GtkVersion) -> &mut Self {
+        self.gtk_version = Some(version);
+        self
+    }
+
+    /// Get an environment variable.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn var(&self, key: &str) -> Option<String> {
+        match &self.env {
+            Some(env) => env.get(key).cloned(),
+            None => std::env::var(key).ok(),
+        }
+    }
+
+    /// Tell whether the environment variables are the process's.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn inherits_env(&self) -> bool {
+        self.env.is_none()
+    }
+
+    /// Get the path that an absolute path refers to, taking the sysroot into account.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn path(&self, path: &Path) -> PathBuf {
+        match &self.sysroot {
+            Some(sysroot) => sysroot.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Get the directory that absolute paths are relative to, if it isn't the root.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn sysroot(&self) -> Option<&Path> {
+        self.sysroot.as_deref()
+    }
+
+    /// Get the version of GTK to prefer.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn gtk_version(&self) -> GtkVersion {
+        self.gtk_version.unwrap_or(GtkVersion::Gtk3)
+    }
+
+    /// Get the address of the session bus set on this context.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn bus_address(&self) -> Option<&str> {
+        self.bus_address.as_deref()
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Mouse cursors from the system's cursor theme.
+
+use crate::{platform, LoadContext, LoadThemeError};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// A cursor theme, used to load cursors by their names.
+///
+/// Cursors that the theme doesn't have are taken from the themes that it builds on.
+#[derive(Debug, Clone)]
+pub struct CursorTheme {
+    inner: platform::CursorTheme,
+}
+
+impl CursorTheme {
+    /// Load the cursor theme that the user picked, or the one with the provided name.
+    ///
+    /// Returns `None` on platforms that don't have cursor themes.
+    pub async fn load(name: impl Into<Option<&str>>) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_cursor_theme(&LoadContext::new(), name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a cursor theme using the blocking API.
+    pub fn load_blocking<'a>(
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_cursor_theme_blocking(&LoadContext::new(), name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a cursor theme, as seen from the provided context.
+    pub async fn load_with_context(
+        context: &LoadContext,
+        name: impl Into<Option<&str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_cursor_theme(context, name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a cursor theme, as seen from the provided context, using the blocking API.
+    pub fn load_blocking_with_context<'a>(
+        context: &LoadContext,
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_cursor_theme_blocking(context, name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Get the name of the cursor theme.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Get the size that the user picked for cursors, in logical pixels.
+    pub fn size(&self) -> u32 {
+        self.inner.size()
+    }
+
+    /// Load a cursor by its name, like `default`, `pointer`, `text` or `col-resize`.
+    ///
+    /// The names from the CSS `cursor` property are used, but the older X11 names like
+    /// `left_ptr` are understood as well.
+    pub async fn load_cursor(&self, name: &str) -> Result<Option<Cursor>, LoadThemeError> {
+        let inner = self.inner.clone();
+        let name = String::from(name);
+        blocking::unblock(move || inner.load_cursor(&name)).await
+    }
+
+    /// Load a cursor by its name using the blocking API.
+    pub fn load_cursor_blocking(&self, name: &str) -> Result<Option<Cursor>, LoadThemeError> {
+        self.inner.load_cursor(name)
+    }
+}
+
+/// A cursor, made of images for each size that it comes in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    /// The images, in the order they're stored in.
+    images: Vec<CursorImage>,
+}
+
+impl Cursor {
+    /// Create a cursor out of its images.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn new(images: Vec<CursorImage>) -> Self {
+        Self { images }
+    }
+
+    /// Get every image of the cursor, for every size.
+    pub fn images(&self) -> &[CursorImage] {
+        &self.images
+    }
+
+    /// Get the sizes that the cursor comes in, from the smallest to the largest.
+    pub fn sizes(&self) -> Vec<u32> {
+        let mut sizes = self
+            .images
+            .iter()
+            .map(|image| image.size)
+            .collect::<Vec<_>>();
+        sizes.sort_unstable();
+        sizes.dedup();
+        sizes
+    }
+
+    /// Get the images to draw the cursor at a size, in pixels.
+    ///
+    /// These are the images of the closest size that the cursor comes in. There is more than one
+    /// if the cursor is animated, in which case they're the frames in order.
+    pub fn frames(&self, size: u32) -> impl Iterator<Item = &CursorImage> + '_ {
+        let closest = self
+            .images
+            .iter()
+            .map(|image| image.size)
+            .min_by_key(|nominal| nominal.abs_diff(size));
+
+        self.images
+            .iter()
+            .filter(move |image| Some(image.size) == closest)
+    }
+}
+
+/// One image of a cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    /// The size that the image is meant for.
+    size: u32,
+
+    /// The width of the image, in pixels.
+    width: u32,
+
+    /// The height of the image, in pixels.
+    height: u32,
+
+    /// The point of the image that the cursor points at.
+    hotspot: (u32, u32),
+
+    /// How long to show the image for, if the cursor is animated.
+    delay: Duration,
+
+    /// The pixels, as RGBA with straight alpha, row by row.
+    pixels: Vec<u8>,
+}
+
+impl CursorImage {
+    /// Create an image.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn new(
+        size: u32,
+        (width, height): (u32, u32),
+        hotspot: (u32, u32),
+        delay: Duration,
+        pixels: Vec<u8>,
+    ) -> Self {
+        Self {
+            size,
+            width,
+            height,
+            hotspot,
+            delay,
+            pixels,
+        }
+    }
+
+    /// Get the size that the image is meant for.
+    ///
+    /// This is the size that the cursor theme was designed around, which may be smaller than the
+    /// image itself.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Get the width of the image, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the image, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the point of the image that the cursor points at, from the top left corner.
+    pub fn hotspot(&self) -> (u32, u32) {
+        self.hotspot
+    }
+
+    /// Get how long to show the image for before the next frame, if the cursor is animated.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Get the pixels, as RGBA with straight alpha, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
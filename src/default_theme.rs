@@ -19,11 +19,27 @@
 //! A default theme similar to Adwaita.
 
 use crate::{
-    Border, Color, FontFamily, LoadThemeError, Margin, ShadePreference, TextAlignment, TextStyle,
-    Theme, Widget, WidgetState, WIDGETS, WIDGET_STATES,
+    Border, Color, ContrastPreference, FontFamily, Margin, ShadePreference, TextAlignment,
+    TextStyle, Theme, ThemeInfo, ThemeSource, Widget, WidgetState, WIDGETS, WIDGET_STATES,
 };
 
 use alloc::format;
+use alloc::vec::Vec;
+
+#[cfg(any(default_platform, not(free_unix)))]
+use crate::{LoadContext, LoadThemeError};
+
+#[cfg(default_platform)]
+use {
+    alloc::string::String,
+    futures_lite::{stream, Stream},
+};
+
+#[cfg(not(free_unix))]
+use {
+    crate::{Cursor, ResolvedFont},
+    std::path::PathBuf,
+};
 
 macro_rules! choose {
     ($T:ident,$light:expr,$dark:expr) => {{
@@ -35,65 +51,65 @@ macro_rules! choose {
     }};
 }
 
+macro_rules! contrast {
+    ($T:ident,$normal:expr,$high:expr) => {{
+        if $T::IS_HIGH_CONTRAST {
+            $high
+        } else {
+            $normal
+        }
+    }};
+}
+
 const BLACK: Color = Color::new(0, 0, 0, 255);
 const WHITE: Color = Color::new(255, 255, 255, 255);
 
 trait ThemeType {
     const IS_LIGHT: bool;
+    const IS_HIGH_CONTRAST: bool = false;
 
     const TEXT_COLOR: Color = choose!(Self, BLACK, WHITE);
     const BASE_COLOR: Color = choose!(Self, WHITE, BLACK);
-    const BG_COLOR: Color = choose!(Self, Color::hex("#f6f5f4"), Color::hex("#3d3846"));
-    const FG_COLOR: Color = choose!(Self, Color::hex("#2e3436"), Color::hex("#eeeeec"));
-
-    const SELECTED_FG_COLOR: Color = WHITE;
-    const SELECTED_BG_COLOR: Color = choose!(
+    const BG_COLOR: Color = contrast!(
         Self,
-        Color::hex("#3584e4"),
-        Color::hex("#3584e3").darken(20)
+        choose!(Self, Color::hex("#f6f5f4"), Color::hex("#3d3846")),
+        Self::BASE_COLOR
     );
-    const SELECTED_BORDERS_COLOR: Color = choose!(
+    const FG_COLOR: Color = contrast!(
         Self,
-        Self::SELECTED_BG_COLOR.darken(15),
-        Self::SELECTED_BG_COLOR.darken(30)
+        choose!(Self, Color::hex("#2e3436"), Color::hex("#eeeeec")),
+        Self::TEXT_COLOR
     );
 
-    const BORDERS_COLOR: Color =
-        choose!(Self, Self::BG_COLOR.darken(18), Self::BG_COLOR.darken(10));
-    const ALT_BORDERS_COLOR: Color =
-        choose!(Self, Self::BG_COLOR.darken(24), Self::BG_COLOR.darken(18));
-    const LINK_COLOR: Color = choose!(
+    const SELECTED_FG_COLOR: Color = WHITE;
+    const ACCENT_COLOR: Color = contrast!(
         Self,
-        Self::SELECTED_BG_COLOR.darken(10),
-        Self::SELECTED_BG_COLOR.darken(20)
+        choose!(Self, Color::hex("#3584e4"), Color::hex("#3584e3")),
+        Color::hex("#1c71d8")
     );
-    const SELECTED_LINK_COLOR: Color = choose!(
+
+    const BORDERS_COLOR: Color = contrast!(
         Self,
-        Self::SELECTED_BG_COLOR.darken(20),
-        Self::SELECTED_BG_COLOR.darken(10)
+        choose!(Self, Self::BG_COLOR.darken(18), Self::BG_COLOR.darken(10)),
+        Self::FG_COLOR
     );
 
-    const SCROLLBAR_BG_COLOR: Color = choose!(
+    const DISABLED_FG_COLOR: Color = contrast!(
         Self,
-        Self::BG_COLOR.mix(Self::FG_COLOR, 80),
-        Self::BASE_COLOR.mix(Self::BG_COLOR, 50)
+        Self::FG_COLOR.mix(Self::BG_COLOR, 50),
+        Self::FG_COLOR.mix(Self::BG_COLOR, 30)
     );
-    const SCROLLBAR_SLIDER_COLOR: Color = Self::FG_COLOR.mix(Self::BG_COLOR, 60);
-
-    const DISABLED_FG_COLOR: Color = Self::FG_COLOR.mix(Self::BG_COLOR, 50);
-    const DISABLED_BG_COLOR: Color = Self::BG_COLOR.mix(Self::BASE_COLOR, 60);
-    const DISABLED_BORDERS_COLOR: Color = Self::BORDERS_COLOR.mix(Self::BG_COLOR, 80);
-
-    const PRESSED_BG_COLOR: Color = choose!(
+    const DISABLED_BG_COLOR: Color = contrast!(
         Self,
-        Self::SELECTED_BG_COLOR.darken(10),
-        Self::SELECTED_BG_COLOR.darken(20)
+        Self::BG_COLOR.mix(Self::BASE_COLOR, 60),
+        Self::BG_COLOR
     );
-    const PRESSED_BORDERS_COLOR: Color = choose!(
+    const DISABLED_BORDERS_COLOR: Color = contrast!(
         Self,
-        Self::SELECTED_BORDERS_COLOR.darken(20),
-        Self::SELECTED_BORDERS_COLOR.darken(10)
+        Self::BORDERS_COLOR.mix(Self::BG_COLOR, 80),
+        Self::DISABLED_FG_COLOR
     );
+
     const PRESSED_FG_COLOR: Color = choose!(
         Self,
         Self::SELECTED_FG_COLOR.darken(30),
@@ -101,6 +117,57 @@ trait ThemeType {
     );
 }
 
+/// The colors that are derived from the accent color.
+struct AccentColors {
+    selected_bg: Color,
+    selected_fg: Color,
+    selected_borders: Color,
+    link: Color,
+    pressed_bg: Color,
+    pressed_fg: Color,
+    pressed_borders: Color,
+}
+
+impl AccentColors {
+    fn new<T: ThemeType>(accent: Color) -> Self {
+        if T::IS_HIGH_CONTRAST {
+            return Self::high_contrast::<T>(accent);
+        }
+
+        let selected_bg = choose!(T, accent, accent.darken(20));
+        let selected_borders = choose!(T, selected_bg.darken(15), selected_bg.darken(30));
+
+        Self {
+            selected_bg,
+            selected_fg: T::SELECTED_FG_COLOR,
+            selected_borders,
+            link: choose!(T, selected_bg.darken(10), selected_bg.darken(20)),
+            pressed_bg: choose!(T, selected_bg.darken(10), selected_bg.darken(20)),
+            pressed_fg: T::PRESSED_FG_COLOR,
+            pressed_borders: choose!(T, selected_borders.darken(20), selected_borders.darken(10)),
+        }
+    }
+
+    /// Derive the colors for a high contrast theme.
+    ///
+    /// Any accent color can be picked, so the text on top of it is black or white, whichever
+    /// stands out more, and the borders are always the foreground color.
+    fn high_contrast<T: ThemeType>(accent: Color) -> Self {
+        let on = |bg: Color| if bg.luminance() > 0.5 { BLACK } else { WHITE };
+        let pressed_bg = choose!(T, accent.darken(80), accent.mix(T::BG_COLOR, 20));
+
+        Self {
+            selected_bg: accent,
+            selected_fg: on(accent),
+            selected_borders: T::FG_COLOR,
+            link: choose!(T, accent.darken(80), accent.mix(WHITE, 50)),
+            pressed_bg,
+            pressed_fg: on(pressed_bg),
+            pressed_borders: T::FG_COLOR,
+        }
+    }
+}
+
 struct Light;
 impl ThemeType for Light {
     const IS_LIGHT: bool = true;
@@ -111,8 +178,22 @@ impl ThemeType for Dark {
     const IS_LIGHT: bool = false;
 }
 
+struct HighContrastLight;
+impl ThemeType for HighContrastLight {
+    const IS_LIGHT: bool = true;
+    const IS_HIGH_CONTRAST: bool = true;
+}
+
+struct HighContrastDark;
+impl ThemeType for HighContrastDark {
+    const IS_LIGHT: bool = false;
+    const IS_HIGH_CONTRAST: bool = true;
+}
+
 #[inline]
-fn default_theme_inner<T: ThemeType>(theme: &mut Theme) {
+fn default_theme_inner<T: ThemeType>(theme: &mut Theme, accent: Option<Color>) {
+    let accent = AccentColors::new::<T>(accent.unwrap_or(T::ACCENT_COLOR));
+
     for widget in WIDGETS {
         for state in WIDGET_STATES {
             let props = theme.get_mut(*widget, *state);
@@ -120,18 +201,19 @@ fn default_theme_inner<T: ThemeType>(theme: &mut Theme) {
             // Set the background color.
             let bg_color = match *state {
                 WidgetState::Disabled => T::DISABLED_BG_COLOR,
-                WidgetState::Selected => T::SELECTED_BG_COLOR,
-                WidgetState::Pressed => T::PRESSED_BG_COLOR,
+                WidgetState::Selected => accent.selected_bg,
+                WidgetState::Pressed => accent.pressed_bg,
                 _ => T::BG_COLOR,
             };
 
             props.set_background(bg_color);
 
             // Set the foreground text color.
-            let fg_color = match *state {
-                WidgetState::Disabled => T::DISABLED_FG_COLOR,
-                WidgetState::Selected => T::SELECTED_FG_COLOR,
-                WidgetState::Pressed => T::PRESSED_FG_COLOR,
+            let fg_color = match (*widget, *state) {
+                (_, WidgetState::Disabled) => T::DISABLED_FG_COLOR,
+                (_, WidgetState::Selected) => accent.selected_fg,
+                (_, WidgetState::Pressed) => accent.pressed_fg,
+                (Widget::TextHyperlink, _) => accent.link,
                 _ => T::FG_COLOR,
             };
 
@@ -145,7 +227,9 @@ fn default_theme_inner<T: ThemeType>(theme: &mut Theme) {
             // Figure out if we need to set a border.
             let border_color = match *state {
                 WidgetState::Disabled => T::DISABLED_BORDERS_COLOR,
-                WidgetState::Selected => T::SELECTED_BORDERS_COLOR,
+                WidgetState::Selected => accent.selected_borders,
+                WidgetState::Pressed => accent.pressed_borders,
+                WidgetState::Focused => accent.selected_bg,
                 _ => T::BORDERS_COLOR,
             };
             let border_data = match *widget {
@@ -177,26 +261,242 @@ fn default_theme_inner<T: ThemeType>(theme: &mut Theme) {
 }
 
 pub(crate) fn default_theme(shade: ShadePreference) -> Theme {
-    let mut theme = Theme::empty(format!("Default_{:?}", shade));
-    match shade {
-        ShadePreference::Light => default_theme_inner::<Light>(&mut theme),
-        ShadePreference::Dark => default_theme_inner::<Dark>(&mut theme),
+    default_theme_with(shade, ContrastPreference::Normal, None)
+}
+
+/// Get the default theme, with the selection, link, pressed and focus colors derived from an
+/// accent color.
+///
+/// A high contrast preference selects the high contrast variant, which uses black and white
+/// for the background, text and borders.
+pub(crate) fn default_theme_with(
+    shade: ShadePreference,
+    contrast: ContrastPreference,
+    accent: Option<Color>,
+) -> Theme {
+    let mut theme = match contrast {
+        ContrastPreference::Normal => Theme::empty(format!("{}_{:?}", NAME, shade)),
+        ContrastPreference::High => Theme::empty(format!("{}_{:?}", HIGH_CONTRAST_NAME, shade)),
+    };
+
+    match (shade, contrast) {
+        (ShadePreference::Light, ContrastPreference::Normal) => {
+            default_theme_inner::<Light>(&mut theme, accent)
+        }
+        (ShadePreference::Dark, ContrastPreference::Normal) => {
+            default_theme_inner::<Dark>(&mut theme, accent)
+        }
+        (ShadePreference::Light, ContrastPreference::High) => {
+            default_theme_inner::<HighContrastLight>(&mut theme, accent)
+        }
+        (ShadePreference::Dark, ContrastPreference::High) => {
+            default_theme_inner::<HighContrastDark>(&mut theme, accent)
+        }
     }
     theme
 }
 
-#[allow(unused)]
+/// The name that the built-in theme is listed by.
+pub(crate) const NAME: &str = "Default";
+
+/// The name that the high contrast variant of the built-in theme is listed by.
+pub(crate) const HIGH_CONTRAST_NAME: &str = "Default_HighContrast";
+
+/// List the themes that are built into this crate.
+pub(crate) fn built_in_themes() -> Vec<ThemeInfo> {
+    [NAME, HIGH_CONTRAST_NAME]
+        .into_iter()
+        .map(|name| ThemeInfo::new(name, ThemeSource::BuiltIn, None, true, true))
+        .collect()
+}
+
+#[cfg(default_platform)]
+pub(super) fn available_themes_blocking(
+    _context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    Ok(built_in_themes())
+}
+
+#[cfg(default_platform)]
+pub(super) async fn available_themes(
+    _context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    Ok(built_in_themes())
+}
+
+#[cfg(default_platform)]
 pub(super) fn load_theme_blocking(
+    _context: &LoadContext,
     _name: Option<&str>,
     shade: ShadePreference,
 ) -> Result<Theme, LoadThemeError> {
     Ok(default_theme(shade))
 }
 
-#[allow(unused)]
+#[cfg(default_platform)]
 pub(super) async fn load_theme(
+    _context: &LoadContext,
     _name: Option<&str>,
     shade: ShadePreference,
 ) -> Result<Theme, LoadThemeError> {
     Ok(default_theme(shade))
 }
+
+#[cfg(default_platform)]
+pub(super) async fn watch(
+    _context: &LoadContext,
+    _name: Option<String>,
+    _shade: ShadePreference,
+) -> Result<impl Stream<Item = Result<Theme, LoadThemeError>> + Send + 'static, LoadThemeError> {
+    Ok(stream::pending())
+}
+
+#[cfg(default_platform)]
+pub(super) fn accent_color_blocking(
+    _context: &LoadContext,
+) -> Result<Option<Color>, LoadThemeError> {
+    Ok(None)
+}
+
+#[cfg(default_platform)]
+pub(super) async fn accent_color(_context: &LoadContext) -> Result<Option<Color>, LoadThemeError> {
+    Ok(None)
+}
+
+/// An icon theme, on platforms that don't have any.
+#[cfg(not(free_unix))]
+#[derive(Debug, Clone)]
+pub(crate) enum IconTheme {}
+
+#[cfg(not(free_unix))]
+impl IconTheme {
+    pub(crate) fn name(&self) -> &str {
+        match *self {}
+    }
+
+    pub(crate) fn display_name(&self) -> Option<&str> {
+        match *self {}
+    }
+
+    pub(crate) fn find_icon(&self, _icon: &str, _size: u32, _scale: u32) -> Option<PathBuf> {
+        match *self {}
+    }
+}
+
+#[cfg(not(free_unix))]
+pub(super) fn load_icon_theme_blocking(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<IconTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+#[cfg(not(free_unix))]
+pub(super) async fn load_icon_theme(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<IconTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+/// A cursor theme, on platforms that don't have any.
+#[cfg(not(free_unix))]
+#[derive(Debug, Clone)]
+pub(crate) enum CursorTheme {}
+
+#[cfg(not(free_unix))]
+impl CursorTheme {
+    pub(crate) fn name(&self) -> &str {
+        match *self {}
+    }
+
+    pub(crate) fn size(&self) -> u32 {
+        match *self {}
+    }
+
+    pub(crate) fn load_cursor(&self, _name: &str) -> Result<Option<Cursor>, LoadThemeError> {
+        match *self {}
+    }
+}
+
+#[cfg(not(free_unix))]
+pub(super) fn load_cursor_theme_blocking(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<CursorTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+#[cfg(not(free_unix))]
+pub(super) async fn load_cursor_theme(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<CursorTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+/// A sound theme, on platforms that don't have any.
+#[cfg(not(free_unix))]
+#[derive(Debug, Clone)]
+pub(crate) enum SoundTheme {}
+
+#[cfg(not(free_unix))]
+impl SoundTheme {
+    pub(crate) fn name(&self) -> &str {
+        match *self {}
+    }
+
+    pub(crate) fn display_name(&self) -> Option<&str> {
+        match *self {}
+    }
+
+    pub(crate) fn event_sounds_enabled(&self) -> bool {
+        match *self {}
+    }
+
+    pub(crate) fn find_sound(&self, _sound: &str) -> Option<PathBuf> {
+        match *self {}
+    }
+}
+
+#[cfg(not(free_unix))]
+pub(super) fn load_sound_theme_blocking(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<SoundTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+#[cfg(not(free_unix))]
+pub(super) async fn load_sound_theme(
+    _context: &LoadContext,
+    _name: Option<&str>,
+) -> Result<Option<SoundTheme>, LoadThemeError> {
+    Ok(None)
+}
+
+/// A font resolver, on platforms where we can't read the font configuration.
+#[cfg(not(free_unix))]
+#[derive(Debug, Clone)]
+pub(crate) enum FontResolver {}
+
+#[cfg(not(free_unix))]
+impl FontResolver {
+    pub(crate) fn resolve(&self, _style: &TextStyle) -> Vec<ResolvedFont> {
+        match *self {}
+    }
+}
+
+#[cfg(not(free_unix))]
+pub(super) fn load_font_resolver_blocking(
+    _context: &LoadContext,
+) -> Result<Option<FontResolver>, LoadThemeError> {
+    Ok(None)
+}
+
+#[cfg(not(free_unix))]
+pub(super) async fn load_font_resolver(
+    _context: &LoadContext,
+) -> Result<Option<FontResolver>, LoadThemeError> {
+    Ok(None)
+}
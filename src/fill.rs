@@ -18,6 +18,8 @@
 
 use crate::color::Color;
 
+use alloc::vec::Vec;
+
 /// The background fill of a widget.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -25,6 +27,9 @@ use crate::color::Color;
 pub enum Fill {
     /// This is a solid color.
     Color(Color),
+
+    /// This is an image, as the contents of its file, like a PNG or an SVG.
+    Image(Vec<u8>),
 }
 
 impl From<Color> for Fill {
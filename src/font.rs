@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Resolving generic font families to the fonts that are installed.
+
+use crate::{platform, LoadContext, LoadThemeError, TextStyle};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::path::{Path, PathBuf};
+
+/// Resolves the font family of a text style to the fonts that the system would use for it.
+///
+/// Generic families like [`FontFamily::SansSerif`] are resolved according to the system's font
+/// configuration, so that text looks the same as in other applications.
+///
+/// [`FontFamily::SansSerif`]: crate::FontFamily::SansSerif
+#[derive(Debug, Clone)]
+pub struct FontResolver {
+    inner: platform::FontResolver,
+}
+
+impl FontResolver {
+    /// Read the system's font configuration and find the fonts that are installed.
+    ///
+    /// Returns `None` on platforms where we can't read the font configuration.
+    pub async fn load() -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_font_resolver(&LoadContext::new()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Read the system's font configuration using the blocking API.
+    pub fn load_blocking() -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_font_resolver_blocking(&LoadContext::new())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Read the font configuration, as seen from the provided context.
+    pub async fn load_with_context(context: &LoadContext) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_font_resolver(context).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Read the font configuration, as seen from the provided context, using the blocking API.
+    pub fn load_blocking_with_context(
+        context: &LoadContext,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_font_resolver_blocking(context)?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Get the fonts to draw text in a style with, from the most preferred to the least.
+    ///
+    /// Later fonts are fallbacks for characters that the earlier ones don't have. For each
+    /// family, the face that is closest to the weight and slant of the style is picked.
+    pub fn resolve(&self, style: &TextStyle) -> Vec<ResolvedFont> {
+        self.inner.resolve(style)
+    }
+}
+
+/// A font that a text style resolved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedFont {
+    /// The name of the family.
+    family: String,
+
+    /// The file that the font is in.
+    path: PathBuf,
+
+    /// The index of the font in the file.
+    index: u32,
+}
+
+impl ResolvedFont {
+    /// Describe a font.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn new(family: String, path: PathBuf, index: u32) -> Self {
+        Self {
+            family,
+            path,
+            index,
+        }
+    }
+
+    /// Get the name of the font's family.
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+
+    /// Get the file that the font is in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the index of the font in its file, for font collections that hold more than one.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
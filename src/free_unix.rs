@@ -19,178 +19,743 @@
 //! Get themes for free Unix.
 //!
 //! First, we try to find the desktop environment from the `XDG_CURRENT_DESKTOP` environment
-//! variable. From there, we take one of two paths:
+//! variable. From there, we take one of a few paths:
 //!
 //! - If the desktop environment is GNOME or Gtk-like, we try to find the theme CSS file in the
 //!   typial directories. If it's there, we parse it and return the theme.
 //! - If the desktop environment is KDE, we open the theme directory and try reading the color schemes
 //!   and properties.
+//! - If there is no desktop environment, we build a theme out of the palette in `~/.Xresources`.
+//! - If the desktop environment has no settings of its own that we know of, we load the GTK theme
+//!   configured in GTK's `settings.ini`, like GTK itself does. If it doesn't configure one, we
+//!   fall back to `~/.Xresources`, since window managers like i3 set `XDG_CURRENT_DESKTOP` too.
 //!
-//! If none of the above steps work, we use `zbus` to get the desired color scheme from the
-//! `org.freedesktop.portal.Settings` service, and use that to choose between the light and dark
-//! variants of the default theme.
+//! Before that, if `GTK_THEME` names a theme like `Adwaita:dark`, we load that GTK theme and
+//! variant. If `QT_QPA_PLATFORMTHEME` names `qt5ct` or `qt6ct`, we read the palette and fonts
+//! configured through that tool instead.
+//!
+//! The GTK theme name, fonts and text scaling come from dconf, or from the
+//! `org.freedesktop.portal.Settings` service if dconf doesn't have them, like inside of a sandbox.
+//! The fonts are Pango font descriptions, which set the family, weight, style, stretch and size of
+//! the text, with the monospace font used for editors.
+//! If none of the above steps work, we use the color scheme from the portal to choose between the
+//! light and dark variants of the default theme, and the accent color from the portal to color it.
+//!
+//! To watch for changes, we listen to the portal and watch every file that the theme was loaded
+//! from, loading the theme again shortly after either reports a change.
+//!
+//! The contrast preference comes from the portal, GNOME's accessibility settings or a high contrast
+//! KDE color scheme. When it's set, the configured GTK theme is skipped in favor of the high
+//! contrast variant of the default theme, unless it's a high contrast theme itself.
+//!
+//! Icons are looked up in the icon theme from the same settings as the GTK theme, or from
+//! `kdeglobals` on KDE. The cursor theme and size come from `XCURSOR_THEME` and `XCURSOR_SIZE`
+//! if they're set, and otherwise from those settings or from `kcminputrc` on KDE. Sounds are looked
+//! up in the sound theme from the sound settings next to them in dconf, the portal, `settings.ini`
+//! or `kdeglobals`.
+//!
+//! Generic font families are resolved by reading fontconfig's configuration and the fonts in the
+//! directories that it lists.
 
+mod context;
+mod cursor_theme;
+mod dconf;
+mod file_watch;
+mod files;
+mod fontconfig;
+mod gtk_settings;
 mod gtk_theme;
+mod gvariant;
+mod gvdb;
+mod icon_theme;
+mod index_theme;
+mod ini;
+mod kde;
+mod palette;
+mod pango_font;
+mod portal;
+mod qt_font;
+mod qtct;
+mod sfnt;
+mod sound_theme;
+mod xcursor;
+mod xdg;
+mod xresources;
+
+use crate::{
+    Color, ContrastPreference, LoadContext, LoadThemeError, ShadePreference, Theme, ThemeInfo,
+    Widget, WIDGETS, WIDGET_STATES,
+};
 
-use crate::{LoadThemeError, ShadePreference, Theme};
+use async_io::Timer;
+use file_watch::FileWatcher;
+use futures_lite::{future, stream, Stream};
+use gtk_settings::GtkSettings;
+use pango_font::PangoFont;
 
-use futures_lite::future;
+pub(super) use cursor_theme::CursorTheme;
+pub(super) use fontconfig::FontResolver;
+pub(super) use icon_theme::IconTheme;
+pub(super) use sound_theme::SoundTheme;
 
 use std::io;
+use std::time::Duration;
 
 /// Get the theme.
 pub(super) async fn load_theme(
-    mut name: Option<&str>,
-    mut shade: ShadePreference,
+    context: &LoadContext,
+    name: Option<&str>,
+    shade: ShadePreference,
 ) -> Result<Theme, LoadThemeError> {
-    let dconf_key: String;
+    // Read everything from the portal in one go, since it's our only source of settings inside of
+    // a sandbox.
+    let portal = portal::Settings::read_all(context)
+        .await
+        .unwrap_or_default();
+    context::enter(context, || load_theme_with(name, shade, &portal))
+}
 
+/// Get the theme, using settings that were already read from the portal.
+fn load_theme_with(
+    name: Option<&str>,
+    mut shade: ShadePreference,
+    portal: &portal::Settings,
+) -> Result<Theme, LoadThemeError> {
     // Take the current theme type.
-    match ThemeType::get() {
-        ThemeType::GtkTheme(key) => {
-            if name.is_none() {
-                if let Ok(key) = dconf_string(key).await {
-                    dconf_key = key;
-                    name = Some(dconf_key.as_str());
+    let theme_type = ThemeType::get();
+    let dconf = theme_type.dconf();
+    let interface = InterfaceSettings::read(&theme_type, &dconf, portal);
+    let contrast = contrast_preference(&theme_type, &dconf, portal, &interface);
+
+    // GTK's own settings can ask for the dark variant.
+    if interface.prefer_dark == Some(true) {
+        shade = ShadePreference::Dark;
+    }
+
+    // A theme forced through the environment wins over the desktop's settings, like in GTK.
+    if let (None, Some((gtk_name, variant))) = (name, gtk_theme::theme_from_env()) {
+        let gtk_theme = gtk_theme::load_theme(&gtk_name, variant.as_deref(), shade);
+        if let Some(mut gtk_theme) = gtk_theme.ok().flatten() {
+            interface.apply(&mut gtk_theme);
+            return Ok(gtk_theme);
+        }
+    }
+
+    match theme_type {
+        ThemeType::GtkTheme(_) | ThemeType::Xresources | ThemeType::None => {
+            // Regular GTK themes don't follow the contrast preference, so leave them out.
+            let gtk_theme = interface.gtk_theme.as_deref().filter(|gtk_theme| {
+                contrast == ContrastPreference::Normal || is_high_contrast_name(gtk_theme)
+            });
+
+            if let Some(name) = name.or(gtk_theme) {
+                if let Some(mut gtk_theme) = gtk_theme::load_theme(name, None, shade).ok().flatten()
+                {
+                    interface.apply(&mut gtk_theme);
+                    return Ok(gtk_theme);
                 }
             }
 
-            if let Some(name) = name {
-                if let Some(gtk_theme) = gtk_theme::load_theme(name, shade).ok().flatten() {
-                    return Ok(gtk_theme);
+            // Without a desktop, or with a window manager like i3 that names itself in
+            // `XDG_CURRENT_DESKTOP`, fall back to the resources if `settings.ini` has no GTK
+            // theme. They aren't a named theme, so only use them if no theme was asked for.
+            if matches!(theme_type, ThemeType::Xresources | ThemeType::None)
+                && name.is_none()
+                && interface.gtk_theme.is_none()
+            {
+                if let Some(x_theme) = xresources::load_theme().ok().flatten() {
+                    return Ok(x_theme);
                 }
             }
         }
 
         ThemeType::KdeTheme => {
-            // TODO
+            if let Some(kde_theme) = kde::load_theme(name).ok().flatten() {
+                return Ok(kde_theme);
+            }
         }
 
-        _ => {}
+        ThemeType::QtConfig(tool) => {
+            if let Some(qt_theme) = qtct::load_theme(tool, name).ok().flatten() {
+                return Ok(qt_theme);
+            }
+        }
+    }
+
+    // Any theme from `available_themes` can be asked for, even if it's from another desktop.
+    if let Some(name) = name {
+        if !matches!(
+            theme_type,
+            ThemeType::GtkTheme(_) | ThemeType::Xresources | ThemeType::None
+        ) {
+            if let Some(mut gtk_theme) = gtk_theme::load_theme(name, None, shade).ok().flatten() {
+                interface.apply(&mut gtk_theme);
+                return Ok(gtk_theme);
+            }
+        }
+
+        if !matches!(theme_type, ThemeType::KdeTheme) {
+            if let Some(kde_theme) = kde::load_theme(Some(name)).ok().flatten() {
+                return Ok(kde_theme);
+            }
+        }
     }
 
+    // The built-in high contrast theme can be asked for by name as well.
+    let contrast = match name {
+        Some(crate::default_theme::HIGH_CONTRAST_NAME) => ContrastPreference::High,
+        _ => contrast,
+    };
+
     // Modify the shade preference if necessary.
-    if let Some(user_shade) = user_shade_preference().await.ok().flatten() {
+    if let Some(user_shade) = portal.shade_preference() {
         shade = user_shade;
     }
 
-    // Load the default value.
-    crate::default_theme::load_theme(name, shade).await
+    // Load the default value, in the user's accent color.
+    let mut theme =
+        crate::default_theme::default_theme_with(shade, contrast, portal.accent_color());
+    interface.apply(&mut theme);
+    Ok(theme)
 }
 
 /// Get the theme in a blocking fashion.
 pub(super) fn load_theme_blocking(
+    context: &LoadContext,
     name: Option<&str>,
     shade: ShadePreference,
 ) -> Result<Theme, LoadThemeError> {
     // TODO: Only use block_on where enecessary
-    future::block_on(load_theme(name, shade))
+    future::block_on(load_theme(context, name, shade))
+}
+
+/// How long to wait for more changes before loading the theme again.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Get a stream that loads the theme again whenever the portal reports that the color scheme,
+/// accent color, contrast or GTK theme changed, or whenever one of the files that it was loaded
+/// from changes.
+pub(super) async fn watch(
+    context: &LoadContext,
+    name: Option<String>,
+    shade: ShadePreference,
+) -> Result<impl Stream<Item = Result<Theme, LoadThemeError>> + Send + 'static, LoadThemeError> {
+    let mut watch = Watch {
+        // Without a portal, the files can still change.
+        portal: portal::Watcher::new(context).await.ok(),
+        files: FileWatcher::new().map_err(LoadThemeError)?,
+        context: context.clone(),
+        name,
+        shade,
+    };
+
+    // Load the theme once to find out which files to watch.
+    watch.reload().await.ok();
+
+    Ok(stream::unfold(watch, |mut watch| async move {
+        watch.changed().await.ok()?;
+        let theme = watch.reload().await;
+        Some((theme, watch))
+    }))
+}
+
+/// The state of a stream of theme changes.
+struct Watch {
+    /// The subscription to the portal's settings, if there is a portal.
+    portal: Option<portal::Watcher>,
+
+    /// The watch on the files that the theme was loaded from.
+    files: FileWatcher,
+
+    /// The environment to load the theme from.
+    context: LoadContext,
+
+    /// The name of the theme to load.
+    name: Option<String>,
+
+    /// The preferred shade of the theme.
+    shade: ShadePreference,
+}
+
+impl Watch {
+    /// Wait until something changes, and then until nothing has changed for a little while.
+    ///
+    /// Saving a file or switching to dark mode often changes a few things in a row, which we
+    /// only want to load the theme once for.
+    async fn changed(&mut self) -> io::Result<()> {
+        self.next_change().await?;
+
+        loop {
+            let more = async {
+                self.next_change().await?;
+                io::Result::Ok(true)
+            };
+            let quiet = async {
+                Timer::after(DEBOUNCE).await;
+                Ok(false)
+            };
+
+            if !future::or(more, quiet).await? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Wait for one change from either the portal or the files.
+    async fn next_change(&mut self) -> io::Result<()> {
+        loop {
+            let Self { portal, files, .. } = self;
+
+            let portal_changed = async {
+                match portal {
+                    Some(portal) => io::Result::Ok(portal.changed().await),
+                    None => future::pending().await,
+                }
+            };
+            let file_changed = async {
+                files.changed().await?;
+                Ok(true)
+            };
+
+            // Once the portal goes away, only the files can change.
+            match future::or(portal_changed, file_changed).await? {
+                true => return Ok(()),
+                false => self.portal = None,
+            }
+        }
+    }
+
+    /// Load the theme again, and watch the files that it's loaded from now.
+    async fn reload(&mut self) -> Result<Theme, LoadThemeError> {
+        let portal = match &self.portal {
+            Some(watcher) => watcher.portal().read_all().await.unwrap_or_default(),
+            None => portal::Settings::default(),
+        };
+
+        let (theme, paths) = context::enter(&self.context, || {
+            files::track(|| load_theme_with(self.name.as_deref(), self.shade, &portal))
+        });
+        self.files.set_files(&paths).map_err(LoadThemeError)?;
+        theme
+    }
+}
+
+/// List the themes that can be loaded.
+pub(super) fn available_themes_blocking(
+    context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    context::enter(context, || {
+        let mut themes = crate::default_theme::built_in_themes();
+        themes.extend(gtk_theme::available().map_err(LoadThemeError)?);
+        themes.extend(kde::available().map_err(LoadThemeError)?);
+        Ok(themes)
+    })
+}
+
+/// List the themes that can be loaded.
+pub(super) async fn available_themes(
+    context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    let context = context.clone();
+    blocking::unblock(move || available_themes_blocking(&context)).await
+}
+
+/// Get the accent color that the user picked.
+pub(super) async fn accent_color(context: &LoadContext) -> Result<Option<Color>, LoadThemeError> {
+    let portal = portal::Settings::read_all(context)
+        .await
+        .map_err(LoadThemeError)?;
+    Ok(portal.accent_color())
+}
+
+/// Get the accent color in a blocking fashion.
+pub(super) fn accent_color_blocking(
+    context: &LoadContext,
+) -> Result<Option<Color>, LoadThemeError> {
+    future::block_on(accent_color(context))
+}
+
+/// Load an icon theme, or the one that the user picked.
+pub(super) async fn load_icon_theme(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<IconTheme>, LoadThemeError> {
+    let portal = match name {
+        Some(_) => portal::Settings::default(),
+        None => portal::Settings::read_all(context)
+            .await
+            .unwrap_or_default(),
+    };
+
+    context::enter(context, || {
+        let active = match name {
+            Some(_) => None,
+            None => active_icon_theme(&portal),
+        };
+        let name = name.or(active.as_deref()).unwrap_or(icon_theme::FALLBACK);
+
+        IconTheme::load(name).map(Some).map_err(LoadThemeError)
+    })
+}
+
+/// Load an icon theme in a blocking fashion.
+pub(super) fn load_icon_theme_blocking(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<IconTheme>, LoadThemeError> {
+    future::block_on(load_icon_theme(context, name))
+}
+
+/// Get the name of the icon theme that the user picked.
+fn active_icon_theme(portal: &portal::Settings) -> Option<String> {
+    let theme_type = ThemeType::get();
+
+    if let ThemeType::KdeTheme = theme_type {
+        if let Some(name) = kde::icon_theme().ok().flatten() {
+            return Some(name);
+        }
+    }
+
+    InterfaceSettings::read(&theme_type, &theme_type.dconf(), portal).icon_theme
+}
+
+/// Load a cursor theme, or the one that the user picked.
+pub(super) async fn load_cursor_theme(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<CursorTheme>, LoadThemeError> {
+    let portal = portal::Settings::read_all(context)
+        .await
+        .unwrap_or_default();
+
+    context::enter(context, || {
+        let (active, size) = active_cursor_theme(&portal);
+        let name = name.or(active.as_deref()).unwrap_or(cursor_theme::FALLBACK);
+
+        CursorTheme::load(name, size)
+            .map(Some)
+            .map_err(LoadThemeError)
+    })
+}
+
+/// Load a cursor theme in a blocking fashion.
+pub(super) fn load_cursor_theme_blocking(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<CursorTheme>, LoadThemeError> {
+    future::block_on(load_cursor_theme(context, name))
+}
+
+/// Get the name and size of the cursor theme that the user picked.
+fn active_cursor_theme(portal: &portal::Settings) -> (Option<String>, u32) {
+    let mut name = context::var("XCURSOR_THEME").filter(|name| !name.is_empty());
+    let mut size = context::var("XCURSOR_SIZE")
+        .and_then(|size| size.trim().parse().ok())
+        .filter(|size| *size > 0);
+
+    let theme_type = ThemeType::get();
+    if let ThemeType::KdeTheme = theme_type {
+        if let Ok((kde_name, kde_size)) = kde::cursor_theme() {
+            name = name.or(kde_name);
+            size = size.or(kde_size);
+        }
+    }
+
+    if name.is_none() || size.is_none() {
+        let interface = InterfaceSettings::read(&theme_type, &theme_type.dconf(), portal);
+        name = name.or(interface.cursor_theme);
+        size = size.or(interface.cursor_size);
+    }
+
+    (name, size.unwrap_or(cursor_theme::DEFAULT_SIZE))
+}
+
+/// Load a sound theme, or the one that the user picked.
+pub(super) async fn load_sound_theme(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<SoundTheme>, LoadThemeError> {
+    let portal = portal::Settings::read_all(context)
+        .await
+        .unwrap_or_default();
+
+    context::enter(context, || {
+        let (active, enabled) = active_sound_theme(&portal);
+        let name = name.or(active.as_deref()).unwrap_or(sound_theme::FALLBACK);
+
+        SoundTheme::load(name, enabled)
+            .map(Some)
+            .map_err(LoadThemeError)
+    })
+}
+
+/// Load a sound theme in a blocking fashion.
+pub(super) fn load_sound_theme_blocking(
+    context: &LoadContext,
+    name: Option<&str>,
+) -> Result<Option<SoundTheme>, LoadThemeError> {
+    future::block_on(load_sound_theme(context, name))
+}
+
+/// Get the name of the sound theme that the user picked, and whether they want event sounds.
+fn active_sound_theme(portal: &portal::Settings) -> (Option<String>, bool) {
+    let (mut name, mut enabled) = (None, None);
+
+    let theme_type = ThemeType::get();
+    match theme_type {
+        ThemeType::GtkTheme(path) => {
+            // The sound settings are next to the interface settings.
+            if let Some(prefix) = path.strip_suffix("interface/") {
+                let dconf = theme_type.dconf();
+                let key = |key: &str| format!("{}sound/{}", prefix, key);
+                name = dconf.string(&key("theme-name"));
+                enabled = dconf.bool(&key("event-sounds"));
+            }
+        }
+
+        ThemeType::KdeTheme => {
+            if let Ok(kde) = kde::sound_theme() {
+                (name, enabled) = kde;
+            }
+        }
+
+        ThemeType::Xresources | ThemeType::None => {
+            let gtk = GtkSettings::read().unwrap_or_default();
+            (name, enabled) = (gtk.sound_theme_name, gtk.enable_event_sounds);
+        }
+
+        _ => {}
+    }
+
+    let name = name.or_else(|| {
+        portal
+            .string(portal::SOUND, "theme-name")
+            .map(str::to_string)
+    });
+    let enabled = enabled.or_else(|| portal.bool(portal::SOUND, "event-sounds"));
+    (name, enabled.unwrap_or(true))
+}
+
+/// Read the font configuration.
+pub(super) fn load_font_resolver_blocking(
+    context: &LoadContext,
+) -> Result<Option<FontResolver>, LoadThemeError> {
+    context::enter(context, || {
+        FontResolver::load().map(Some).map_err(LoadThemeError)
+    })
+}
+
+/// Read the font configuration.
+pub(super) async fn load_font_resolver(
+    context: &LoadContext,
+) -> Result<Option<FontResolver>, LoadThemeError> {
+    // Finding the fonts reads from a lot of files, so we need to unblock it.
+    let context = context.clone();
+    blocking::unblock(move || load_font_resolver_blocking(&context)).await
+}
+
+/// Find out whether the user prefers high contrast.
+fn contrast_preference(
+    theme_type: &ThemeType,
+    dconf: &dconf::Settings,
+    portal: &portal::Settings,
+    interface: &InterfaceSettings,
+) -> ContrastPreference {
+    /// GNOME's high contrast switch.
+    const HIGH_CONTRAST_KEY: &str = "/org/gnome/desktop/a11y/interface/high-contrast";
+
+    if let Some(contrast) = portal.contrast_preference() {
+        return contrast;
+    }
+
+    let high_contrast = match theme_type {
+        ThemeType::GtkTheme(_) => {
+            dconf.bool(HIGH_CONTRAST_KEY) == Some(true)
+                || interface
+                    .gtk_theme
+                    .as_deref()
+                    .is_some_and(is_high_contrast_name)
+        }
+        ThemeType::Xresources | ThemeType::None => interface
+            .gtk_theme
+            .as_deref()
+            .is_some_and(is_high_contrast_name),
+        ThemeType::KdeTheme => kde::contrast_preference().ok().flatten().is_some(),
+        _ => false,
+    };
+
+    match high_contrast {
+        true => ContrastPreference::High,
+        false => ContrastPreference::Normal,
+    }
+}
+
+/// Tell whether the name of a theme marks it as high contrast, like `HighContrast` or
+/// `Breeze High Contrast`.
+fn is_high_contrast_name(name: &str) -> bool {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .contains("highcontrast")
 }
 
 /// The type of theme to load.
 enum ThemeType {
-    /// We are loading a GTK theme using the provided Dconf entry.
+    /// We are loading a GTK theme, using the settings under the provided dconf path.
     GtkTheme(&'static str),
 
     /// We are loading a KDE theme.
     KdeTheme,
 
-    /// We are loading neither.
+    /// We are loading the settings of the provided Qt configuration tool.
+    QtConfig(&'static str),
+
+    /// There is no desktop environment, so we are loading a GTK theme using the settings in
+    /// GTK's `settings.ini`, or building a theme out of the X resources without one.
+    Xresources,
+
+    /// We are loading a GTK theme, using the settings in GTK's `settings.ini`.
     None,
 }
 
 impl ThemeType {
     fn get() -> Self {
-        use detect_desktop_environment::DesktopEnvironment as DE;
+        // Qt configuration tools take over from the desktop environment.
+        match context::var("QT_QPA_PLATFORMTHEME")
+            .as_deref()
+            .map(str::trim)
+        {
+            Some("qt5ct") => return Self::QtConfig("qt5ct"),
+            Some("qt6ct") => return Self::QtConfig("qt6ct"),
+            _ => {}
+        }
 
-        match DE::detect() {
-            DE::Kde => Self::KdeTheme,
-            DE::Cinnamon => Self::GtkTheme("/org/cinnamon/desktop/interface/gtk-theme"),
-            DE::Gnome | DE::Unity => Self::GtkTheme("/org/gnome/desktop/interface/gtk-theme"),
-            DE::Mate => Self::GtkTheme("/org/mate/desktop/interface/gtk-theme"),
-            _ => Self::None,
+        let desktops = match context::var("XDG_CURRENT_DESKTOP") {
+            Some(desktops) if !desktops.is_empty() => desktops,
+            _ => return Self::Xresources,
+        };
+
+        // The desktop may be a list like `ubuntu:GNOME`, so go with the first one we know.
+        for desktop in desktops.split(':') {
+            match desktop {
+                "KDE" => return Self::KdeTheme,
+                "Cinnamon" | "X-Cinnamon" => {
+                    return Self::GtkTheme("/org/cinnamon/desktop/interface/")
+                }
+                "GNOME" | "Unity" => return Self::GtkTheme("/org/gnome/desktop/interface/"),
+                "MATE" => return Self::GtkTheme("/org/mate/desktop/interface/"),
+                _ => {}
+            }
+        }
+
+        Self::None
+    }
+
+    /// Open the dconf databases, if this type of theme keeps its settings in them.
+    fn dconf(&self) -> dconf::Settings {
+        match self {
+            ThemeType::GtkTheme(_) => dconf::Settings::open().unwrap_or_default(),
+            _ => dconf::Settings::default(),
         }
     }
 }
 
-/// Get the user's light/dark mode preference, if any.
-async fn user_shade_preference() -> io::Result<Option<ShadePreference>> {
-    const LIGHT_MODE: u32 = 1;
-    const DARK_MODE: u32 = 2;
+/// The interface settings of GTK-like desktops.
+#[derive(Debug, Default)]
+struct InterfaceSettings {
+    /// The name of the GTK theme.
+    gtk_theme: Option<String>,
 
-    // Open a ZBus connection, but make sure we poll the executor ourselves.
-    let conn = zbus::ConnectionBuilder::session()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-        .internal_executor(false)
-        .build()
-        .await
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-    // Poll the executor as we continue.
-    let poll_executor = {
-        let executor = conn.executor();
-        async move {
-            loop {
-                executor.tick().await;
-            }
+    /// The font for most text, like `Cantarell 11`.
+    font: Option<PangoFont>,
+
+    /// The font for monospace text.
+    monospace_font: Option<PangoFont>,
+
+    /// The factor to scale all text by.
+    text_scaling_factor: Option<f64>,
+
+    /// The name of the icon theme.
+    icon_theme: Option<String>,
+
+    /// The name of the cursor theme.
+    cursor_theme: Option<String>,
+
+    /// The size of the cursor.
+    cursor_size: Option<u32>,
+
+    /// Whether GTK is asked for the dark variant of the theme.
+    prefer_dark: Option<bool>,
+}
+
+impl InterfaceSettings {
+    /// Read the settings from dconf, falling back to the portal, and then to GTK's `settings.ini`
+    /// for desktops that have no settings of their own.
+    fn read(theme_type: &ThemeType, dconf: &dconf::Settings, portal: &portal::Settings) -> Self {
+        let dconf_path = match theme_type {
+            ThemeType::GtkTheme(path) => Some(*path),
+            _ => None,
+        };
+        let dconf_key = |key: &str| dconf_path.map(|path| format!("{}{}", path, key));
+
+        let string = |key: &str| {
+            dconf_key(key)
+                .and_then(|key| dconf.string(&key))
+                .or_else(|| portal.string(portal::INTERFACE, key).map(str::to_string))
+        };
+
+        let gtk = match theme_type {
+            ThemeType::Xresources | ThemeType::None => GtkSettings::read().unwrap_or_default(),
+            _ => GtkSettings::default(),
+        };
+
+        Self {
+            gtk_theme: string("gtk-theme").or(gtk.theme_name),
+            font: string("font-name")
+                .or(gtk.font_name)
+                .and_then(|name| PangoFont::parse(&name)),
+            monospace_font: string("monospace-font-name").and_then(|name| PangoFont::parse(&name)),
+            text_scaling_factor: dconf_key("text-scaling-factor")
+                .and_then(|key| dconf.double(&key))
+                .or_else(|| portal.double(portal::INTERFACE, "text-scaling-factor")),
+            icon_theme: string("icon-theme").or(gtk.icon_theme_name),
+            cursor_theme: string("cursor-theme").or(gtk.cursor_theme_name),
+            cursor_size: dconf_key("cursor-size")
+                .and_then(|key| dconf.int(&key))
+                .or_else(|| portal.int(portal::INTERFACE, "cursor-size"))
+                .and_then(|size| u32::try_from(size).ok())
+                .filter(|size| *size > 0)
+                .or(gtk.cursor_theme_size),
+            prefer_dark: gtk.prefer_dark,
         }
-    };
+    }
+
+    /// Apply the fonts and text scaling to a theme.
+    fn apply(&self, theme: &mut Theme) {
+        let scale = self
+            .text_scaling_factor
+            .filter(|scale| *scale > 0.0)
+            .unwrap_or(1.0) as f32;
+
+        for widget in WIDGETS {
+            let font = match widget {
+                Widget::Editor => self.monospace_font.as_ref().or(self.font.as_ref()),
+                _ => self.font.as_ref(),
+            };
+
+            for state in WIDGET_STATES {
+                let props = theme.get_mut(*widget, *state);
 
-    // On another future, get the theme info.
-    let get_theme_info = {
-        let conn = &conn;
-        async move {
-            // Call the `org.freedesktop.portal.Settings` service.
-            let reply = conn
-                .call_method(
-                    Some("org.freedesktop.portal.Desktop"),
-                    "/org/freedesktop/portal/desktop",
-                    Some("org.freedesktop.portal.Settings"),
-                    "Read",
-                    &("org.freedesktop.appearance", "color-scheme"),
-                )
-                .await
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-            // Get the value.
-            let theme = reply
-                .body::<zvariant::Value>()
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-                .downcast::<u32>()
-                .ok_or_else(|| {
-                    io::Error::new(io::ErrorKind::InvalidData, "Failed to downcast theme info")
-                })?;
-
-            // Figure out if it's light or dark mode.
-            match theme {
-                LIGHT_MODE => io::Result::Ok(Some(ShadePreference::Light)),
-                DARK_MODE => Ok(Some(ShadePreference::Dark)),
-                _ => Ok(None),
+                if let Some(mut text) = props.text_style().cloned() {
+                    if let Some(font) = font {
+                        font.apply(&mut text);
+                    }
+
+                    text.set_size(text.size() * scale);
+                    props.set_text_style(text);
+                }
             }
         }
-    };
-
-    // Zip them together.
-    future::or(get_theme_info, poll_executor).await
-}
-
-/// Get a string value from a key through `dconf`.
-async fn dconf_string(key: &str) -> io::Result<String> {
-    let mut stdout = String::from_utf8(
-        async_process::Command::new("dconf")
-            .args(["read", key])
-            .output()
-            .await?
-            .stdout,
-    )
-    .map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Failed to convert dconf output to string: {}", e),
-        )
-    })?;
-
-    stdout.retain(|c| " '".contains(c));
-
-    Ok(stdout)
+    }
 }
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Access to the environment of the load in progress.
+//!
+//! Most of the loading code runs deep below `load_theme`, so rather than passing the context all
+//! the way down, it's kept for the thread while a theme is being loaded. Outside of a load, the
+//! process's environment is used.
+
+use crate::{GtkVersion, LoadContext};
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+std::thread_local! {
+    /// The context of the load in progress on this thread.
+    static CURRENT: RefCell<Option<LoadContext>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `context` as the environment.
+pub(super) fn enter<T>(context: &LoadContext, f: impl FnOnce() -> T) -> T {
+    /// Puts the outer context back, even if `f` panics.
+    struct Restore(Option<LoadContext>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let outer = self.0.take();
+            CURRENT.with(|current| current.replace(outer));
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|current| current.replace(Some(context.clone()))));
+    f()
+}
+
+/// Run `f` with the current context.
+fn with<T>(f: impl FnOnce(&LoadContext) -> T) -> T {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(context) => f(context),
+        None => f(&LoadContext::new()),
+    })
+}
+
+/// Get an environment variable.
+pub(super) fn var(key: &str) -> Option<String> {
+    with(|context| context.var(key))
+}
+
+/// Get the version of GTK to prefer.
+pub(super) fn gtk_version() -> GtkVersion {
+    with(LoadContext::gtk_version)
+}
+
+/// Get the path that an absolute path refers to.
+pub(super) fn path(path: impl AsRef<Path>) -> PathBuf {
+    with(|context| context.path(path.as_ref()))
+}
+
+/// Resolve the symbolic links in a path, and its `.` and `..` components.
+///
+/// Links are resolved inside of the sysroot, so that absolute links don't lead out of it.
+pub(super) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    /// The most links to follow before giving up, like Linux.
+    const MAX_LINKS: usize = 40;
+
+    let sysroot = match with(|context| context.sysroot().map(Path::to_path_buf)) {
+        Some(sysroot) => sysroot,
+        None => return fs::canonicalize(path),
+    };
+
+    // The steps left to take, backwards, and the path inside of the sysroot so far.
+    let mut pending = steps(path.strip_prefix(&sysroot).unwrap_or(path));
+    let mut resolved = PathBuf::new();
+    let mut links = 0;
+
+    while let Some(step) = pending.pop() {
+        let name = match step {
+            Step::Name(name) => name,
+            Step::Parent => {
+                resolved.pop();
+                continue;
+            }
+            Step::Root => {
+                resolved = PathBuf::new();
+                continue;
+            }
+        };
+
+        let current = sysroot.join(&resolved).join(&name);
+        if !fs::symlink_metadata(&current)?.file_type().is_symlink() {
+            resolved.push(name);
+            continue;
+        }
+
+        links += 1;
+        if links > MAX_LINKS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("too many levels of symbolic links in {}", path.display()),
+            ));
+        }
+
+        pending.extend(steps(&fs::read_link(&current)?));
+    }
+
+    Ok(sysroot.join(resolved))
+}
+
+/// A step through the directory tree.
+enum Step {
+    /// Go back to the root.
+    Root,
+
+    /// Go up to the parent directory.
+    Parent,
+
+    /// Go into the entry with this name.
+    Name(OsString),
+}
+
+/// Get the steps to take to follow a path, backwards.
+fn steps(path: &Path) -> Vec<Step> {
+    path.components()
+        .rev()
+        .filter_map(|component| match component {
+            Component::RootDir => Some(Step::Root),
+            Component::ParentDir => Some(Step::Parent),
+            Component::Normal(name) => Some(Step::Name(name.to_os_string())),
+            Component::CurDir | Component::Prefix(_) => None,
+        })
+        .collect()
+}
+
+/// Get the user's home directory.
+pub(super) fn home_dir() -> Option<PathBuf> {
+    with(|context| {
+        let home = match context.inherits_env() {
+            true => dirs::home_dir(),
+            false => context.var("HOME").map(PathBuf::from),
+        };
+
+        home.map(|home| context.path(&home))
+    })
+}
+
+/// Get a context that reads out of a fixture in `tests/fixtures`, with the given environment
+/// variables.
+#[cfg(test)]
+pub(super) fn fixture_context(fixture: &str, env: &[(&str, &str)]) -> LoadContext {
+    let mut context = LoadContext::new();
+    context
+        .set_env(env.iter().copied().chain([("HOME", "/home/user")]))
+        .set_sysroot(
+            [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture]
+                .iter()
+                .collect::<PathBuf>(),
+        );
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enter_unwinds() {
+        let mut outer = LoadContext::new();
+        outer.set_env([("NAME", "outer")]);
+        let mut inner = LoadContext::new();
+        inner.set_env([("NAME", "inner")]);
+
+        enter(&outer, || {
+            let result = std::panic::catch_unwind(|| {
+                enter(&inner, || {
+                    assert_eq!(var("NAME").as_deref(), Some("inner"));
+                    panic!("loading failed");
+                })
+            });
+            assert!(result.is_err());
+
+            // The panic didn't leave the inner context behind.
+            assert_eq!(var("NAME").as_deref(), Some("outer"));
+        });
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Cursor themes, in the layout that libXcursor reads.
+//!
+//! A cursor theme is a directory with a `cursors` subdirectory in any of the icon theme base
+//! directories, and may inherit cursors from other themes through its `index.theme`. Cursors are
+//! Xcursor files named after the cursor.
+
+use super::index_theme::IndexTheme;
+use super::{context, files, icon_theme, xcursor};
+use crate::{Cursor, LoadThemeError};
+
+use std::io;
+use std::path::PathBuf;
+
+/// The theme that every cursor theme falls back to.
+pub(super) const FALLBACK: &str = "default";
+
+/// The size of cursors if the user didn't pick one.
+pub(super) const DEFAULT_SIZE: u32 = 24;
+
+/// Groups of cursor names that mean the same thing, with the CSS name first.
+///
+/// Themes that predate the CSS names only ship the X11 names.
+const ALIASES: &[&[&str]] = &[
+    &["default", "left_ptr", "arrow", "top_left_arrow"],
+    &["pointer", "hand2", "hand1", "hand", "pointing_hand"],
+    &["text", "xterm", "ibeam"],
+    &["vertical-text", "vertical_text"],
+    &["wait", "watch"],
+    &["progress", "left_ptr_watch", "half-busy"],
+    &["help", "question_arrow", "whats_this", "left_ptr_help"],
+    &["context-menu", "context_menu"],
+    &["crosshair", "cross", "tcross"],
+    &["cell", "plus"],
+    &["move", "fleur"],
+    &["all-scroll", "fleur"],
+    &["grab", "openhand"],
+    &["grabbing", "closedhand", "fleur"],
+    &["not-allowed", "crossed_circle", "forbidden"],
+    &["no-drop", "circle", "forbidden"],
+    &["copy", "dnd-copy"],
+    &["alias", "dnd-link", "link"],
+    &[
+        "col-resize",
+        "sb_h_double_arrow",
+        "split_h",
+        "h_double_arrow",
+    ],
+    &[
+        "row-resize",
+        "sb_v_double_arrow",
+        "split_v",
+        "v_double_arrow",
+    ],
+    &[
+        "ew-resize",
+        "sb_h_double_arrow",
+        "h_double_arrow",
+        "size_hor",
+    ],
+    &[
+        "ns-resize",
+        "sb_v_double_arrow",
+        "v_double_arrow",
+        "size_ver",
+    ],
+    &["nesw-resize", "fd_double_arrow", "size_bdiag"],
+    &["nwse-resize", "bd_double_arrow", "size_fdiag"],
+    &["n-resize", "top_side"],
+    &["s-resize", "bottom_side"],
+    &["e-resize", "right_side"],
+    &["w-resize", "left_side"],
+    &["ne-resize", "top_right_corner"],
+    &["nw-resize", "top_left_corner"],
+    &["se-resize", "bottom_right_corner"],
+    &["sw-resize", "bottom_left_corner"],
+    &["zoom-in", "zoom_in"],
+    &["zoom-out", "zoom_out"],
+];
+
+/// A cursor theme, along with every theme that it inherits from.
+#[derive(Debug, Clone)]
+pub(crate) struct CursorTheme {
+    /// The name of the theme.
+    name: String,
+
+    /// The size of cursors that the user picked.
+    size: u32,
+
+    /// The `cursors` directories to look cursors up in, in order.
+    dirs: Vec<PathBuf>,
+}
+
+impl CursorTheme {
+    /// Load a cursor theme by its name.
+    pub(super) fn load(name: &str, size: u32) -> io::Result<Self> {
+        let base_dirs = base_dirs();
+        let mut themes = Vec::new();
+        let mut dirs = Vec::new();
+
+        add_theme(name, &base_dirs, &mut themes, &mut dirs)?;
+        add_theme(FALLBACK, &base_dirs, &mut themes, &mut dirs)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            size,
+            dirs,
+        })
+    }
+
+    /// Get the name of the theme.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the size of cursors that the user picked.
+    pub(crate) fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Load a cursor by its name, trying the names that it's also known by.
+    pub(crate) fn load_cursor(&self, name: &str) -> Result<Option<Cursor>, LoadThemeError> {
+        // Cursor names are file names; don't let them point elsewhere.
+        if name.is_empty() || name.contains('/') || name.starts_with('.') {
+            return Ok(None);
+        }
+
+        let aliases = ALIASES
+            .iter()
+            .find(|group| group.contains(&name))
+            .map_or(&[][..], |group| *group);
+        let mut names = vec![name];
+        names.extend(aliases.iter().filter(|alias| **alias != name));
+
+        for name in names {
+            for dir in &self.dirs {
+                let path = dir.join(name);
+                if files::is_file(&path) {
+                    let data = files::read(&path).map_err(LoadThemeError)?;
+                    return xcursor::parse(&data).map(Some).map_err(LoadThemeError);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Add the `cursors` directories of a theme, followed by those of the themes it inherits from.
+///
+/// Themes that are already in the chain are skipped.
+fn add_theme(
+    name: &str,
+    base_dirs: &[PathBuf],
+    themes: &mut Vec<String>,
+    dirs: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    if themes.iter().any(|theme| theme == name) {
+        return Ok(());
+    }
+    themes.push(name.to_string());
+
+    let roots = base_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .filter(|root| files::is_dir(root))
+        .collect::<Vec<_>>();
+
+    dirs.extend(
+        roots
+            .iter()
+            .map(|root| root.join("cursors"))
+            .filter(|dir| files::is_dir(dir)),
+    );
+
+    // Like libXcursor, only the first index counts.
+    for root in &roots {
+        if let Some(index) = IndexTheme::open(root)? {
+            for parent in index.parents() {
+                add_theme(parent, base_dirs, themes, dirs)?;
+            }
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the directories that cursor themes are installed in.
+///
+/// `XCURSOR_PATH` replaces the icon theme directories, like in libXcursor.
+fn base_dirs() -> Vec<PathBuf> {
+    match context::var("XCURSOR_PATH") {
+        Some(path) => path
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .filter_map(|dir| match dir.strip_prefix("~/") {
+                Some(rest) => context::home_dir().map(|home| home.join(rest)),
+                None => Some(context::path(dir)),
+            })
+            .collect(),
+        None => icon_theme::base_dirs(),
+    }
+}
@@ -0,0 +1,420 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A reader for dconf databases, so that GSettings values can be read without running `dconf`.
+//!
+//! The databases to read come from the dconf profile. The user's database lives in
+//! `~/.config/dconf/user`, and system databases in `/etc/dconf/db`. They're GVDB files keyed by
+//! the full path of each setting, with a `.locks` table in system databases for the keys that
+//! users can't change. System databases are compiled from key files in a `.d` directory next to
+//! them, which we read instead if the database hasn't been compiled.
+//!
+//! Sandboxes often use GLib's key file backend rather than dconf, which we fall back to.
+
+use super::gvariant::Variant;
+use super::gvdb::GvdbFile;
+use super::ini::{optional, KeyFile};
+use super::xdg;
+use super::{context, files};
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory holding system profiles and databases.
+const SYSCONF_DIR: &str = "/etc/dconf";
+
+/// The settings in the databases of the dconf profile.
+#[derive(Default)]
+pub(super) struct Settings {
+    /// The databases, from the highest priority to the lowest.
+    databases: Vec<Database>,
+
+    /// GLib's key file backend, for the settings that dconf doesn't have.
+    keyfile: Option<Database>,
+}
+
+impl Settings {
+    /// Open the databases, so that they can be read from for the rest of the load.
+    pub(super) fn open() -> io::Result<Self> {
+        let databases = match context::var("GSETTINGS_BACKEND").as_deref() {
+            Some("keyfile") => Vec::new(),
+            _ => open_databases()?,
+        };
+
+        let keyfile = match xdg::config_home() {
+            Some(config_home) => optional(KeyFile::open(
+                &config_home.join("glib-2.0/settings/keyfile"),
+            ))?,
+            None => None,
+        };
+
+        Ok(Self {
+            databases,
+            keyfile: keyfile.map(|file| Database::Text(file, Vec::new())),
+        })
+    }
+
+    /// Read a string setting by its full path, like `/org/gnome/desktop/interface/gtk-theme`.
+    pub(super) fn string(&self, key: &str) -> Option<String> {
+        self.read_with(key, |value| value.as_str())
+    }
+
+    /// Read a floating point setting by its full path.
+    pub(super) fn double(&self, key: &str) -> Option<f64> {
+        self.read_with(key, |value| value.as_f64())
+    }
+
+    /// Read an integer setting by its full path.
+    pub(super) fn int(&self, key: &str) -> Option<i32> {
+        self.read_with(key, |value| value.as_i32())
+    }
+
+    /// Read a boolean setting by its full path.
+    pub(super) fn bool(&self, key: &str) -> Option<bool> {
+        self.read_with(key, |value| value.as_bool())
+    }
+
+    /// Read a setting, converting it with `convert`.
+    fn read_with<T>(&self, key: &str, convert: impl Fn(Value<'_>) -> Option<T>) -> Option<T> {
+        // Like dconf, a lock hides the values of every database ahead of the lowest priority one
+        // that locks the key. Missing databases were left out, so the rest are still in order.
+        let start = self
+            .databases
+            .iter()
+            .rposition(|db| db.is_locked(key))
+            .unwrap_or(0);
+
+        self.databases[start..]
+            .iter()
+            .chain(&self.keyfile)
+            .find_map(|db| db.get(key).and_then(&convert))
+    }
+}
+
+/// Open the databases in the dconf profile, from the highest priority to the lowest.
+fn open_databases() -> io::Result<Vec<Database>> {
+    let mut databases = Vec::new();
+
+    for line in read_profile()?.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let (kind, name) = match line.split_once(':') {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let database = match kind {
+            "user-db" => match xdg::config_home() {
+                Some(config_home) => Database::open_user(&config_home.join("dconf").join(name))?,
+                None => None,
+            },
+            "system-db" => {
+                Database::open_system(&context::path(SYSCONF_DIR).join("db").join(name))?
+            }
+            "file-db" => Database::open_system(&context::path(name))?,
+            _ => None,
+        };
+
+        databases.extend(database);
+    }
+
+    Ok(databases)
+}
+
+/// Read the active dconf profile.
+///
+/// Without a profile, only the user's database is used.
+fn read_profile() -> io::Result<String> {
+    const DEFAULT_PROFILE: &str = "user-db:user";
+
+    let name = context::var("DCONF_PROFILE").unwrap_or_else(|| "user".to_string());
+    let paths = match Path::new(&name).is_absolute() {
+        true => vec![context::path(&name)],
+        false => Some(context::path(SYSCONF_DIR))
+            .into_iter()
+            .chain(xdg::data_dirs().map(|dir| dir.join("dconf")))
+            .map(|dir| dir.join("profile").join(&name))
+            .collect(),
+    };
+
+    for path in paths {
+        if let Some(profile) = optional(files::read_to_string(&path))? {
+            return Ok(profile);
+        }
+    }
+
+    Ok(DEFAULT_PROFILE.to_string())
+}
+
+/// A database of settings.
+enum Database {
+    /// A compiled GVDB database.
+    Binary(GvdbFile),
+
+    /// Settings in a key file, with the groups as paths and the values as `GVariant` text, along
+    /// with the keys that are locked.
+    Text(KeyFile, Vec<String>),
+}
+
+impl Database {
+    /// Open the user's database.
+    fn open_user(path: &Path) -> io::Result<Option<Self>> {
+        Ok(optional(GvdbFile::open(path))?.map(Database::Binary))
+    }
+
+    /// Open a system database, or the key files it's compiled from.
+    fn open_system(path: &Path) -> io::Result<Option<Self>> {
+        if let Some(file) = optional(GvdbFile::open(path))? {
+            return Ok(Some(Database::Binary(file)));
+        }
+
+        let mut dir = path.as_os_str().to_owned();
+        dir.push(".d");
+        let dir = PathBuf::from(dir);
+
+        let paths = match sorted_files(&dir)? {
+            Some(paths) => paths,
+            None => return Ok(None),
+        };
+
+        let mut keyfile = KeyFile::default();
+        for path in paths {
+            keyfile.merge(KeyFile::open(&path)?);
+        }
+
+        // Locked keys are listed one per line.
+        let mut locks = Vec::new();
+        for path in sorted_files(&dir.join("locks"))?.unwrap_or_default() {
+            let text = files::read_to_string(&path)?;
+            locks.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| line.starts_with('/'))
+                    .map(str::to_string),
+            );
+        }
+
+        Ok(Some(Database::Text(keyfile, locks)))
+    }
+
+    /// Get a value by its key.
+    fn get(&self, key: &str) -> Option<Value<'_>> {
+        match self {
+            Database::Binary(file) => file
+                .root()
+                .ok()?
+                .get(key)?
+                .unwrap_variant()
+                .map(Value::Binary),
+            Database::Text(file, _) => {
+                let (group, name) = key.strip_prefix('/')?.rsplit_once('/')?;
+                file.get(group, name).map(Value::Text)
+            }
+        }
+    }
+
+    /// Check whether a key is locked.
+    fn is_locked(&self, key: &str) -> bool {
+        match self {
+            Database::Binary(file) => file
+                .root()
+                .ok()
+                .and_then(|root| root.table(".locks"))
+                .is_some_and(|locks| locks.contains(key)),
+            Database::Text(_, locks) => locks.iter().any(|lock| lock == key),
+        }
+    }
+}
+
+/// List the files in a directory in order, or `None` if it doesn't exist.
+fn sorted_files(dir: &Path) -> io::Result<Option<Vec<PathBuf>>> {
+    let entries = match optional(files::read_dir(dir))? {
+        Some(entries) => entries,
+        None => return Ok(None),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+
+    paths.sort();
+    Ok(Some(paths))
+}
+
+/// A value read from a database.
+enum Value<'a> {
+    /// A serialized `GVariant`.
+    Binary(Variant<'a>),
+
+    /// A `GVariant` in its text format.
+    Text(&'a str),
+}
+
+impl Value<'_> {
+    /// Get the value of a string.
+    fn as_str(&self) -> Option<String> {
+        match self {
+            Value::Binary(variant) => variant.as_str().map(str::to_string),
+            Value::Text(text) => parse_text_string(text),
+        }
+    }
+
+    /// Get the value of a boolean.
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Binary(variant) => variant.as_bool(),
+            Value::Text(text) => match text.trim() {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            },
+        }
+    }
+
+    /// Get the value of a signed 32-bit integer.
+    fn as_i32(&self) -> Option<i32> {
+        match self {
+            Value::Binary(variant) => variant.as_i32(),
+            Value::Text(text) => {
+                let text = text.trim();
+                text.strip_prefix("int32")
+                    .unwrap_or(text)
+                    .trim()
+                    .parse()
+                    .ok()
+            }
+        }
+    }
+
+    /// Get the value of a double.
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Binary(variant) => variant.as_f64(),
+            Value::Text(text) => {
+                let text = text.trim();
+                text.strip_prefix("double")
+                    .unwrap_or(text)
+                    .trim()
+                    .parse()
+                    .ok()
+            }
+        }
+    }
+}
+
+/// Parse a string in the `GVariant` text format, like `'Adwaita'`.
+fn parse_text_string(text: &str) -> Option<String> {
+    let text = text.trim();
+    let text = text.strip_prefix("@s").unwrap_or(text).trim_start();
+
+    let mut chars = text.chars();
+    let quote = chars.next().filter(|c| *c == '\'' || *c == '"')?;
+    let mut result = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => return chars.as_str().trim().is_empty().then_some(result),
+            '\\' => match chars.next()? {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                'r' => result.push('\r'),
+                escape @ ('u' | 'U') => {
+                    let len = if escape == 'u' { 4 } else { 8 };
+                    let digits = chars.as_str().get(..len)?;
+                    result.push(char::from_u32(u32::from_str_radix(digits, 16).ok()?)?);
+                    chars = chars.as_str()[len..].chars();
+                }
+                c => result.push(c),
+            },
+            c => result.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open the settings in the GNOME fixture with a profile.
+    fn open(profile: &str) -> Settings {
+        let fixture = context::fixture_context("gnome", &[("DCONF_PROFILE", profile)]);
+        context::enter(&fixture, || Settings::open().unwrap())
+    }
+
+    const THEME: &str = "/org/gnome/desktop/interface/gtk-theme";
+    const FONT: &str = "/org/gnome/desktop/interface/font-name";
+    const SCALE: &str = "/org/gnome/desktop/interface/text-scaling-factor";
+
+    #[test]
+    fn profile() {
+        let settings = open("user");
+        assert_eq!(settings.databases.len(), 3);
+
+        // The `local` database locks the theme, which hides the user's value.
+        assert_eq!(settings.string(THEME).as_deref(), Some("Fixture"));
+
+        // Otherwise the user's database comes first, then the system ones in order.
+        assert_eq!(settings.string(FONT).as_deref(), Some("Cantarell Bold 12"));
+        assert_eq!(settings.double(SCALE), Some(1.5));
+        assert_eq!(settings.bool("/org/gnome/desktop/interface/missing"), None);
+    }
+
+    #[test]
+    fn file_db() {
+        // The missing database and the unknown kind of database are skipped.
+        let settings = open("/etc/dconf/profile/files");
+        assert_eq!(settings.databases.len(), 2);
+
+        // Without the lock, the user's value is used.
+        assert_eq!(settings.string(THEME).as_deref(), Some("Other"));
+        assert_eq!(settings.double(SCALE), Some(1.5));
+    }
+
+    #[test]
+    fn default_profile() {
+        // Without a profile, only the user's database is read.
+        let settings = open("missing");
+        assert_eq!(settings.databases.len(), 1);
+        assert_eq!(settings.string(THEME).as_deref(), Some("Other"));
+        assert_eq!(settings.double(SCALE), None);
+    }
+
+    #[test]
+    fn text_values() {
+        assert_eq!(
+            Value::Text(" 'Adwaita' ").as_str().as_deref(),
+            Some("Adwaita")
+        );
+        assert_eq!(
+            Value::Text(r#"@s "it's é\tok""#).as_str().as_deref(),
+            Some("it's \u{e9}\tok")
+        );
+        assert_eq!(Value::Text("'one' 'two'").as_str(), None);
+        assert_eq!(Value::Text("Adwaita").as_str(), None);
+
+        assert_eq!(Value::Text("true").as_bool(), Some(true));
+        assert_eq!(Value::Text("int32 -4").as_i32(), Some(-4));
+        assert_eq!(Value::Text("double 1.25").as_f64(), Some(1.25));
+        assert_eq!(Value::Text("1.25").as_i32(), None);
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Watching the files that a theme was loaded from.
+//!
+//! Rather than the files themselves, we watch the directories that they're in. Editors often save
+//! by replacing the file with a new one, which would end a watch on the old file, and files that
+//! we looked for but didn't find may be created later. If the directory doesn't exist either, we
+//! watch the nearest one that does for the next directory down. Directories that were listed are
+//! watched for any change at all, and so is the end of a watch or the loss of events when the
+//! queue overflows. Only Linux is supported for now, using inotify; elsewhere, the files never
+//! change as far as we can tell.
+
+use std::io;
+
+use super::files::Tracked;
+
+#[cfg(target_os = "linux")]
+use {
+    crate::util::HashMap,
+    async_io::Async,
+    rustix::fs::inotify,
+    rustix::io::Errno,
+    std::ffi::{OsStr, OsString},
+    std::mem::MaybeUninit,
+    std::os::fd::OwnedFd,
+    std::os::unix::ffi::OsStrExt,
+    std::path::Path,
+};
+
+/// Watches a set of files for changes.
+#[cfg(target_os = "linux")]
+pub(super) struct FileWatcher {
+    /// The inotify instance.
+    inotify: Async<OwnedFd>,
+
+    /// What is watched in each directory, by the watch on it.
+    dirs: HashMap<i32, Watched>,
+}
+
+/// What is watched in a directory.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct Watched {
+    /// The names of the watched files.
+    names: Vec<OsString>,
+
+    /// Every file in the directory is watched.
+    all: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl FileWatcher {
+    /// The events that mean that a file in a directory changed.
+    const EVENTS: inotify::WatchFlags = inotify::WatchFlags::CLOSE_WRITE
+        .union(inotify::WatchFlags::MODIFY)
+        .union(inotify::WatchFlags::CREATE)
+        .union(inotify::WatchFlags::DELETE)
+        .union(inotify::WatchFlags::MOVED_FROM)
+        .union(inotify::WatchFlags::MOVED_TO);
+
+    /// Create a watcher that doesn't watch any files yet.
+    pub(super) fn new() -> io::Result<Self> {
+        let fd = inotify::init(inotify::CreateFlags::CLOEXEC | inotify::CreateFlags::NONBLOCK)?;
+
+        Ok(Self {
+            inotify: Async::new(fd)?,
+            dirs: HashMap::default(),
+        })
+    }
+
+    /// Watch a new set of files, instead of the ones watched before.
+    pub(super) fn set_files(&mut self, tracked: &Tracked) -> io::Result<()> {
+        for (watch, _) in self.dirs.drain() {
+            // The watch is already gone if the directory was removed.
+            inotify::remove_watch(self.inotify.get_ref(), watch).ok();
+        }
+
+        for path in &tracked.files {
+            if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+                self.add(dir, Some(name))?;
+            }
+        }
+
+        for dir in &tracked.dirs {
+            self.add(dir, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Watch a file in a directory, or every file in it if `name` is `None`.
+    ///
+    /// If the directory doesn't exist, this watches the nearest directory above it that does for
+    /// the creation of the next one down.
+    fn add<'a>(&mut self, mut dir: &'a Path, mut name: Option<&'a OsStr>) -> io::Result<()> {
+        let watch = loop {
+            match inotify::add_watch(self.inotify.get_ref(), dir, Self::EVENTS) {
+                Ok(watch) => break watch,
+                Err(Errno::NOENT | Errno::NOTDIR) => match (dir.parent(), dir.file_name()) {
+                    (Some(parent), Some(dir_name)) => {
+                        name = Some(dir_name);
+                        dir = parent;
+                    }
+                    _ => return Ok(()),
+                },
+                Err(Errno::ACCESS) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        // Adding a watch on a directory that is already watched gives the same watch back.
+        let watched = self.dirs.entry(watch).or_default();
+        match name {
+            Some(name) => watched.names.push(name.to_os_string()),
+            None => watched.all = true,
+        }
+
+        Ok(())
+    }
+
+    /// Wait until one of the files changes.
+    pub(super) async fn changed(&mut self) -> io::Result<()> {
+        let mut buffer = [MaybeUninit::uninit(); 4096];
+
+        loop {
+            let mut events = inotify::Reader::new(self.inotify.get_ref(), &mut buffer);
+            let mut changed = false;
+
+            // Go through every event that is ready, so that they aren't reported again.
+            loop {
+                let event = match events.next() {
+                    Ok(event) => event,
+                    Err(Errno::AGAIN) => break,
+                    Err(err) => return Err(err.into()),
+                };
+
+                // Events were lost, so any of the files may have changed.
+                if event.events().contains(inotify::ReadFlags::QUEUE_OVERFLOW) {
+                    changed = true;
+                    continue;
+                }
+
+                // A watch ends when its directory is removed, and what was in it went with it.
+                if event.events().contains(inotify::ReadFlags::IGNORED) {
+                    changed |= self.dirs.remove(&event.wd()).is_some();
+                    continue;
+                }
+
+                let (watched, name) = match (self.dirs.get(&event.wd()), event.file_name()) {
+                    (Some(watched), Some(name)) => (watched, name),
+                    _ => continue,
+                };
+
+                changed |= watched.all
+                    || watched
+                        .names
+                        .iter()
+                        .any(|n| n.as_bytes() == name.to_bytes());
+            }
+
+            if changed {
+                return Ok(());
+            }
+
+            self.inotify.readable().await?;
+        }
+    }
+}
+
+/// Watches a set of files for changes.
+#[cfg(not(target_os = "linux"))]
+pub(super) struct FileWatcher(());
+
+#[cfg(not(target_os = "linux"))]
+impl FileWatcher {
+    /// Create a watcher that doesn't watch any files yet.
+    pub(super) fn new() -> io::Result<Self> {
+        Ok(Self(()))
+    }
+
+    /// Watch a new set of files, instead of the ones watched before.
+    pub(super) fn set_files(&mut self, _tracked: &Tracked) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Wait until one of the files changes, which is never.
+    pub(super) async fn changed(&mut self) -> io::Result<()> {
+        futures_lite::future::pending().await
+    }
+}
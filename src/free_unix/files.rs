@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Reading files while keeping track of which ones were read.
+//!
+//! Every file that a theme is loaded from goes through here, so that the theme can be loaded again
+//! once one of them changes. Files that we looked for but that weren't there are tracked as well,
+//! since creating them changes the theme too, so checks for whether a file or directory exists go
+//! through here as well. Directories that were listed are tracked apart from the files, since any
+//! file added to them or removed from them changes the theme.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+std::thread_local! {
+    /// The paths read on this thread since tracking started, if it did.
+    static TRACKED: RefCell<Option<Tracked>> = const { RefCell::new(None) };
+}
+
+/// The paths that were read while tracking.
+#[derive(Debug, Default)]
+pub(super) struct Tracked {
+    /// The files that were read or looked for.
+    pub(super) files: Vec<PathBuf>,
+
+    /// The directories that were listed.
+    pub(super) dirs: Vec<PathBuf>,
+}
+
+/// Run `f`, returning the paths of all of the files and directories that it read.
+pub(super) fn track<T>(f: impl FnOnce() -> T) -> (T, Tracked) {
+    let outer = TRACKED.with(|tracked| tracked.replace(Some(Tracked::default())));
+    let result = f();
+    let paths = TRACKED.with(|tracked| mem::replace(&mut *tracked.borrow_mut(), outer));
+
+    (result, paths.unwrap_or_default())
+}
+
+/// Add a path to the tracked files.
+fn record(path: &Path) {
+    record_in(path, |tracked| &mut tracked.files);
+}
+
+/// Add a path to one of the lists of tracked paths.
+fn record_in(path: &Path, list: impl FnOnce(&mut Tracked) -> &mut Vec<PathBuf>) {
+    TRACKED.with(|tracked| {
+        if let Some(tracked) = tracked.borrow_mut().as_mut() {
+            let paths = list(tracked);
+            if !paths.iter().any(|tracked| tracked == path) {
+                paths.push(path.to_path_buf());
+            }
+        }
+    });
+}
+
+/// Check whether a path is a file, tracking it either way.
+pub(super) fn is_file(path: &Path) -> bool {
+    record(path);
+    path.is_file()
+}
+
+/// Check whether a path is a directory, tracking it either way.
+pub(super) fn is_dir(path: &Path) -> bool {
+    record(path);
+    path.is_dir()
+}
+
+/// Read the contents of a file.
+pub(super) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    record(path);
+    fs::read(path)
+}
+
+/// Read the contents of a text file.
+pub(super) fn read_to_string(path: &Path) -> io::Result<String> {
+    record(path);
+    fs::read_to_string(path)
+}
+
+/// Open a file, to read parts of it.
+pub(super) fn open(path: &Path) -> io::Result<fs::File> {
+    record(path);
+    fs::File::open(path)
+}
+
+/// List the entries of a directory.
+///
+/// The directory itself is tracked as well as its entries, so that it is noticed when it's created.
+pub(super) fn read_dir(path: &Path) -> io::Result<fs::ReadDir> {
+    record(path);
+    record_in(path, |tracked| &mut tracked.dirs);
+    fs::read_dir(path)
+}
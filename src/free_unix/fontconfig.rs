@@ -0,0 +1,743 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Resolving font families the way fontconfig does, without linking to it.
+//!
+//! fontconfig's configuration starts at `fonts.conf`, which includes the files in `conf.d` and
+//! the user's configuration. The `<alias>` rules there, and `<match>` rules that only look at the
+//! family, turn a family like `sans-serif` into a list of concrete families, in order. The
+//! `<dir>` elements list the directories that fonts are installed in.
+
+use super::sfnt::{self, Face};
+use super::{context, files, xdg};
+use crate::{FontFamily, ResolvedFont, TextStyle};
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+/// The directory that fontconfig reads its configuration from by default.
+const CONFIG_DIR: &str = "/etc/fonts";
+
+/// The name of the main configuration file.
+const CONFIG_FILE: &str = "fonts.conf";
+
+/// The file extensions of the fonts that we can read.
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+
+/// How deeply to follow includes and font directories.
+const MAX_DEPTH: usize = 16;
+
+/// Resolves font families to the fonts that are installed.
+#[derive(Debug, Clone)]
+pub(crate) struct FontResolver {
+    /// The rules that edit the list of families, in order.
+    rules: Vec<Rule>,
+
+    /// The faces that are installed.
+    fonts: Vec<Font>,
+}
+
+/// An installed face.
+#[derive(Debug, Clone)]
+struct Font {
+    /// The file that the face is in.
+    path: PathBuf,
+
+    /// The names and style of the face.
+    face: Face,
+}
+
+/// A rule that edits the list of families if its tests pass.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// The tests on the list of families.
+    tests: Vec<Test>,
+
+    /// The edits to make, in order.
+    edits: Vec<Edit>,
+}
+
+/// A test on the list of families.
+#[derive(Debug, Clone)]
+struct Test {
+    /// Whether every family has to pass, rather than any of them.
+    all: bool,
+
+    /// Whether the family has to differ from the value, rather than equal it.
+    not_eq: bool,
+
+    /// The family to compare to.
+    family: String,
+}
+
+/// An edit to the list of families.
+#[derive(Debug, Clone)]
+struct Edit {
+    /// Where the families go.
+    mode: Mode,
+
+    /// The families to add.
+    families: Vec<String>,
+}
+
+/// Where an edit puts its families, relative to the family that matched the tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Replace the family that matched.
+    Assign,
+
+    /// Replace every family.
+    AssignReplace,
+
+    /// Go before the family that matched.
+    Prepend,
+
+    /// Go before every family.
+    PrependFirst,
+
+    /// Go after the family that matched.
+    Append,
+
+    /// Go after every family.
+    AppendLast,
+
+    /// Remove the family that matched.
+    Delete,
+
+    /// Remove every family.
+    DeleteAll,
+}
+
+impl FontResolver {
+    /// Read the configuration and find the fonts that it points to.
+    pub(super) fn load() -> io::Result<Self> {
+        let mut config = Config::default();
+        // `FONTCONFIG_PATH` lists directories to look in before the default one.
+        let config_dirs = context::var("FONTCONFIG_PATH")
+            .iter()
+            .flat_map(|path| path.split(':'))
+            .filter(|dir| !dir.is_empty())
+            .map(context::path)
+            .chain(Some(context::path(CONFIG_DIR)))
+            .collect::<Vec<_>>();
+        let config_file = match context::var("FONTCONFIG_FILE") {
+            Some(file) if Path::new(&file).is_absolute() => Some(context::path(file)),
+            Some(file) => find_config(&config_dirs, Path::new(&file)),
+            None => find_config(&config_dirs, Path::new(CONFIG_FILE)),
+        };
+
+        let found = match config_file {
+            Some(config_file) => config.include(&config_file, &config_dirs, 0)?,
+            None => false,
+        };
+
+        // Without a configuration, look where fontconfig is usually set up to look.
+        if !found {
+            config
+                .dirs
+                .extend(xdg::data_dirs().map(|dir| dir.join("fonts")));
+            config
+                .dirs
+                .extend(xdg::data_home().map(|dir| dir.join("fonts")));
+            config
+                .dirs
+                .extend(context::home_dir().map(|dir| dir.join(".fonts")));
+        }
+
+        let mut fonts = Vec::new();
+        let mut visited = Vec::new();
+        for dir in &config.dirs {
+            scan_dir(dir, &mut fonts, &mut visited, 0)?;
+        }
+
+        Ok(Self {
+            rules: config.rules,
+            fonts,
+        })
+    }
+
+    /// Resolve the family of a text style to the installed families, in order of preference.
+    ///
+    /// For each family, the face closest to the weight and slant of the style is taken.
+    pub(crate) fn resolve(&self, style: &TextStyle) -> Vec<ResolvedFont> {
+        let family = match style.family() {
+            FontFamily::Monospace => "monospace",
+            FontFamily::SansSerif => "sans-serif",
+            FontFamily::Serif => "serif",
+            FontFamily::Custom(family) => family.as_str(),
+        };
+
+        let mut families = vec![family.to_string()];
+        for rule in &self.rules {
+            rule.apply(&mut families);
+        }
+
+        let mut resolved: Vec<ResolvedFont> = Vec::new();
+        for family in &families {
+            let best = self
+                .fonts
+                .iter()
+                .filter_map(|font| {
+                    let name = font
+                        .face
+                        .families
+                        .iter()
+                        .find(|name| same_family(name, family))?;
+                    Some((font, name))
+                })
+                .min_by_key(|(font, _)| {
+                    let slant = u32::from(font.face.italic != style.italic()) * 1000;
+                    slant + u32::from(font.face.weight.abs_diff(style.weight()))
+                });
+
+            if let Some((font, name)) = best {
+                if !resolved
+                    .iter()
+                    .any(|known| same_family(known.family(), name))
+                {
+                    resolved.push(ResolvedFont::new(
+                        name.clone(),
+                        font.path.clone(),
+                        font.face.index,
+                    ));
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+impl Rule {
+    /// Apply the rule to a list of families.
+    fn apply(&self, families: &mut Vec<String>) {
+        // Edits happen around the first family that passes an `any` test.
+        let mut position = None;
+
+        for test in &self.tests {
+            let passes = |family: &String| same_family(family, &test.family) != test.not_eq;
+
+            if test.all {
+                if !families.iter().all(passes) {
+                    return;
+                }
+            } else {
+                match families.iter().position(passes) {
+                    Some(index) => position = position.or(Some(index)),
+                    None => return,
+                }
+            }
+        }
+
+        for edit in &self.edits {
+            let values = edit.families.iter().cloned();
+
+            match (edit.mode, position) {
+                (Mode::Assign, Some(index)) => {
+                    families.splice(index..=index, values);
+                    position = (!edit.families.is_empty()).then_some(index);
+                }
+                (Mode::Prepend, Some(index)) => {
+                    families.splice(index..index, values);
+                    position = Some(index + edit.families.len());
+                }
+                (Mode::Append, Some(index)) => {
+                    families.splice(index + 1..index + 1, values);
+                }
+                (Mode::Delete, Some(index)) => {
+                    families.remove(index);
+                    position = None;
+                }
+                (Mode::AssignReplace | Mode::Assign, _) => {
+                    *families = values.collect();
+                    position = None;
+                }
+                (Mode::PrependFirst | Mode::Prepend, _) => {
+                    families.splice(0..0, values);
+                    position = position.map(|index| index + edit.families.len());
+                }
+                (Mode::AppendLast | Mode::Append, _) => families.extend(values),
+                (Mode::DeleteAll | Mode::Delete, _) => {
+                    families.clear();
+                    position = None;
+                }
+            }
+
+            if position.is_some_and(|index| index >= families.len()) {
+                position = None;
+            }
+        }
+    }
+}
+
+/// The parts of the configuration that we use.
+#[derive(Debug, Default)]
+struct Config {
+    /// The directories that fonts are installed in.
+    dirs: Vec<PathBuf>,
+
+    /// The rules, in order.
+    rules: Vec<Rule>,
+
+    /// The files that were already read.
+    files: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Read a configuration file, or every `.conf` file in a directory, in order.
+    ///
+    /// Returns whether there was anything to read.
+    fn include(&mut self, path: &Path, config_dirs: &[PathBuf], depth: usize) -> io::Result<bool> {
+        if depth > MAX_DEPTH {
+            return Ok(false);
+        }
+
+        // Links are resolved inside of the sysroot, and files linked in twice are read once.
+        let canonical = match context::canonicalize(path) {
+            Ok(canonical) => canonical,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+
+        if files::is_dir(&canonical) {
+            let mut confs = Vec::new();
+            for entry in files::read_dir(&canonical)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "conf") {
+                    confs.push(path);
+                }
+            }
+
+            confs.sort();
+            for file in confs {
+                self.include(&file, config_dirs, depth + 1)?;
+            }
+
+            return Ok(true);
+        }
+
+        if self.files.contains(&canonical) {
+            return Ok(true);
+        }
+
+        let text = files::read_to_string(&canonical)?;
+        self.files.push(canonical);
+        let options = ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        };
+
+        // fontconfig skips files that aren't well formed, and so do we.
+        let document = match Document::parse_with_options(&text, options) {
+            Ok(document) => document,
+            Err(_) => return Ok(true),
+        };
+
+        let file_dir = path.parent().unwrap_or(path);
+        for element in document.root_element().children().filter(Node::is_element) {
+            match element.tag_name().name() {
+                "dir" => self.dirs.extend(resolve_path(element, file_dir)),
+                "include" => {
+                    // Relative includes are looked for in each configuration directory.
+                    let include = match resolve_path(element, Path::new("")) {
+                        Some(include) if include.is_relative() => {
+                            find_config(config_dirs, &include)
+                        }
+                        include => include,
+                    };
+
+                    if let Some(include) = include {
+                        self.include(&include, config_dirs, depth + 1)?;
+                    }
+                }
+                "alias" => self.rules.extend(parse_alias(element)),
+                "match" => self.rules.extend(parse_match(element)),
+                _ => {}
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Turn an `<alias>` into the rules it stands for, one for each family that it's for.
+fn parse_alias(element: Node<'_, '_>) -> Vec<Rule> {
+    let mut edits = Vec::new();
+    for (tag, mode) in [
+        ("prefer", Mode::Prepend),
+        ("accept", Mode::Append),
+        ("default", Mode::AppendLast),
+    ] {
+        for child in element.children().filter(|child| child.has_tag_name(tag)) {
+            edits.push(Edit {
+                mode,
+                families: families_in(child, "family"),
+            });
+        }
+    }
+
+    element
+        .children()
+        .filter(|child| child.has_tag_name("family"))
+        .filter_map(text)
+        .map(|family| Rule {
+            tests: vec![Test {
+                all: false,
+                not_eq: false,
+                family,
+            }],
+            edits: edits.clone(),
+        })
+        .collect()
+}
+
+/// Parse a `<match>`, if it's one that only looks at and edits the family of the pattern.
+fn parse_match(element: Node<'_, '_>) -> Option<Rule> {
+    if element.attribute("target").unwrap_or("pattern") != "pattern" {
+        return None;
+    }
+
+    let mut rule = Rule {
+        tests: Vec::new(),
+        edits: Vec::new(),
+    };
+
+    for child in element.children().filter(Node::is_element) {
+        if child.attribute("name") != Some("family") {
+            return None;
+        }
+
+        match child.tag_name().name() {
+            "test" => {
+                let all = match child.attribute("qual").unwrap_or("any") {
+                    "any" => false,
+                    "all" => true,
+                    _ => return None,
+                };
+                let not_eq = match child.attribute("compare").unwrap_or("eq") {
+                    "eq" => false,
+                    "not_eq" => true,
+                    _ => return None,
+                };
+
+                for family in families_in(child, "string") {
+                    rule.tests.push(Test {
+                        all,
+                        not_eq,
+                        family,
+                    });
+                }
+            }
+
+            "edit" => {
+                let mode = match child.attribute("mode").unwrap_or("assign") {
+                    "assign" => Mode::Assign,
+                    "assign_replace" => Mode::AssignReplace,
+                    "prepend" => Mode::Prepend,
+                    "prepend_first" => Mode::PrependFirst,
+                    "append" => Mode::Append,
+                    "append_last" => Mode::AppendLast,
+                    "delete" => Mode::Delete,
+                    "delete_all" => Mode::DeleteAll,
+                    _ => return None,
+                };
+
+                rule.edits.push(Edit {
+                    mode,
+                    families: families_in(child, "string"),
+                });
+            }
+
+            _ => return None,
+        }
+    }
+
+    Some(rule)
+}
+
+/// Get the text of the children of an element with a tag name.
+fn families_in(element: Node<'_, '_>, tag: &str) -> Vec<String> {
+    element
+        .children()
+        .filter(|child| child.has_tag_name(tag))
+        .filter_map(text)
+        .collect()
+}
+
+/// Get the trimmed text of an element, if it has any.
+fn text(element: Node<'_, '_>) -> Option<String> {
+    let text = element.text()?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Find a relative configuration file or directory in the first configuration directory that has
+/// it.
+fn find_config(config_dirs: &[PathBuf], name: &Path) -> Option<PathBuf> {
+    config_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .find(|path| context::canonicalize(path).is_ok())
+}
+
+/// Resolve the path in a `<dir>` or `<include>`.
+fn resolve_path(element: Node<'_, '_>, relative_to: &Path) -> Option<PathBuf> {
+    let path = text(element)?;
+
+    if element.attribute("prefix") == Some("xdg") {
+        let base = match element.tag_name().name() {
+            "dir" => xdg::data_home(),
+            _ => xdg::config_home(),
+        };
+        return Some(base?.join(path));
+    }
+
+    if let Some(rest) = path.strip_prefix('~') {
+        return Some(context::home_dir()?.join(rest.trim_start_matches('/')));
+    }
+
+    match Path::new(&path).is_absolute() {
+        true => Some(context::path(path)),
+        false => Some(relative_to.join(path)),
+    }
+}
+
+/// Find the fonts in a directory and the directories in it.
+fn scan_dir(
+    dir: &Path,
+    fonts: &mut Vec<Font>,
+    visited: &mut Vec<PathBuf>,
+    depth: usize,
+) -> io::Result<()> {
+    if depth > MAX_DEPTH {
+        return Ok(());
+    }
+
+    let skip = |err: io::Error| match err.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => Ok(()),
+        _ => Err(err),
+    };
+
+    // Directories can be linked into each other, or listed twice.
+    let dir = match context::canonicalize(dir) {
+        Ok(dir) => dir,
+        Err(err) => return skip(err),
+    };
+    if visited.contains(&dir) {
+        return Ok(());
+    }
+
+    let entries = match files::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) => return skip(err),
+    };
+    visited.push(dir);
+
+    let mut entries = entries.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.path());
+
+    for entry in entries {
+        // Follow links inside of the sysroot, skipping the broken ones.
+        let path = match entry.file_type()?.is_symlink() {
+            true => match context::canonicalize(&entry.path()) {
+                Ok(path) => path,
+                Err(_) => continue,
+            },
+            false => entry.path(),
+        };
+
+        if files::is_dir(&path) {
+            scan_dir(&path, fonts, visited, depth + 1)?;
+            continue;
+        }
+
+        let is_font = path.extension().is_some_and(|ext| {
+            let ext = ext.to_string_lossy().to_ascii_lowercase();
+            FONT_EXTENSIONS.contains(&ext.as_str())
+        });
+
+        // A broken font shouldn't keep the others from loading.
+        if is_font {
+            if let Ok(faces) = sfnt::read_faces(&path) {
+                fonts.extend(faces.into_iter().map(|face| Font {
+                    path: path.clone(),
+                    face,
+                }));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare family names like fontconfig does, ignoring case and spaces.
+fn same_family(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse the rules in a configuration file.
+    fn rules(xml: &str) -> Vec<Rule> {
+        let document = Document::parse(xml).unwrap();
+        let mut rules = Vec::new();
+
+        for element in document.root_element().children().filter(Node::is_element) {
+            match element.tag_name().name() {
+                "alias" => rules.extend(parse_alias(element)),
+                "match" => rules.extend(parse_match(element)),
+                _ => {}
+            }
+        }
+
+        rules
+    }
+
+    /// Apply rules to a list of families.
+    fn apply(rules: &[Rule], families: &[&str]) -> Vec<String> {
+        let mut families = families.iter().map(|f| f.to_string()).collect();
+        for rule in rules {
+            rule.apply(&mut families);
+        }
+        families
+    }
+
+    #[test]
+    fn alias() {
+        let rules = rules(
+            "<fontconfig><alias>
+                <family>sans-serif</family>
+                <default><family>D</family></default>
+                <accept><family>C</family></accept>
+                <prefer><family>A</family><family>B</family></prefer>
+            </alias></fontconfig>",
+        );
+
+        // Preferred families go before the alias, accepted ones after it and defaults last.
+        assert_eq!(
+            apply(&rules, &["sans-serif", "Other"]),
+            ["A", "B", "sans-serif", "C", "Other", "D"]
+        );
+
+        // Families are compared without case and spaces.
+        assert_eq!(
+            apply(&rules, &["Sans- Serif"]),
+            ["A", "B", "Sans- Serif", "C", "D"]
+        );
+        assert_eq!(apply(&rules, &["serif"]), ["serif"]);
+    }
+
+    #[test]
+    fn edit_modes() {
+        let edit = |mode: &str| {
+            let rules = rules(&format!(
+                "<fontconfig><match target=\"pattern\">
+                    <test name=\"family\"><string>b</string></test>
+                    <edit name=\"family\" mode=\"{}\"><string>x</string></edit>
+                </match></fontconfig>",
+                mode
+            ));
+            apply(&rules, &["a", "b", "c"])
+        };
+
+        assert_eq!(edit("assign"), ["a", "x", "c"]);
+        assert_eq!(edit("assign_replace"), ["x"]);
+        assert_eq!(edit("prepend"), ["a", "x", "b", "c"]);
+        assert_eq!(edit("prepend_first"), ["x", "a", "b", "c"]);
+        assert_eq!(edit("append"), ["a", "b", "x", "c"]);
+        assert_eq!(edit("append_last"), ["a", "b", "c", "x"]);
+        assert_eq!(edit("delete"), ["a", "c"]);
+        assert_eq!(edit("delete_all"), Vec::<String>::new());
+
+        // Rules that test or edit anything other than the family are skipped.
+        assert_eq!(edit("unknown"), ["a", "b", "c"]);
+        let rules = rules(
+            "<fontconfig><match target=\"font\">
+                <edit name=\"family\"><string>x</string></edit>
+            </match><match>
+                <test name=\"lang\"><string>ja</string></test>
+                <edit name=\"family\"><string>x</string></edit>
+            </match></fontconfig>",
+        );
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn tests() {
+        let rules = rules(
+            "<fontconfig><match>
+                <test qual=\"all\" compare=\"not_eq\" name=\"family\"><string>c</string></test>
+                <edit name=\"family\" mode=\"append\"><string>x</string></edit>
+            </match></fontconfig>",
+        );
+
+        // Without an `any` test to put them next to, edits go at either end.
+        assert_eq!(apply(&rules, &["a", "b"]), ["a", "b", "x"]);
+        assert_eq!(apply(&rules, &["a", "c"]), ["a", "c"]);
+    }
+
+    #[test]
+    fn includes() {
+        let fixture = context::fixture_context("fonts", &[]);
+
+        let config = context::enter(&fixture, || {
+            let config_dirs = [context::path(CONFIG_DIR)];
+            let mut config = Config::default();
+            let found = config
+                .include(&context::path("/etc/fonts/fonts.conf"), &config_dirs, 0)
+                .unwrap();
+            assert!(found);
+
+            // A missing file is skipped, like with `ignore_missing`.
+            let missing = context::path("/etc/fonts/missing.conf");
+            assert!(!config.include(&missing, &config_dirs, 0).unwrap());
+            config
+        });
+
+        // `prefix="xdg"` puts directories in the data home and includes in the config home.
+        let root = fixture.sysroot().unwrap();
+        assert_eq!(
+            config.dirs,
+            [
+                root.join("usr/share/fonts"),
+                root.join("home/user/.local/share/fonts"),
+            ]
+        );
+        assert_eq!(
+            config.files,
+            [
+                root.join("etc/fonts/fonts.conf"),
+                root.join("etc/fonts/conf.d/60-generic.conf"),
+                root.join("home/user/.config/fontconfig/fonts.conf"),
+            ]
+        );
+
+        // Two aliases from `conf.d` and the user's rule.
+        assert_eq!(config.rules.len(), 3);
+    }
+}
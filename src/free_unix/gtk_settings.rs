@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Reading GTK's own settings file.
+//!
+//! Without dconf or an XSETTINGS daemon, GTK reads its settings out of `settings.ini` in its
+//! configuration directory, like on tiling window managers. The system-wide files are read first,
+//! so that the user's take precedence.
+
+use super::ini::{optional, KeyFile};
+use super::xdg;
+use crate::GtkVersion;
+
+use std::io;
+
+/// The group that the settings are in.
+const GROUP: &str = "Settings";
+
+/// The settings in `settings.ini`.
+#[derive(Debug, Default)]
+pub(super) struct GtkSettings {
+    /// The name of the GTK theme.
+    pub(super) theme_name: Option<String>,
+
+    /// The font for most text, like `Cantarell 11`.
+    pub(super) font_name: Option<String>,
+
+    /// The name of the icon theme.
+    pub(super) icon_theme_name: Option<String>,
+
+    /// The name of the cursor theme.
+    pub(super) cursor_theme_name: Option<String>,
+
+    /// The size of the cursor.
+    pub(super) cursor_theme_size: Option<u32>,
+
+    /// The name of the sound theme.
+    pub(super) sound_theme_name: Option<String>,
+
+    /// Whether sounds are played for events.
+    pub(super) enable_event_sounds: Option<bool>,
+
+    /// Whether the dark variant of the theme is preferred.
+    pub(super) prefer_dark: Option<bool>,
+}
+
+impl GtkSettings {
+    /// Read the settings of the preferred GTK version, falling back to the other one.
+    pub(super) fn read() -> io::Result<Self> {
+        let preferred = GtkVersion::preferred();
+        let mut settings = KeyFile::default();
+        let config_dirs = xdg::config_dirs().collect::<Vec<_>>();
+
+        for dir in config_dirs.into_iter().rev().chain(xdg::config_home()) {
+            for version in [preferred.other(), preferred] {
+                let path = dir.join(version.dir_name()).join("settings.ini");
+
+                if let Some(file) = optional(KeyFile::open(&path))? {
+                    settings.merge(file);
+                }
+            }
+        }
+
+        let string = |key: &str| {
+            settings
+                .get(GROUP, key)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        Ok(Self {
+            theme_name: string("gtk-theme-name"),
+            font_name: string("gtk-font-name"),
+            icon_theme_name: string("gtk-icon-theme-name"),
+            cursor_theme_name: string("gtk-cursor-theme-name"),
+            cursor_theme_size: settings
+                .get(GROUP, "gtk-cursor-theme-size")
+                .and_then(|size| size.trim().parse().ok())
+                .filter(|size| *size > 0),
+            sound_theme_name: string("gtk-sound-theme-name"),
+            enable_event_sounds: settings
+                .get(GROUP, "gtk-enable-event-sounds")
+                .and_then(parse_bool),
+            prefer_dark: settings
+                .get(GROUP, "gtk-application-prefer-dark-theme")
+                .and_then(parse_bool),
+        })
+    }
+}
+
+/// Parse a boolean the way `GKeyFile` does, which also takes the numbers that GTK 2 wrote.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -18,63 +18,217 @@
 
 //! Code for loading a GTK theme.
 
-use crate::ShadePreference;
-use crate::{LoadThemeError, Theme};
+mod color;
+mod css;
+mod gresource;
+
+use super::index_theme::IndexTheme;
+use super::ini::optional;
+use super::{context, files, xdg};
+use crate::{GtkVersion, ShadePreference};
+use crate::{LoadThemeError, Theme, ThemeInfo, ThemeSource};
 
-use std::env;
-use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
-use simplecss::StyleSheet;
-use tinyvec::array_vec;
+use color::ColorTable;
+use css::StyleSheet;
+use gresource::ResourceBundle;
 
 /// Load a GTK theme by its name.
 ///
-/// Only supports CSS themes for now.
+/// The stylesheet of the variant is preferred if there is one, like `gtk-dark.css` for `dark`.
+/// Without a variant, the dark one is preferred for the dark shade. Only supports CSS themes for
+/// now.
 pub(super) fn load_theme(
     name: &str,
+    variant: Option<&str>,
     shade: ShadePreference,
 ) -> Result<Option<Theme>, LoadThemeError> {
-    // Try the user data directory first.
-    if let Some(mut user_data) = user_data_dir() {
-        user_data.push("themes");
-        if let Some(theme) = load_from_dir(&user_data, name, shade)? {
-            return Ok(Some(theme));
+    let shade = match variant {
+        Some("dark") => ShadePreference::Dark,
+        _ => shade,
+    };
+    let variant = variant.or(match shade {
+        ShadePreference::Dark => Some("dark"),
+        ShadePreference::Light => None,
+    });
+
+    // Like GTK, fall back to the main stylesheet if the variant doesn't have one.
+    let variant_file = variant.map(|variant| format!("gtk-{}.css", variant));
+    let stylesheets = variant_file
+        .iter()
+        .map(String::as_str)
+        .chain(Some("gtk.css"))
+        .collect::<Vec<_>>();
+
+    load_named(name, &stylesheets, shade, Versions::Any, &mut Vec::new())
+}
+
+/// Which versions of GTK to take stylesheets for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Versions {
+    /// Only the preferred version.
+    Preferred,
+
+    /// Any version, starting with the preferred one.
+    Any,
+}
+
+/// Load a theme from the first directory that has it, keeping track of the themes that we're
+/// already loading to avoid inheritance cycles.
+fn load_named(
+    name: &str,
+    stylesheets: &[&str],
+    shade: ShadePreference,
+    versions: Versions,
+    stack: &mut Vec<String>,
+) -> Result<Option<Theme>, LoadThemeError> {
+    if stack.iter().any(|loading| loading == name) {
+        return Ok(None);
+    }
+
+    stack.push(name.to_string());
+    let mut result = Ok(None);
+    for dir in theme_dirs() {
+        result = load_from_dir(&dir.join(name), name, stylesheets, shade, versions, stack);
+        if !matches!(result, Ok(None)) {
+            break;
         }
     }
+    stack.pop();
+
+    result
+}
+
+/// Get the directories that themes are installed in, from the highest precedence to the lowest.
+fn theme_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    // Try the user data directory first.
+    if let Some(user_data) = xdg::data_home() {
+        dirs.push(user_data.join("themes"));
+    }
 
     // Try the home directory.
-    if let Some(mut home_dir) = dirs::home_dir() {
-        home_dir.push(".themes");
-        if let Some(theme) = load_from_dir(&home_dir, name, shade)? {
-            return Ok(Some(theme));
-        }
+    if let Some(home_dir) = context::home_dir() {
+        dirs.push(home_dir.join(".themes"));
     }
 
     // Try the data directories.
-    for data_dir in data_dirs() {
-        if let Some(theme) = load_from_dir(&data_dir, name, shade)? {
-            return Ok(Some(theme));
+    dirs.extend(xdg::data_dirs().map(|data_dir| data_dir.join("themes")));
+
+    // Try the GTK data prefix.
+    if let Some(gtk_prefix) = context::var("GTK_DATA_PREFIX") {
+        dirs.push(context::path(gtk_prefix).join("share/themes"));
+    }
+
+    dirs
+}
+
+/// List the GTK themes that are installed.
+///
+/// A theme that is installed in more than one place is listed once, for the directory that it
+/// would be loaded from.
+pub(super) fn available() -> io::Result<Vec<ThemeInfo>> {
+    let mut themes: Vec<ThemeInfo> = Vec::new();
+
+    for dir in theme_dirs() {
+        let entries = match optional(files::read_dir(&dir))? {
+            Some(entries) => entries,
+            None => continue,
+        };
+
+        for entry in entries {
+            let theme_dir = entry?.path();
+            let name = match theme_dir.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            if themes.iter().any(|theme| theme.name() == name) {
+                continue;
+            }
+
+            // Look for stylesheets in any of the GTK directories.
+            let (mut light, mut dark) = (false, false);
+            for version_dir in optional(files::read_dir(&theme_dir))?.into_iter().flatten() {
+                let version_dir = version_dir?.path();
+                let is_gtk_dir = version_dir
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("gtk-"));
+
+                if is_gtk_dir {
+                    light |= files::is_file(&version_dir.join("gtk.css"));
+                    dark |= files::is_file(&version_dir.join("gtk-dark.css"));
+                }
+            }
+
+            if light || dark {
+                themes.push(ThemeInfo::new(
+                    name,
+                    ThemeSource::Gtk,
+                    Some(theme_dir),
+                    light,
+                    dark,
+                ));
+            }
         }
     }
 
-    // Try the GTK data prefix.
-    if let Some(mut gtk_prefix) = env::var_os("GTK_DATA_PREFIX") {
-        gtk_prefix.push("/share/themes");
-        if let Some(theme) = load_from_dir(gtk_prefix.as_ref(), name, shade)? {
-            return Ok(Some(theme));
+    themes.sort_by(|a, b| a.name().cmp(b.name()));
+    Ok(themes)
+}
+
+/// Get the theme forced by the `GTK_THEME` environment variable, like `Adwaita:dark`.
+///
+/// Returns the name of the theme and the variant, if any.
+pub(super) fn theme_from_env() -> Option<(String, Option<String>)> {
+    let value = context::var("GTK_THEME")?;
+    let (name, variant) = match value.split_once(':') {
+        Some((name, variant)) => (name, Some(variant.trim())),
+        None => (value.as_str(), None),
+    };
+
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let variant = variant.filter(|variant| !variant.is_empty());
+    Some((name.to_string(), variant.map(str::to_string)))
+}
+
+impl GtkVersion {
+    /// Get the version to prefer when a theme supports both, as set on the load context.
+    pub(super) fn preferred() -> Self {
+        context::gtk_version()
+    }
+
+    /// Get the name of the directory containing the stylesheets for this version.
+    pub(super) fn dir_name(self) -> &'static str {
+        match self {
+            Self::Gtk3 => "gtk-3.0",
+            Self::Gtk4 => "gtk-4.0",
         }
     }
 
-    // Out of places to look.
-    Ok(None)
+    /// Get the other version.
+    pub(super) fn other(self) -> Self {
+        match self {
+            Self::Gtk3 => Self::Gtk4,
+            Self::Gtk4 => Self::Gtk3,
+        }
+    }
 }
 
 fn load_from_dir(
-    dir: &Path,
+    theme_dir: &Path,
     name: &str,
+    stylesheets: &[&str],
     shade: ShadePreference,
+    versions: Versions,
+    stack: &mut Vec<String>,
 ) -> Result<Option<Theme>, LoadThemeError> {
     macro_rules! leap {
         ($e:expr) => {{
@@ -89,31 +243,84 @@ fn load_from_dir(
         }};
     }
 
-    let mut variants = array_vec![[&'static str; 2] => "gtk.css"];
+    if !files::is_dir(theme_dir) {
+        return Ok(None);
+    }
 
-    if matches!(shade, ShadePreference::Dark) {
-        variants.push("gtk-dark.css");
+    let index = IndexTheme::open(theme_dir).map_err(LoadThemeError)?;
+    let mut metadata = index.as_ref().map(IndexTheme::metadata).unwrap_or_default();
+
+    // Check the preferred version first, then the other one, then any other GTK directories
+    // from the newest down.
+    let preferred = GtkVersion::preferred();
+    let mut version_dirs = vec![theme_dir.join(preferred.dir_name())];
+
+    if versions == Versions::Any {
+        version_dirs.push(theme_dir.join(preferred.other().dir_name()));
+
+        let mut others = Vec::new();
+        for entry in leap!(files::read_dir(theme_dir)) {
+            let entry = leap!(entry);
+            let path = entry.path();
+
+            if leap!(entry.file_type()).is_dir()
+                && entry.file_name().to_string_lossy().starts_with("gtk-")
+                && !version_dirs.contains(&path)
+            {
+                others.push(path);
+            }
+        }
+
+        others.sort_unstable_by(|a, b| b.cmp(a));
+        version_dirs.extend(others);
     }
 
-    // Create a directory from the name and dir.
-    let theme_dir = dir.join(name);
+    for version_dir in version_dirs {
+        // Check if it contains one of our stylesheets.
+        for stylesheet in stylesheets {
+            let stylesheet_path = version_dir.join(stylesheet);
 
-    // List the directories in this directory.
-    let list_dir = leap!(fs::read_dir(theme_dir));
+            if files::is_file(&stylesheet_path) {
+                // The stylesheet may import resources out of a bundle next to it.
+                let bundle_path = version_dir.join("gtk.gresource");
+                let bundle = match files::is_file(&bundle_path) {
+                    true => Some(leap!(ResourceBundle::open(&bundle_path))),
+                    false => None,
+                };
 
-    for dir in list_dir {
-        let dir = leap!(dir);
-        let path = dir.path();
+                let sources = leap!(read_with_imports(
+                    Location::File(stylesheet_path),
+                    bundle.as_ref()
+                ));
+                // Colors are mixed differently depending on the version of GTK.
+                let version = match version_dir.ends_with(GtkVersion::Gtk4.dir_name()) {
+                    true => GtkVersion::Gtk4,
+                    false => GtkVersion::Gtk3,
+                };
 
-        if leap!(dir.file_type()).is_dir() {
-            // Check if it contains one of our variants.
-            for variant in variants {
-                let variant_path = path.join(variant);
+                let mut theme = load_css(name, shade, &sources, bundle.as_ref(), version)?;
+                // Keep the problems found in the stylesheet.
+                metadata.set_diagnostics(theme.metadata().diagnostics().to_vec());
+                *theme.metadata_mut() = metadata;
+                return Ok(Some(theme));
+            }
+        }
+    }
 
-                if let Ok(file) = fs::File::open(&variant_path) {
-                    let file = io::BufReader::new(file);
-                    return Ok(Some(load_css(name, shade, file)?));
-                }
+    // Without a stylesheet of its own, the theme takes one from its parents. A parent with a
+    // stylesheet for the preferred version of GTK beats one for another version.
+    let passes: &[Versions] = match versions {
+        Versions::Preferred => &[Versions::Preferred],
+        Versions::Any => &[Versions::Preferred, Versions::Any],
+    };
+    for versions in passes {
+        for parent in index.iter().flat_map(IndexTheme::parents) {
+            if let Some(mut theme) = load_named(parent, stylesheets, shade, *versions, stack)? {
+                metadata.inherit(theme.metadata());
+
+                theme.set_name(name);
+                *theme.metadata_mut() = metadata;
+                return Ok(Some(theme));
             }
         }
     }
@@ -121,37 +328,192 @@ fn load_from_dir(
     Ok(None)
 }
 
-fn load_css<IO: io::BufRead>(
+/// Where a stylesheet is read from.
+#[derive(Debug, Clone, PartialEq)]
+enum Location {
+    /// A file on disk.
+    File(PathBuf),
+
+    /// A file in the theme's resource bundle, by its absolute resource path.
+    Resource(String),
+}
+
+impl Location {
+    /// Resolve an import relative to this location.
+    ///
+    /// Returns `None` for URLs that we can't load from.
+    fn resolve(&self, import: &str) -> Option<Location> {
+        if let Some(path) = import.strip_prefix("resource://") {
+            return Some(Location::Resource(gresource::resolve("/", path)));
+        }
+
+        if let Some(path) = import.strip_prefix("file://") {
+            return Some(Location::File(context::path(path)));
+        }
+
+        if import.contains("://") {
+            return None;
+        }
+
+        match self {
+            Location::File(path) => Some(Location::File(path.parent()?.join(import))),
+            Location::Resource(path) => Some(Location::Resource(gresource::resolve(path, import))),
+        }
+    }
+
+    /// Read the file at this location.
+    fn read(&self, bundle: Option<&ResourceBundle>) -> io::Result<Vec<u8>> {
+        match self {
+            Location::File(path) => files::read(path),
+            Location::Resource(path) => {
+                let bundle = bundle.ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("no resource bundle to read {} from", path),
+                    )
+                })?;
+
+                bundle.read(path)
+            }
+        }
+    }
+
+    /// Read the stylesheet at this location.
+    fn read_to_string(&self, bundle: Option<&ResourceBundle>) -> io::Result<String> {
+        String::from_utf8(self.read(bundle)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Read a stylesheet, preceded by everything it imports in the order they're imported.
+fn read_with_imports(
+    location: Location,
+    bundle: Option<&ResourceBundle>,
+) -> io::Result<Vec<(Location, String)>> {
+    let mut sources = Vec::new();
+    read_imports_inner(location, bundle, &mut Vec::new(), &mut sources)?;
+    Ok(sources)
+}
+
+fn read_imports_inner(
+    location: Location,
+    bundle: Option<&ResourceBundle>,
+    stack: &mut Vec<Location>,
+    sources: &mut Vec<(Location, String)>,
+) -> io::Result<()> {
+    let location = match location {
+        Location::File(path) => Location::File(context::canonicalize(&path)?),
+        location => location,
+    };
+
+    if stack.contains(&location) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("import cycle through stylesheet {:?}", location),
+        ));
+    }
+
+    let css = location.read_to_string(bundle)?;
+    let imports = StyleSheet::parse(&css)
+        .imports()
+        .filter_map(|import| location.resolve(import))
+        .collect::<Vec<_>>();
+
+    stack.push(location.clone());
+
+    for import in imports {
+        // GTK carries on when an import is missing, and so do we.
+        match read_imports_inner(import, bundle, stack, sources) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            result => result?,
+        }
+    }
+
+    stack.pop();
+    sources.push((location, css));
+    Ok(())
+}
+
+fn load_css(
     name: &str,
     shade: ShadePreference,
-    mut file: IO,
+    sources: &[(Location, String)],
+    bundle: Option<&ResourceBundle>,
+    version: GtkVersion,
 ) -> Result<Theme, LoadThemeError> {
     let mut theme = Theme::default_theme(shade);
+    theme.set_name(name);
+
+    // Parse the CSS of every file as one stylesheet.
+    let mut sheet = StyleSheet::new();
+    for (_, css) in sources {
+        sheet.parse_more(css);
+    }
 
-    // Read in the file and parse the CSS.
-    let mut css = String::new();
-    file.read_to_string(&mut css).map_err(LoadThemeError)?;
-    let sheet = StyleSheet::parse(&css);
+    // Images are relative to the stylesheet that they're in, and may be in the bundle too.
+    let assets = |source: usize, url: &str| {
+        let (location, _) = sources.get(source)?;
+        location.resolve(url)?.read(bundle).ok()
+    };
 
-    // TODO: Read the GTK theme from the stylesheet.
+    // Resolve the symbolic colors, then apply the rules on top of the default theme.
+    let colors = ColorTable::new(sheet.color_definitions(), version);
+    let diagnostics = sheet.apply(&mut theme, &colors, &assets);
+    theme.metadata_mut().set_diagnostics(diagnostics);
 
     Ok(theme)
 }
 
-fn user_data_dir() -> Option<PathBuf> {
-    env::var_os("XDG_CONFIG_HOME").map(Into::into).or_else(|| {
-        dirs::home_dir().map(|mut p| {
-            p.push("local/.share");
-            p
-        })
-    })
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Widget, WidgetState};
+
+    #[test]
+    fn import_cycle() {
+        let fixture = context::fixture_context("gtk", &[]);
+
+        let result = context::enter(&fixture, || {
+            load_theme("Cycle", None, ShadePreference::Light)
+        });
+        let err = result.err().unwrap();
+        assert_eq!(
+            err.io_error().map(io::Error::kind),
+            Some(io::ErrorKind::InvalidData)
+        );
+    }
+
+    #[test]
+    fn color_cycle() {
+        let css = "
+            @define-color bg_color shade(@base_color, 1.1);
+            @define-color base_color @bg_color;
+            button { background-color: #333333; background-color: @bg_color; }
+            label { color: @base_color; }
+        ";
+        let sources = [(Location::Resource("/gtk.css".into()), css.to_string())];
+        let theme = load_css(
+            "Cycle",
+            ShadePreference::Light,
+            &sources,
+            None,
+            GtkVersion::Gtk3,
+        )
+        .unwrap();
 
-fn data_dirs() -> impl Iterator<Item = PathBuf> {
-    env::var("XDG_DATA_DIRS")
-        .unwrap_or_else(|_| "/usr/local/share:/usr/share".into())
-        .split(':')
-        .map(Into::into)
-        .collect::<Vec<PathBuf>>()
-        .into_iter()
+        // The declarations are dropped, and the cycle is reported once for each color used.
+        assert_eq!(
+            theme.get(Widget::Button, WidgetState::Enabled).background(),
+            Some(&crate::Fill::Color(crate::Color::new(
+                0x33, 0x33, 0x33, 0xff
+            )))
+        );
+        assert_eq!(
+            theme.metadata().diagnostics(),
+            [
+                "cycle in @define-color: @bg_color -> @base_color -> @bg_color",
+                "cycle in @define-color: @base_color -> @bg_color -> @base_color",
+            ]
+        );
+    }
 }
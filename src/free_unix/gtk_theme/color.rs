@@ -0,0 +1,517 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Parsing colors out of GTK CSS.
+
+use crate::util::HashMap;
+use crate::{Color, GtkVersion};
+
+use std::cell::RefCell;
+use std::io;
+use std::mem;
+
+/// Named colors that show up in GTK themes.
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("transparent", Color::new(0, 0, 0, 0)),
+    ("black", Color::new(0, 0, 0, 255)),
+    ("silver", Color::new(192, 192, 192, 255)),
+    ("gray", Color::new(128, 128, 128, 255)),
+    ("grey", Color::new(128, 128, 128, 255)),
+    ("white", Color::new(255, 255, 255, 255)),
+    ("maroon", Color::new(128, 0, 0, 255)),
+    ("red", Color::new(255, 0, 0, 255)),
+    ("purple", Color::new(128, 0, 128, 255)),
+    ("fuchsia", Color::new(255, 0, 255, 255)),
+    ("magenta", Color::new(255, 0, 255, 255)),
+    ("green", Color::new(0, 128, 0, 255)),
+    ("lime", Color::new(0, 255, 0, 255)),
+    ("olive", Color::new(128, 128, 0, 255)),
+    ("yellow", Color::new(255, 255, 0, 255)),
+    ("orange", Color::new(255, 165, 0, 255)),
+    ("navy", Color::new(0, 0, 128, 255)),
+    ("blue", Color::new(0, 0, 255, 255)),
+    ("teal", Color::new(0, 128, 128, 255)),
+    ("aqua", Color::new(0, 255, 255, 255)),
+    ("cyan", Color::new(0, 255, 255, 255)),
+];
+
+/// The symbolic colors defined by a theme through `@define-color`.
+pub(super) struct ColorTable<'a> {
+    /// The definitions, by name.
+    definitions: HashMap<&'a str, &'a str>,
+
+    /// The version of GTK that the stylesheet is for, which decides how colors are mixed.
+    version: GtkVersion,
+
+    /// The colors that were already resolved, so that chains of references are only followed
+    /// once.
+    ///
+    /// Colors that depend on `currentColor` are keyed on its value, and the rest on `None`.
+    resolved: RefCell<HashMap<ResolvedKey<'a>, Option<Color>>>,
+}
+
+/// The name of a resolved color, along with the value of `currentColor` if it depends on it.
+type ResolvedKey<'a> = (&'a str, Option<Color>);
+
+/// The state of resolving one color expression.
+#[derive(Default)]
+struct Resolver<'a> {
+    /// The symbolic colors being resolved, from the outermost in.
+    stack: Vec<&'a str>,
+
+    /// Whether the current symbolic color refers to `currentColor`.
+    uses_current: bool,
+
+    /// How many expressions are being evaluated, from the outermost in.
+    depth: usize,
+}
+
+/// How deeply color expressions and references may nest before we give up on them.
+///
+/// Without a limit, a long enough chain of references or nested functions would overflow the
+/// stack.
+const MAX_DEPTH: usize = 256;
+
+impl<'a> ColorTable<'a> {
+    /// Create a table from the name-value pairs of every `@define-color` rule in the theme.
+    ///
+    /// Later definitions replace earlier ones.
+    pub(super) fn new(
+        definitions: impl IntoIterator<Item = (&'a str, &'a str)>,
+        version: GtkVersion,
+    ) -> Self {
+        Self {
+            definitions: definitions.into_iter().collect(),
+            version,
+            resolved: RefCell::new(HashMap::default()),
+        }
+    }
+
+    /// Parse a color, resolving references to symbolic colors.
+    ///
+    /// `current` is the value that `currentColor` refers to. Colors that refer to themselves
+    /// through a cycle, or that nest too deeply, are errors. GTK drops the declarations that use
+    /// them, and so should the caller.
+    pub(super) fn parse(&self, value: &str, current: Color) -> io::Result<Option<Color>> {
+        self.eval(value, current, &mut Resolver::default())
+    }
+
+    /// Evaluate a color expression.
+    fn eval(
+        &self,
+        value: &str,
+        current: Color,
+        resolver: &mut Resolver<'a>,
+    ) -> io::Result<Option<Color>> {
+        if resolver.depth >= MAX_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "color expression nests too deeply",
+            ));
+        }
+
+        resolver.depth += 1;
+        let color = self.eval_inner(value, current, resolver);
+        resolver.depth -= 1;
+        color
+    }
+
+    /// Evaluate a color expression, once its depth has been counted.
+    fn eval_inner(
+        &self,
+        value: &str,
+        current: Color,
+        resolver: &mut Resolver<'a>,
+    ) -> io::Result<Option<Color>> {
+        let value = value.trim();
+
+        if let Some(name) = value.strip_prefix('@') {
+            return self.lookup(name, current, resolver);
+        }
+
+        if value.eq_ignore_ascii_case("currentColor") {
+            resolver.uses_current = true;
+            return Ok(Some(current));
+        }
+
+        let (function, args) = match split_function(value) {
+            Some(call) => call,
+            None => return Ok(parse_color(value, current)),
+        };
+
+        // Evaluate GTK's color functions.
+        let color = match (function, split_args(args).as_slice()) {
+            ("shade", [color, factor]) => self
+                .eval(color, current, resolver)?
+                .zip(parse_number(factor))
+                .map(|(color, factor)| color.shade(factor)),
+            ("lighter", [color]) => self.eval(color, current, resolver)?.map(|c| c.shade(1.3)),
+            ("darker", [color]) => self.eval(color, current, resolver)?.map(|c| c.shade(0.7)),
+            ("alpha", [color, factor]) => self
+                .eval(color, current, resolver)?
+                .zip(parse_number(factor))
+                .map(|(color, factor)| color.scale_alpha(factor)),
+            ("mix", [first, second, factor]) => {
+                let first = self.eval(first, current, resolver)?;
+                let second = self.eval(second, current, resolver)?;
+
+                // GTK 4 mixes premultiplied colors, unlike GTK 3.
+                let mix = match self.version {
+                    GtkVersion::Gtk3 => Color::mix_straight,
+                    GtkVersion::Gtk4 => Color::mix_by,
+                };
+
+                first
+                    .zip(second)
+                    .zip(parse_number(factor))
+                    .map(|((first, second), factor)| mix(first, second, factor.clamp(0.0, 1.0)))
+            }
+            _ => parse_color(value, current),
+        };
+
+        Ok(color)
+    }
+
+    /// Resolve a symbolic color by name.
+    ///
+    /// Colors that aren't defined resolve to `None`, like they do in GTK. Returns an error if the
+    /// color refers to itself.
+    fn lookup(
+        &self,
+        name: &str,
+        current: Color,
+        resolver: &mut Resolver<'a>,
+    ) -> io::Result<Option<Color>> {
+        let (name, value) = match self.definitions.get_key_value(name) {
+            Some((name, value)) => (*name, *value),
+            None => return Ok(None),
+        };
+
+        {
+            let resolved = self.resolved.borrow();
+            if let Some(color) = resolved.get(&(name, None)) {
+                return Ok(*color);
+            }
+            if let Some(color) = resolved.get(&(name, Some(current))) {
+                resolver.uses_current = true;
+                return Ok(*color);
+            }
+        }
+
+        if resolver.stack.contains(&name) {
+            let mut chain = resolver.stack.join(" -> @");
+            chain.push_str(" -> @");
+            chain.push_str(name);
+
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("cycle in @define-color: @{}", chain),
+            ));
+        }
+
+        // Find out whether this color on its own refers to `currentColor`.
+        let outer_uses_current = mem::take(&mut resolver.uses_current);
+        resolver.stack.push(name);
+        let color = self.eval(value, current, resolver)?;
+        resolver.stack.pop();
+
+        let key = (name, resolver.uses_current.then_some(current));
+        self.resolved.borrow_mut().insert(key, color);
+        resolver.uses_current |= outer_uses_current;
+
+        Ok(color)
+    }
+}
+
+/// Parse a literal CSS color.
+///
+/// `current` is the value that `currentColor` refers to.
+fn parse_color(value: &str, current: Color) -> Option<Color> {
+    let value = value.trim();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    if let Some((name, args)) = split_function(value) {
+        let args = args.split(',').map(str::trim).collect::<Vec<_>>();
+
+        return match (name, args.as_slice()) {
+            ("rgb" | "rgba", [r, g, b]) => Some(Color::new(
+                parse_channel(r)?,
+                parse_channel(g)?,
+                parse_channel(b)?,
+                255,
+            )),
+            ("rgb" | "rgba", [r, g, b, a]) => Some(Color::new(
+                parse_channel(r)?,
+                parse_channel(g)?,
+                parse_channel(b)?,
+                parse_alpha(a)?,
+            )),
+            _ => None,
+        };
+    }
+
+    if value.eq_ignore_ascii_case("currentColor") {
+        return Some(current);
+    }
+
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, color)| *color)
+}
+
+/// Split a function call like `rgb(1, 2, 3)` into its name and arguments.
+pub(super) fn split_function(value: &str) -> Option<(&str, &str)> {
+    let (name, rest) = value.split_once('(')?;
+    let args = rest.strip_suffix(')')?;
+    Some((name.trim(), args))
+}
+
+/// Split function arguments at the commas that aren't nested in other calls.
+pub(super) fn split_args(args: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    result.push(args[start..].trim());
+    result
+}
+
+/// Parse a number, which may be given as a percentage.
+fn parse_number(value: &str) -> Option<f32> {
+    match value.trim().strip_suffix('%') {
+        Some(percent) => Some(percent.trim().parse::<f32>().ok()? / 100.0),
+        None => value.trim().parse().ok(),
+    }
+}
+
+/// Parse the digits of a hex color.
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digit = |i: usize| {
+        hex.as_bytes()
+            .get(i)
+            .and_then(|b| (*b as char).to_digit(16))
+            .map(|d| d as u8)
+    };
+    let short = |i: usize| digit(i).map(|d| d * 17);
+    let long = |i: usize| Some(digit(i)? * 16 + digit(i + 1)?);
+
+    match hex.len() {
+        3 => Some(Color::new(short(0)?, short(1)?, short(2)?, 255)),
+        4 => Some(Color::new(short(0)?, short(1)?, short(2)?, short(3)?)),
+        6 => Some(Color::new(long(0)?, long(2)?, long(4)?, 255)),
+        8 => Some(Color::new(long(0)?, long(2)?, long(4)?, long(6)?)),
+        _ => None,
+    }
+}
+
+/// Parse an RGB channel, either as a number or a percentage.
+fn parse_channel(value: &str) -> Option<u8> {
+    let channel = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? * 2.55,
+        None => value.parse::<f32>().ok()?,
+    };
+
+    Some(channel.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Parse an alpha channel between zero and one.
+fn parse_alpha(value: &str) -> Option<u8> {
+    let alpha = parse_number(value)?;
+
+    Some((alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::new(255, 0, 0, 255);
+    const BLUE: Color = Color::new(0, 0, 255, 255);
+
+    #[test]
+    fn chains() {
+        let table = ColorTable::new(
+            [
+                ("base", "#ff0000"),
+                ("accent", "@base"),
+                ("button", "@accent"),
+                ("missing_ref", "@nowhere"),
+                ("shaded", "shade(@missing_ref, 0.5)"),
+            ],
+            GtkVersion::Gtk3,
+        );
+
+        assert_eq!(table.parse("@button", BLUE).unwrap(), Some(RED));
+        assert_eq!(table.parse("@nowhere", BLUE).unwrap(), None);
+        assert_eq!(table.parse("@missing_ref", BLUE).unwrap(), None);
+        assert_eq!(table.parse("@shaded", BLUE).unwrap(), None);
+    }
+
+    #[test]
+    fn redefinition() {
+        let table = ColorTable::new(
+            [("fg", "#ff0000"), ("text", "@fg"), ("fg", "#0000ff")],
+            GtkVersion::Gtk3,
+        );
+
+        assert_eq!(table.parse("@fg", RED).unwrap(), Some(BLUE));
+        assert_eq!(table.parse("@text", RED).unwrap(), Some(BLUE));
+    }
+
+    #[test]
+    fn current_color() {
+        let table = ColorTable::new(
+            [
+                ("fg", "currentColor"),
+                ("faded", "alpha(@fg, 0.5)"),
+                ("fixed", "alpha(#ff0000, 0.5)"),
+            ],
+            GtkVersion::Gtk3,
+        );
+
+        // Colors that refer to `currentColor` are resolved again for each value of it.
+        assert_eq!(
+            table.parse("@faded", RED).unwrap(),
+            Some(Color::new(255, 0, 0, 128))
+        );
+        assert_eq!(
+            table.parse("@faded", BLUE).unwrap(),
+            Some(Color::new(0, 0, 255, 128))
+        );
+        assert_eq!(
+            table.parse("@fixed", BLUE).unwrap(),
+            Some(Color::new(255, 0, 0, 128))
+        );
+    }
+
+    #[test]
+    fn mix_versions() {
+        let definitions = [
+            ("red", "rgba(255, 0, 0, 0.5)"),
+            ("mixed", "mix(@red, #0000ff, 0.5)"),
+        ];
+
+        let gtk3 = ColorTable::new(definitions, GtkVersion::Gtk3);
+        assert_eq!(
+            gtk3.parse("@mixed", RED).unwrap(),
+            Some(Color::new(128, 0, 128, 192))
+        );
+
+        let gtk4 = ColorTable::new(definitions, GtkVersion::Gtk4);
+        assert_eq!(
+            gtk4.parse("@mixed", RED).unwrap(),
+            Some(Color::new(85, 0, 170, 192))
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let table = ColorTable::new(
+            [
+                ("a", "@b"),
+                ("b", "shade(@c, 1.1)"),
+                ("c", "@a"),
+                ("d", "@d"),
+                ("fg", "#ff0000"),
+            ],
+            GtkVersion::Gtk3,
+        );
+
+        // Colors in a cycle are errors, but the others still resolve.
+        for name in ["@a", "@b", "@c", "@d"] {
+            let err = table.parse(name, BLUE).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", name);
+        }
+        assert_eq!(table.parse("@fg", BLUE).unwrap(), Some(RED));
+
+        let err = table.parse("shade(@a, 0.5)", BLUE).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "cycle in @define-color: @a -> @b -> @c -> @a"
+        );
+    }
+
+    #[test]
+    fn too_deep() {
+        // A chain of references longer than the limit.
+        let names = (0..=MAX_DEPTH)
+            .map(|i| format!("c{}", i))
+            .collect::<Vec<_>>();
+        let values = (0..MAX_DEPTH)
+            .map(|i| format!("@c{}", i + 1))
+            .chain(Some("#ff0000".to_string()))
+            .collect::<Vec<_>>();
+        let table = ColorTable::new(
+            names
+                .iter()
+                .map(String::as_str)
+                .zip(values.iter().map(String::as_str)),
+            GtkVersion::Gtk3,
+        );
+        assert!(table.parse("@c0", BLUE).is_err());
+
+        // The end of the chain still resolves on its own.
+        assert_eq!(
+            table.parse(&format!("@c{}", MAX_DEPTH - 8), BLUE).unwrap(),
+            Some(RED)
+        );
+
+        // So does a chain of nested functions.
+        let nested = format!(
+            "{}#ff0000{}",
+            "lighter(".repeat(MAX_DEPTH),
+            ")".repeat(MAX_DEPTH)
+        );
+        assert!(table.parse(&nested, BLUE).is_err());
+    }
+
+    #[test]
+    fn deep_references() {
+        // Each color refers to the one before it twice, which would take 2^64 steps to resolve
+        // without remembering the colors that were already resolved.
+        let names = (0..64).map(|i| format!("c{}", i)).collect::<Vec<_>>();
+        let values = (1..64)
+            .map(|i| format!("mix(@c{0}, @c{0}, 0.5)", i - 1))
+            .collect::<Vec<_>>();
+        let definitions = Some((names[0].as_str(), "#ff0000")).into_iter().chain(
+            names[1..]
+                .iter()
+                .map(String::as_str)
+                .zip(values.iter().map(String::as_str)),
+        );
+
+        let table = ColorTable::new(definitions, GtkVersion::Gtk3);
+        assert_eq!(table.parse("@c63", BLUE).unwrap(), Some(RED));
+    }
+}
@@ -0,0 +1,1274 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Mapping GTK CSS onto widget properties.
+//!
+//! GTK's dialect of CSS uses pseudo-classes and values that `simplecss` doesn't understand, so
+//! the stylesheet is split into rules here and `simplecss` is only used to parse and match the
+//! selectors. Every widget is described as a chain of GTK CSS nodes, which is matched against the
+//! rules and cascaded like GTK would.
+
+use super::color::{split_args, split_function, ColorTable};
+
+use crate::{
+    Border, Color, Fill, FontFamily, FontStretch, Margin, TextStyle, Theme, Widget,
+    WidgetProperties, WidgetState, WIDGETS, WIDGET_STATES,
+};
+
+use simplecss::{AttributeOperator, Element, PseudoClass, Selector};
+
+use std::cell::RefCell;
+
+/// The attribute that GTK pseudo-classes are rewritten into.
+///
+/// `simplecss` rejects selectors containing pseudo-classes it doesn't know about, like
+/// `:disabled` or `:checked`. Instead, we rewrite them into attribute selectors on this name.
+const STATE_ATTRIBUTE: &str = "gtk-state";
+
+/// The attribute that negated pseudo-classes, like `:not(:disabled)`, are rewritten into.
+///
+/// It matches when the state attribute wouldn't.
+const NOT_STATE_ATTRIBUTE: &str = "gtk-not-state";
+
+/// The attribute that negated classes, like `:not(.flat)`, are rewritten into.
+const NOT_CLASS_ATTRIBUTE: &str = "gtk-not-class";
+
+/// The font size to use if neither the theme nor the stylesheet provide one.
+const DEFAULT_FONT_SIZE: f32 = 12.0;
+
+/// Reads the image at a URL, relative to the stylesheet with the given index.
+///
+/// Stylesheets are numbered in the order that they were parsed into the [`StyleSheet`].
+pub(super) type Assets<'a> = dyn Fn(usize, &str) -> Option<Vec<u8>> + 'a;
+
+/// A GTK stylesheet.
+pub(super) struct StyleSheet<'a> {
+    /// The rules in the stylesheet, in source order.
+    rules: Vec<Rule<'a>>,
+
+    /// The at-rules without blocks, in source order.
+    at_rules: Vec<AtRule<'a>>,
+
+    /// The number of stylesheets parsed so far.
+    sources: usize,
+}
+
+/// An at-rule without a block, like `@define-color`.
+struct AtRule<'a> {
+    /// The name of the rule, without the `@`.
+    name: &'a str,
+
+    /// Everything between the name and the semicolon.
+    prelude: &'a str,
+}
+
+/// A single rule in the stylesheet.
+struct Rule<'a> {
+    /// The comma-separated list of selectors, as written.
+    selectors: &'a str,
+
+    /// The declarations in the block.
+    declarations: Vec<Declaration<'a>>,
+}
+
+/// A single `name: value` pair.
+struct Declaration<'a> {
+    /// The name of the property.
+    name: &'a str,
+
+    /// The value of the property.
+    value: &'a str,
+
+    /// Whether this declaration is `!important`.
+    important: bool,
+
+    /// The index of the stylesheet that the declaration is in, which URLs are relative to.
+    source: usize,
+}
+
+impl<'a> StyleSheet<'a> {
+    /// Create an empty stylesheet.
+    pub(super) fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            at_rules: Vec::new(),
+            sources: 0,
+        }
+    }
+
+    /// Parse a stylesheet.
+    pub(super) fn parse(css: &'a str) -> Self {
+        let mut sheet = Self::new();
+        sheet.parse_more(css);
+        sheet
+    }
+
+    /// Parse more CSS into this stylesheet, after the existing rules.
+    ///
+    /// Like browsers do, this skips over anything it doesn't understand.
+    pub(super) fn parse_more(&mut self, css: &'a str) {
+        let rules = &mut self.rules;
+        let at_rules = &mut self.at_rules;
+        let source = self.sources;
+        let mut rest = css;
+        self.sources += 1;
+
+        loop {
+            rest = skip_whitespace_and_comments(rest);
+
+            if rest.is_empty() {
+                break;
+            }
+
+            // At-rules either end at a semicolon or carry a block.
+            if let Some(at_rule) = rest.strip_prefix('@') {
+                let end = find_top_level(at_rule, |b| b == b';' || b == b'{');
+
+                rest = match at_rule.as_bytes().get(end) {
+                    Some(b'{') => skip_block(&at_rule[end..]),
+                    Some(_) => {
+                        let statement = &at_rule[..end];
+                        let name_end = statement
+                            .find(|c: char| c.is_whitespace())
+                            .unwrap_or(statement.len());
+
+                        at_rules.push(AtRule {
+                            name: &statement[..name_end],
+                            prelude: statement[name_end..].trim(),
+                        });
+
+                        &at_rule[end + 1..]
+                    }
+                    None => "",
+                };
+
+                continue;
+            }
+
+            let open = find_top_level(rest, |b| b == b'{');
+            if open == rest.len() {
+                break;
+            }
+
+            let body = &rest[open + 1..];
+            let close = find_top_level(body, |b| b == b'}');
+
+            rules.push(Rule {
+                selectors: &rest[..open],
+                declarations: parse_declarations(&body[..close], source),
+            });
+
+            rest = body.get(close + 1..).unwrap_or("");
+        }
+    }
+
+    /// Get the targets of the `@import` rules.
+    pub(super) fn imports(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.at_rules
+            .iter()
+            .filter(|rule| rule.name == "import")
+            .filter_map(|rule| {
+                let target = rule.prelude;
+                let target = match target.strip_prefix("url(") {
+                    Some(url) => &url[..url.find(')')?],
+                    None => target.split_whitespace().next()?,
+                };
+
+                Some(target.trim().trim_matches(|c| c == '"' || c == '\''))
+            })
+    }
+
+    /// Get the symbolic colors defined through `@define-color`, as name-value pairs.
+    pub(super) fn color_definitions(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.at_rules
+            .iter()
+            .filter(|rule| rule.name == "define-color")
+            .filter_map(|rule| {
+                let (name, value) = rule.prelude.split_once(char::is_whitespace)?;
+                Some((name, value.trim()))
+            })
+    }
+
+    /// Apply the stylesheet to a theme.
+    ///
+    /// Symbolic colors are looked up in `colors`, and images are read through `assets`. The
+    /// declarations with colors that don't resolve, like ones in a cycle, are dropped, and the
+    /// errors are returned once each.
+    pub(super) fn apply(
+        &self,
+        theme: &mut Theme,
+        colors: &ColorTable<'_>,
+        assets: &Assets<'_>,
+    ) -> Vec<String> {
+        let colors = Colors {
+            table: colors,
+            errors: RefCell::new(Vec::new()),
+        };
+
+        // Rewrite the selectors into something `simplecss` can parse.
+        let selectors = self
+            .rules
+            .iter()
+            .map(|rule| rewrite_pseudo_classes(rule.selectors))
+            .collect::<Vec<_>>();
+
+        let mut matchers = Vec::new();
+        for (order, (rule, selectors)) in self.rules.iter().zip(&selectors).enumerate() {
+            for selector in selectors.split(',') {
+                if let Some(selector) = Selector::parse(selector.trim()) {
+                    matchers.push(Matcher {
+                        specificity: selector.specificity(),
+                        order,
+                        selector,
+                        declarations: &rule.declarations,
+                    });
+                }
+            }
+        }
+
+        // Sort the rules into cascade order, so that later declarations take precedence.
+        matchers.sort_by_key(|matcher| (matcher.specificity, matcher.order));
+
+        for widget in WIDGETS {
+            let path = css_path(*widget);
+
+            for state in WIDGET_STATES {
+                let mut leaf_state = String::from(state_name(*state));
+                if matches!(widget, Widget::TextHyperlink) {
+                    leaf_state.push_str(" link");
+                }
+
+                apply_to_widget(
+                    theme.get_mut(*widget, *state),
+                    &colors,
+                    assets,
+                    &matchers,
+                    path,
+                    &leaf_state,
+                );
+            }
+        }
+
+        colors.errors.into_inner()
+    }
+}
+
+/// The symbolic colors of a stylesheet, along with the errors found while resolving them.
+struct Colors<'t, 'a> {
+    /// The definitions of the colors.
+    table: &'t ColorTable<'a>,
+
+    /// The errors, without duplicates.
+    errors: RefCell<Vec<String>>,
+}
+
+impl Colors<'_, '_> {
+    /// Parse a color, keeping track of the error if it doesn't resolve.
+    fn parse(&self, value: &str, current: Color) -> Option<Color> {
+        match self.table.parse(value, current) {
+            Ok(color) => color,
+            Err(err) => {
+                let err = err.to_string();
+                let mut errors = self.errors.borrow_mut();
+                if !errors.contains(&err) {
+                    errors.push(err);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// A selector and the declarations it applies.
+struct Matcher<'s, 'a> {
+    /// The specificity of the selector.
+    specificity: [u8; 3],
+
+    /// The index of the rule in the stylesheet.
+    order: usize,
+
+    /// The parsed selector.
+    selector: Selector<'s>,
+
+    /// The declarations that the selector applies.
+    declarations: &'s [Declaration<'a>],
+}
+
+/// Cascade the stylesheet onto one widget in one state.
+fn apply_to_widget(
+    props: &mut WidgetProperties,
+    colors: &Colors<'_, '_>,
+    assets: &Assets<'_>,
+    matchers: &[Matcher<'_, '_>],
+    path: &[CssNode],
+    leaf_state: &str,
+) {
+    let mut text = props
+        .text_style()
+        .cloned()
+        .unwrap_or_else(|| TextStyle::new(DEFAULT_FONT_SIZE, FontFamily::SansSerif));
+    let mut border = props.border().cloned();
+
+    // Walk from the root down, so inherited properties carry over to the children.
+    for depth in 1..=path.len() {
+        let is_leaf = depth == path.len();
+        let node = Node {
+            path: &path[..depth],
+            state: if is_leaf { leaf_state } else { "" },
+        };
+
+        let matched = matchers
+            .iter()
+            .filter(|matcher| matcher.selector.matches(&node))
+            .collect::<Vec<_>>();
+        let normal = matched
+            .iter()
+            .flat_map(|matcher| matcher.declarations)
+            .filter(|decl| !decl.important);
+        let important = matched
+            .iter()
+            .flat_map(|matcher| matcher.declarations)
+            .filter(|decl| decl.important);
+
+        for decl in normal.chain(important) {
+            if !apply_text_declaration(&mut text, colors, decl) && is_leaf {
+                apply_box_declaration(props, &mut border, colors, assets, &text, decl);
+            }
+        }
+    }
+
+    if let Some(border) = border {
+        props.set_border(border);
+    }
+    props.set_text_style(text);
+}
+
+/// Apply an inherited, text-related declaration.
+///
+/// Returns `false` if the declaration isn't one of these.
+fn apply_text_declaration(
+    text: &mut TextStyle,
+    colors: &Colors<'_, '_>,
+    decl: &Declaration<'_>,
+) -> bool {
+    match decl.name {
+        "color" => {
+            if let Some(color) = colors.parse(decl.value, text.color()) {
+                text.set_color(color);
+            }
+        }
+        "font" => apply_font_shorthand(text, decl.value),
+        "font-family" => {
+            if let Some(family) = parse_font_family(decl.value) {
+                text.set_family(family);
+            }
+        }
+        "font-size" => {
+            if let Some(size) = parse_font_size(decl.value, text.size()) {
+                text.set_size(size);
+            }
+        }
+        "font-weight" => {
+            if let Some(weight) = parse_font_weight(decl.value, text.weight()) {
+                text.set_weight(weight);
+            }
+        }
+        "font-style" => {
+            if let Some(italic) = parse_font_style(decl.value) {
+                text.set_italic(italic);
+            }
+        }
+        "font-stretch" => {
+            if let Some(stretch) = parse_font_stretch(decl.value.trim()) {
+                text.set_stretch(stretch);
+            }
+        }
+        "text-decoration" | "text-decoration-line" => {
+            let mut underline = false;
+            let mut strikethrough = false;
+
+            for token in tokens(decl.value) {
+                match token {
+                    "underline" => underline = true,
+                    "line-through" => strikethrough = true,
+                    _ => {}
+                }
+            }
+
+            text.set_underline(underline)
+                .set_strikethrough(strikethrough);
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Apply a declaration that only affects the widget it is set on.
+fn apply_box_declaration(
+    props: &mut WidgetProperties,
+    border: &mut Option<Border>,
+    colors: &Colors<'_, '_>,
+    assets: &Assets<'_>,
+    text: &TextStyle,
+    decl: &Declaration<'_>,
+) {
+    let value = decl.value;
+    let current_color = text.color();
+    let font_size = text.size();
+
+    match decl.name {
+        "background-color" => {
+            if let Some(color) = colors.parse(value, current_color) {
+                props.set_background(color);
+            }
+        }
+        "background" | "background-image" => {
+            if value == "none" {
+                if decl.name == "background" {
+                    props.set_background(Color::new(0, 0, 0, 0));
+                }
+            } else if let Some(image) =
+                parse_image_url(value).and_then(|url| assets(decl.source, url))
+            {
+                // An image is drawn over the color.
+                props.set_background(Fill::Image(image));
+            } else if let Some(color) = parse_background(value, colors, current_color) {
+                props.set_background(color);
+            }
+        }
+        "border" => {
+            for token in tokens(value) {
+                if let Some(width) = parse_border_width(token, font_size) {
+                    border_or_default(border, current_color).set_thickness(width);
+                } else if matches!(token, "none" | "hidden") {
+                    border_or_default(border, current_color).set_thickness(0.0);
+                } else if let Some(color) = colors.parse(token, current_color) {
+                    border_or_default(border, current_color).set_color(color);
+                }
+            }
+        }
+        "border-width" => {
+            if let Some(width) = tokens(value)
+                .next()
+                .and_then(|w| parse_border_width(w, font_size))
+            {
+                border_or_default(border, current_color).set_thickness(width);
+            }
+        }
+        "border-color" => {
+            if let Some(color) = tokens(value)
+                .next()
+                .and_then(|c| colors.parse(c, current_color))
+            {
+                border_or_default(border, current_color).set_color(color);
+            }
+        }
+        "border-style" => {
+            if matches!(tokens(value).next(), Some("none" | "hidden")) {
+                border_or_default(border, current_color).set_thickness(0.0);
+            }
+        }
+        "border-radius" => {
+            if let Some(radius) = tokens(value)
+                .next()
+                .and_then(|r| parse_length(r, font_size))
+            {
+                border_or_default(border, current_color).set_radius(radius);
+            }
+        }
+        "padding" => {
+            if let Some(padding) = parse_sides(value, font_size) {
+                props.set_padding(padding);
+            }
+        }
+        "margin" => {
+            if let Some(margin) = parse_sides(value, font_size) {
+                props.set_margin(margin);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Get the border, creating one in the current color if there isn't one.
+fn border_or_default(border: &mut Option<Border>, current_color: Color) -> &mut Border {
+    border.get_or_insert_with(|| Border::new(0.0, current_color))
+}
+
+/// A node in GTK's CSS tree.
+#[derive(Debug, Clone, Copy)]
+struct CssNode {
+    /// The name of the node.
+    name: &'static str,
+
+    /// The space-separated style classes of the node.
+    classes: &'static str,
+}
+
+const fn node(name: &'static str, classes: &'static str) -> CssNode {
+    CssNode { name, classes }
+}
+
+/// Build a `'static` slice of nodes.
+macro_rules! path {
+    ($($node:expr),* $(,)?) => {{
+        const PATH: &[CssNode] = &[$($node),*];
+        PATH
+    }};
+}
+
+const WINDOW: CssNode = node("window", "background");
+const POPUP: CssNode = node("window", "background popup");
+
+/// Get the chain of GTK CSS nodes, from the root down, that represents a widget.
+fn css_path(widget: Widget) -> &'static [CssNode] {
+    match widget {
+        Widget::Button => path![WINDOW, node("button", "text-button")],
+        Widget::Checkbox => path![WINDOW, node("checkbutton", ""), node("check", "")],
+        Widget::RadioButton => path![WINDOW, node("radiobutton", ""), node("radio", "")],
+        Widget::ComboBox => path![WINDOW, node("combobox", ""), node("button", "combo")],
+        Widget::ComboBoxButton => path![
+            WINDOW,
+            node("combobox", ""),
+            node("button", "combo"),
+            node("arrow", ""),
+        ],
+        Widget::DateTimePicker => path![WINDOW, node("spinbutton", ""), node("entry", "")],
+        Widget::Editor => path![WINDOW, node("entry", "")],
+        Widget::ListView => path![WINDOW, node("list", "")],
+        Widget::ListViewItem => path![WINDOW, node("list", ""), node("row", "activatable")],
+        Widget::ListViewExpandButton => path![WINDOW, node("treeview", "view expander")],
+        Widget::MenuBar => path![WINDOW, node("menubar", "")],
+        Widget::MenuBarItem => path![WINDOW, node("menubar", ""), node("menuitem", "")],
+        Widget::PopupMenu => path![POPUP, node("menu", "")],
+        Widget::PopupMenuItem => path![POPUP, node("menu", ""), node("menuitem", "")],
+        Widget::MenuSeparator => path![POPUP, node("menu", ""), node("separator", "")],
+        Widget::NavigationBack | Widget::NavigationForward => path![
+            WINDOW,
+            node("headerbar", "titlebar"),
+            node("button", "image-button"),
+        ],
+        Widget::NavigationMenu => path![
+            WINDOW,
+            node("headerbar", "titlebar"),
+            node("button", "image-button popup"),
+        ],
+        Widget::NavigationPageDown | Widget::NavigationPageUp | Widget::ScrollBarArrow => {
+            path![WINDOW, node("scrollbar", ""), node("button", "")]
+        }
+        Widget::ProgressBar => path![WINDOW, node("progressbar", ""), node("trough", "")],
+        Widget::ProgressBarChunk => path![
+            WINDOW,
+            node("progressbar", ""),
+            node("trough", ""),
+            node("progress", ""),
+        ],
+        Widget::ScrollBarHandle => path![
+            WINDOW,
+            node("scrollbar", ""),
+            node("contents", ""),
+            node("trough", ""),
+            node("slider", ""),
+        ],
+        Widget::SpinnerDown => path![WINDOW, node("spinbutton", ""), node("button", "down")],
+        Widget::SpinnerUp => path![WINDOW, node("spinbutton", ""), node("button", "up")],
+        Widget::TabBody => path![WINDOW, node("notebook", ""), node("stack", "")],
+        Widget::TabPane => path![WINDOW, node("notebook", ""), node("header", "top")],
+        Widget::TabItem => path![
+            WINDOW,
+            node("notebook", ""),
+            node("header", "top"),
+            node("tabs", ""),
+            node("tab", ""),
+        ],
+        Widget::Taskbar => path![WINDOW, node("statusbar", "")],
+        Widget::TextBody => path![WINDOW, node("textview", "view"), node("text", "")],
+        Widget::TextTitle => path![
+            WINDOW,
+            node("headerbar", "titlebar"),
+            node("label", "title"),
+        ],
+        Widget::TextHyperlink => path![WINDOW, node("label", ""), node("link", "")],
+        Widget::TextLabel => path![WINDOW, node("label", "")],
+        Widget::ToolbarButton => path![
+            WINDOW,
+            node("toolbar", ""),
+            node("toolbutton", ""),
+            node("button", "flat"),
+        ],
+        Widget::ToolbarDropdownButton => path![
+            WINDOW,
+            node("toolbar", ""),
+            node("toolbutton", ""),
+            node("button", "flat popup"),
+        ],
+        Widget::ToolbarSeparator => path![WINDOW, node("toolbar", ""), node("separator", "")],
+        Widget::TooltipBalloon | Widget::TooltipBalloonStem => {
+            path![node("tooltip", "background")]
+        }
+    }
+}
+
+/// Get the name of the GTK pseudo-class for a widget state.
+fn state_name(state: WidgetState) -> &'static str {
+    match state {
+        WidgetState::Disabled => "disabled",
+        WidgetState::Enabled => "",
+        WidgetState::Focused => "focus",
+        WidgetState::Selected => "selected",
+        WidgetState::Hovered => "hover",
+        WidgetState::Pressed => "active",
+        WidgetState::Checked => "checked",
+        WidgetState::Inactive => "backdrop",
+    }
+}
+
+/// A node being matched against selectors.
+#[derive(Clone, Copy)]
+struct Node<'a> {
+    /// The path from the root to this node.
+    path: &'a [CssNode],
+
+    /// The space-separated pseudo-classes that apply to this node.
+    state: &'a str,
+}
+
+impl Element for Node<'_> {
+    fn parent_element(&self) -> Option<Self> {
+        match self.path {
+            [parent @ .., _] if !parent.is_empty() => Some(Node {
+                path: parent,
+                state: "",
+            }),
+            _ => None,
+        }
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        None
+    }
+
+    fn has_local_name(&self, name: &str) -> bool {
+        self.path.last().is_some_and(|node| node.name == name)
+    }
+
+    fn attribute_matches(&self, local_name: &str, operator: AttributeOperator<'_>) -> bool {
+        let node = match self.path.last() {
+            Some(node) => node,
+            None => return false,
+        };
+
+        match local_name {
+            "class" => operator.matches(node.classes),
+            STATE_ATTRIBUTE => operator.matches(self.state),
+            NOT_STATE_ATTRIBUTE => !operator.matches(self.state),
+            NOT_CLASS_ATTRIBUTE => !operator.matches(node.classes),
+            _ => false,
+        }
+    }
+
+    fn pseudo_class_matches(&self, _class: PseudoClass<'_>) -> bool {
+        // All pseudo-classes are rewritten into attributes.
+        false
+    }
+}
+
+/// Rewrite every pseudo-class in a selector list into a state attribute.
+///
+/// `:not()` is rewritten into negated attributes if its arguments are single pseudo-classes or
+/// classes, like `:not(:disabled)` or `:not(.flat, :backdrop)`. Other functional pseudo-classes,
+/// and `:not()` with anything else, are rewritten along with their arguments, which means that
+/// they never match.
+fn rewrite_pseudo_classes(selectors: &str) -> String {
+    let mut result = String::with_capacity(selectors.len());
+    let mut rest = selectors;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '/' if rest.starts_with("/*") => {
+                rest = rest[2..].split_once("*/").map_or("", |(_, after)| after);
+                result.push(' ');
+            }
+            '[' => {
+                let end = rest.find(']').map_or(rest.len(), |end| end + 1);
+                result.push_str(&rest[..end]);
+                rest = &rest[end..];
+            }
+            ':' => {
+                let name = rest.trim_start_matches(':');
+                let end = name
+                    .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(name.len());
+                let (name, after) = name.split_at(end);
+
+                let mut args = None;
+                rest = match after.strip_prefix('(') {
+                    Some(after) => {
+                        let close = find_top_level(after, |b| b == b')');
+                        args = Some(&after[..close]);
+                        after.get(close + 1..).unwrap_or("")
+                    }
+                    None => after,
+                };
+
+                let negated = match (name, args) {
+                    ("not", Some(args)) => rewrite_negation(args),
+                    _ => None,
+                };
+
+                match negated {
+                    Some(negated) => result.push_str(&negated),
+                    None => push_attribute(&mut result, STATE_ATTRIBUTE, name),
+                }
+            }
+            c => {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    result
+}
+
+/// Rewrite the arguments of `:not()` into negated attributes, if they're all single pseudo-classes
+/// or classes.
+fn rewrite_negation(args: &str) -> Option<String> {
+    let mut result = String::new();
+
+    for arg in split_args(args) {
+        let (attribute, name) = match arg.as_bytes().first() {
+            Some(b':') => (NOT_STATE_ATTRIBUTE, &arg[1..]),
+            Some(b'.') => (NOT_CLASS_ATTRIBUTE, &arg[1..]),
+            _ => return None,
+        };
+
+        let is_ident = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(is_ident) {
+            return None;
+        }
+
+        push_attribute(&mut result, attribute, name);
+    }
+
+    Some(result)
+}
+
+/// Add an attribute selector that looks for a word in an attribute.
+fn push_attribute(selector: &mut String, attribute: &str, word: &str) {
+    selector.push('[');
+    selector.push_str(attribute);
+    selector.push_str("~=\"");
+    selector.push_str(word);
+    selector.push_str("\"]");
+}
+
+/// Parse the declarations in a block.
+fn parse_declarations(block: &str, source: usize) -> Vec<Declaration<'_>> {
+    let mut declarations = Vec::new();
+    let mut rest = block;
+
+    while !rest.is_empty() {
+        let end = find_top_level(rest, |b| b == b';');
+        let declaration = skip_whitespace_and_comments(&rest[..end]);
+        rest = rest.get(end + 1..).unwrap_or("");
+
+        if let Some((name, value)) = declaration.split_once(':') {
+            let value = trim_comments(value);
+            let (value, important) = match value.strip_suffix("!important") {
+                Some(value) => (value.trim_end(), true),
+                None => (value, false),
+            };
+
+            if !value.is_empty() {
+                declarations.push(Declaration {
+                    name: name.trim(),
+                    value,
+                    important,
+                    source,
+                });
+            }
+        }
+    }
+
+    declarations
+}
+
+/// Skip over leading whitespace and comments.
+fn skip_whitespace_and_comments(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+
+        match text.strip_prefix("/*") {
+            Some(comment) => {
+                text = comment.split_once("*/").map_or("", |(_, after)| after);
+            }
+            None => return text,
+        }
+    }
+}
+
+/// Trim whitespace and comments from both ends of a value.
+fn trim_comments(value: &str) -> &str {
+    let mut value = skip_whitespace_and_comments(value).trim_end();
+
+    while let Some(before) = value.strip_suffix("*/") {
+        value = before
+            .rfind("/*")
+            .map_or("", |start| before[..start].trim_end());
+    }
+
+    value
+}
+
+/// Skip over a `{}` block at the start of the text.
+fn skip_block(text: &str) -> &str {
+    let body = &text[1..];
+    let close = find_top_level(body, |b| b == b'}');
+    body.get(close + 1..).unwrap_or("")
+}
+
+/// Find the first byte outside of strings, comments and brackets that matches `stop`.
+///
+/// Returns the length of the text if there is none.
+fn find_top_level(text: &str, stop: impl Fn(u8) -> bool) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        match b {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            b'"' | b'\'' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            _ if depth == 0 && stop(b) => return i,
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+/// Split a value into its whitespace-separated components, keeping functions intact.
+fn tokens(value: &str) -> impl Iterator<Item = &str> + '_ {
+    let mut rest = value.trim();
+
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = find_top_level(rest, |b| b.is_ascii_whitespace());
+        let (token, after) = rest.split_at(end);
+        rest = after.trim_start();
+        Some(token)
+    })
+}
+
+/// Parse a length into pixels.
+fn parse_length(value: &str, font_size: f32) -> Option<f32> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f32>().ok()?;
+
+    match unit {
+        "px" | "" => Some(number),
+        "pt" => Some(number * 96.0 / 72.0),
+        "em" | "rem" => Some(number * font_size),
+        _ => None,
+    }
+}
+
+/// Parse a border width, including the keyword widths.
+fn parse_border_width(value: &str, font_size: f32) -> Option<f32> {
+    match value {
+        "thin" => Some(1.0),
+        "medium" => Some(3.0),
+        "thick" => Some(5.0),
+        value => parse_length(value, font_size),
+    }
+}
+
+/// Parse the one to four lengths that make up a padding or margin.
+fn parse_sides(value: &str, font_size: f32) -> Option<Margin> {
+    let sides = tokens(value)
+        .map(|side| parse_length(side, font_size))
+        .collect::<Option<Vec<_>>>()?;
+
+    let (top, right, bottom, left) = match *sides.as_slice() {
+        [all] => (all, all, all, all),
+        [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+        [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+        [top, right, bottom, left] => (top, right, bottom, left),
+        _ => return None,
+    };
+
+    Some(Margin::new(left, right, top, bottom))
+}
+
+/// Get a solid color out of a background value.
+///
+/// Gradients are approximated by their first color stop.
+fn parse_background(value: &str, colors: &Colors<'_, '_>, current: Color) -> Option<Color> {
+    tokens(value).find_map(|token| {
+        if let Some(color) = colors.parse(token, current) {
+            return Some(color);
+        }
+
+        let (name, args) = split_function(token)?;
+        match name {
+            "image" | "linear-gradient" | "radial-gradient" => split_args(args)
+                .into_iter()
+                .find_map(|arg| tokens(arg).find_map(|stop| colors.parse(stop, current))),
+            _ => None,
+        }
+    })
+}
+
+/// Get the URL of the image in a background, like `url("assets/button.png")`.
+///
+/// For images at more than one scale, like `-gtk-scaled(url(a.png), url(a@2.png))`, this is the
+/// unscaled one.
+fn parse_image_url(value: &str) -> Option<&str> {
+    tokens(value).find_map(|token| {
+        let (name, args) = split_function(token)?;
+        match name {
+            "url" => Some(args.trim().trim_matches(|c| c == '"' || c == '\'')),
+            "-gtk-scaled" => parse_image_url(split_args(args).first()?),
+            _ => None,
+        }
+    })
+}
+
+/// Parse the `font` shorthand.
+fn apply_font_shorthand(text: &mut TextStyle, value: &str) {
+    let mut rest = value.trim();
+
+    // The style, variant and weight come before the size.
+    while let Some(token) = tokens(rest).next() {
+        let after = rest[token.len()..].trim_start();
+
+        if let Some(italic) = parse_font_style(token) {
+            text.set_italic(italic);
+        } else if let Some(weight) = parse_font_weight(token, text.weight()) {
+            text.set_weight(weight);
+        } else if let Some(stretch) = parse_font_stretch(token) {
+            text.set_stretch(stretch);
+        } else if token == "small-caps" {
+            // We don't track the variant.
+        } else {
+            // This is the size, optionally followed by the line height.
+            let size = token.split('/').next().unwrap_or(token);
+            if let Some(size) = parse_font_size(size, text.size()) {
+                text.set_size(size);
+            }
+
+            if let Some(family) = parse_font_family(after) {
+                text.set_family(family);
+            }
+
+            return;
+        }
+
+        rest = after;
+    }
+}
+
+/// Parse a font family list, taking the first family.
+fn parse_font_family(value: &str) -> Option<FontFamily> {
+    let family = value.split(',').next()?.trim();
+    let family = family.trim_matches(|c| c == '"' || c == '\'');
+
+    if family.is_empty() {
+        None
+    } else {
+        Some(FontFamily::from_name(family))
+    }
+}
+
+/// Parse a font size relative to the parent's size.
+fn parse_font_size(value: &str, parent: f32) -> Option<f32> {
+    match value {
+        "smaller" => Some(parent / 1.2),
+        "larger" => Some(parent * 1.2),
+        value => match value.strip_suffix('%') {
+            Some(percent) => Some(percent.parse::<f32>().ok()? * parent / 100.0),
+            None => parse_length(value, parent),
+        },
+    }
+}
+
+/// Parse a font weight relative to the parent's weight.
+fn parse_font_weight(value: &str, parent: u16) -> Option<u16> {
+    match value {
+        "normal" => Some(400),
+        "bold" => Some(700),
+        "bolder" => Some(if parent < 600 { 700 } else { 900 }),
+        "lighter" => Some(if parent > 500 { 400 } else { 100 }),
+        value => value.parse::<u16>().ok().filter(|w| (1..=1000).contains(w)),
+    }
+}
+
+/// Parse a font stretch keyword.
+fn parse_font_stretch(value: &str) -> Option<FontStretch> {
+    match value {
+        "ultra-condensed" => Some(FontStretch::UltraCondensed),
+        "extra-condensed" => Some(FontStretch::ExtraCondensed),
+        "condensed" => Some(FontStretch::Condensed),
+        "semi-condensed" => Some(FontStretch::SemiCondensed),
+        "normal" => Some(FontStretch::Normal),
+        "semi-expanded" => Some(FontStretch::SemiExpanded),
+        "expanded" => Some(FontStretch::Expanded),
+        "extra-expanded" => Some(FontStretch::ExtraExpanded),
+        "ultra-expanded" => Some(FontStretch::UltraExpanded),
+        _ => None,
+    }
+}
+
+/// Parse a font style into whether the font is italic.
+fn parse_font_style(value: &str) -> Option<bool> {
+    match value {
+        "normal" => Some(false),
+        "italic" | "oblique" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GtkVersion, ShadePreference};
+
+    /// Apply a stylesheet on top of the default theme.
+    fn apply(css: &str) -> Theme {
+        let sheet = StyleSheet::parse(css);
+        let colors = ColorTable::new(sheet.color_definitions(), GtkVersion::Gtk3);
+        let mut theme = Theme::default_theme(ShadePreference::Light);
+        sheet.apply(&mut theme, &colors, &|_, _| None);
+        theme
+    }
+
+    fn text_color(theme: &Theme, widget: Widget, state: WidgetState) -> Color {
+        theme.get(widget, state).text_style().unwrap().color()
+    }
+
+    fn background(theme: &Theme, widget: Widget, state: WidgetState) -> Option<Color> {
+        theme
+            .get(widget, state)
+            .background()
+            .and_then(|fill| match fill {
+                Fill::Color(color) => Some(*color),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn states() {
+        let theme = apply(
+            "button { color: #111111; }
+             button:hover { color: #222222; }
+             button:disabled { color: #333333; }
+             button:backdrop { color: #444444; }
+             button:hover:disabled { color: #555555; }",
+        );
+
+        let color = |state| text_color(&theme, Widget::Button, state);
+        assert_eq!(
+            color(WidgetState::Enabled),
+            Color::new(0x11, 0x11, 0x11, 255)
+        );
+        assert_eq!(
+            color(WidgetState::Hovered),
+            Color::new(0x22, 0x22, 0x22, 255)
+        );
+        assert_eq!(
+            color(WidgetState::Disabled),
+            Color::new(0x33, 0x33, 0x33, 255)
+        );
+        assert_eq!(
+            color(WidgetState::Inactive),
+            Color::new(0x44, 0x44, 0x44, 255)
+        );
+        assert_eq!(
+            color(WidgetState::Pressed),
+            Color::new(0x11, 0x11, 0x11, 255)
+        );
+
+        // The state only applies to the widget itself, not to its ancestors.
+        let theme = apply("window:hover button { color: #666666; }");
+        assert_ne!(
+            text_color(&theme, Widget::Button, WidgetState::Hovered),
+            Color::new(0x66, 0x66, 0x66, 255)
+        );
+    }
+
+    #[test]
+    fn rewrite() {
+        assert_eq!(
+            rewrite_pseudo_classes("button:hover, entry:not(:focus, .flat) /* :checked */"),
+            "button[gtk-state~=\"hover\"], \
+             entry[gtk-not-state~=\"focus\"][gtk-not-class~=\"flat\"]  "
+        );
+
+        // Negations of anything else never match.
+        assert_eq!(
+            rewrite_pseudo_classes("button:not(box button):nth-child(2)"),
+            "button[gtk-state~=\"not\"][gtk-state~=\"nth-child\"]"
+        );
+        assert_eq!(
+            rewrite_pseudo_classes("a[href=\"x:y\"]::selection"),
+            "a[href=\"x:y\"][gtk-state~=\"selection\"]"
+        );
+    }
+
+    #[test]
+    fn negation() {
+        // Like Adwaita, which only colors buttons that can be used.
+        let theme = apply(
+            "button { background-color: #ffffff; color: #000000; }
+             button:not(:disabled):not(:backdrop) { background-color: #3584e4; }
+             button:not(.flat):hover { color: #222222; }
+             button:not(.text-button) { color: #ff0000; }",
+        );
+
+        let blue = Some(Color::new(0x35, 0x84, 0xe4, 255));
+        let white = Some(Color::new(255, 255, 255, 255));
+        let button = |state| background(&theme, Widget::Button, state);
+        assert_eq!(button(WidgetState::Enabled), blue);
+        assert_eq!(button(WidgetState::Hovered), blue);
+        assert_eq!(button(WidgetState::Disabled), white);
+        assert_eq!(button(WidgetState::Inactive), white);
+
+        let color = |state| text_color(&theme, Widget::Button, state);
+        assert_eq!(color(WidgetState::Enabled), Color::new(0, 0, 0, 255));
+        assert_eq!(
+            color(WidgetState::Hovered),
+            Color::new(0x22, 0x22, 0x22, 255)
+        );
+    }
+
+    #[test]
+    fn specificity() {
+        // The class selector wins, even though it comes first.
+        let theme = apply(
+            "button.text-button { background-color: #ff0000; }
+             window button { background-color: #00ff00; }
+             button { background-color: #0000ff; }",
+        );
+        assert_eq!(
+            background(&theme, Widget::Button, WidgetState::Enabled),
+            Some(Color::new(255, 0, 0, 255))
+        );
+
+        // With equal specificity, the later rule wins.
+        let theme = apply(
+            "button { background-color: #ff0000; }
+             button { background-color: #0000ff; }",
+        );
+        assert_eq!(
+            background(&theme, Widget::Button, WidgetState::Enabled),
+            Some(Color::new(0, 0, 255, 255))
+        );
+    }
+
+    #[test]
+    fn important() {
+        let theme = apply(
+            "button { background-color: #ff0000 !important; }
+             button.text-button:hover { background-color: #0000ff; }",
+        );
+        assert_eq!(
+            background(&theme, Widget::Button, WidgetState::Hovered),
+            Some(Color::new(255, 0, 0, 255))
+        );
+    }
+
+    #[test]
+    fn comments_and_strings() {
+        let theme = apply(
+            "/* button { color: #ff0000; } */
+             @import url(\"a;b.css\");
+             @media print { button { color: #00ff00; } }
+             button /* } */ {
+                 font-family: \"Foo}Bar\", sans-serif;
+                 color: /* ; */ #010203 /* } */;
+             }
+             label { color: #040506; }",
+        );
+
+        let button = theme.get(Widget::Button, WidgetState::Enabled);
+        let text = button.text_style().unwrap();
+        assert_eq!(text.family(), &FontFamily::Custom("Foo}Bar".into()));
+        assert_eq!(text.color(), Color::new(1, 2, 3, 255));
+        assert_eq!(
+            text_color(&theme, Widget::TextLabel, WidgetState::Enabled),
+            Color::new(4, 5, 6, 255)
+        );
+
+        let sheet = StyleSheet::parse("@import url(\"a;b.css\"); @import 'c.css';");
+        assert_eq!(sheet.imports().collect::<Vec<_>>(), ["a;b.css", "c.css"]);
+    }
+
+    #[test]
+    fn inheritance() {
+        let theme = apply(
+            "window {
+                 color: #abcdef;
+                 font: bold 10px Cantarell;
+                 background-color: #123456;
+             }
+             button { font-size: 150%; }",
+        );
+
+        // Text properties carry over from the window, and sizes are relative to it.
+        let button = theme.get(Widget::Button, WidgetState::Enabled);
+        let text = button.text_style().unwrap();
+        assert_eq!(text.color(), Color::new(0xab, 0xcd, 0xef, 255));
+        assert_eq!(text.family(), &FontFamily::Custom("Cantarell".into()));
+        assert_eq!(text.weight(), 700);
+        assert_eq!(text.size(), 15.0);
+
+        // The background doesn't.
+        assert_ne!(
+            background(&theme, Widget::Button, WidgetState::Enabled),
+            Some(Color::new(0x12, 0x34, 0x56, 255))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Reading files out of `gtk.gresource` bundles.
+//!
+//! Themes can ship their stylesheets and assets compiled into a `gresource` bundle next to
+//! `gtk.css`, which then refers to them through `resource:///` URLs.
+//!
+//! Both the stylesheets and the images that they use as backgrounds are read out of the bundle.
+
+use crate::free_unix::gvariant::Variant;
+use crate::free_unix::gvdb::GvdbFile;
+
+use std::io;
+use std::path::Path;
+
+/// The flag for resources compressed with zlib.
+const COMPRESSED: u32 = 1;
+
+/// A `gresource` bundle.
+pub(super) struct ResourceBundle {
+    /// The underlying GVDB file.
+    file: GvdbFile,
+}
+
+impl ResourceBundle {
+    /// Open a bundle.
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: GvdbFile::open(path)?,
+        })
+    }
+
+    /// Read a file out of the bundle by its absolute resource path.
+    pub(super) fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let resource = self.file.root()?.get(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("resource {} is not in the bundle", path),
+            )
+        })?;
+
+        match resource.unwrap_variant() {
+            Some(value) => read_value(value, path),
+            None => Err(invalid(path)),
+        }
+    }
+}
+
+/// Read the contents of a resource out of its value.
+fn read_value(value: Variant<'_>, path: &str) -> io::Result<Vec<u8>> {
+    // Every resource is a `(uuay)` of its size, flags and data.
+    let fields = match value.type_str() {
+        "(uuay)" => value.fields().ok_or_else(|| invalid(path))?,
+        _ => return Err(invalid(path)),
+    };
+
+    let size = fields[0].as_u32().ok_or_else(|| invalid(path))? as usize;
+    let flags = fields[1].as_u32().ok_or_else(|| invalid(path))?;
+    let data = fields[2].data();
+
+    if flags & COMPRESSED != 0 {
+        // Don't inflate past the size that the bundle claims, which a corrupt one may lie about.
+        let data = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, size)
+            .map_err(|_| invalid(path))?;
+        match data.len() == size {
+            true => Ok(data),
+            false => Err(invalid(path)),
+        }
+    } else {
+        Ok(data.get(..size).ok_or_else(|| invalid(path))?.to_vec())
+    }
+}
+
+/// The error for a corrupt resource.
+fn invalid(path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("resource {} is corrupt", path),
+    )
+}
+
+/// Resolve a path relative to a resource path.
+pub(super) fn resolve(base: &str, relative: &str) -> String {
+    let dir = match relative.starts_with('/') {
+        true => "",
+        false => &base[..base.rfind('/').map_or(0, |slash| slash + 1)],
+    };
+    let mut components = Vec::new();
+
+    for component in dir.split('/').chain(relative.split('/')) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    let mut path = String::new();
+    for component in components {
+        path.push('/');
+        path.push_str(component);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> ResourceBundle {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/theme.gresource");
+        ResourceBundle::open(Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn read() {
+        let bundle = bundle();
+
+        let css = bundle.read("/org/example/theme/gtk.css").unwrap();
+        assert!(css.starts_with(b"@import url(\"resource:///org/example/theme/colors.css\");"));
+
+        // This one is compressed.
+        let css = bundle.read("/org/example/theme/colors.css").unwrap();
+        assert_eq!(css, b"@define-color fg #336699;\n".repeat(4));
+
+        let err = bundle.read("/org/example/theme/gtk-dark.css").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn compressed_size() {
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&[b'a'; 4096], 6);
+        let resource = |size: u32| {
+            let mut value = Vec::new();
+            value.extend(size.to_le_bytes());
+            value.extend(COMPRESSED.to_le_bytes());
+            value.extend(&compressed);
+            value
+        };
+
+        // Data that inflates to more or less than the size that the bundle claims is corrupt.
+        for size in [16, 8192] {
+            let err = read_value(Variant::new("(uuay)", &resource(size)), "/big").unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", size);
+        }
+        let data = read_value(Variant::new("(uuay)", &resource(4096)), "/big").unwrap();
+        assert_eq!(data, [b'a'; 4096]);
+    }
+
+    #[test]
+    fn resolve_paths() {
+        let base = "/org/example/theme/gtk.css";
+        assert_eq!(resolve(base, "colors.css"), "/org/example/theme/colors.css");
+        assert_eq!(resolve(base, "./a/../b.css"), "/org/example/theme/b.css");
+        assert_eq!(resolve(base, "../../../../x.css"), "/x.css");
+        assert_eq!(resolve(base, "/org/other.css"), "/org/other.css");
+    }
+}
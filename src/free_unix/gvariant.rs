@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A reader for serialized `GVariant` values.
+//!
+//! This only covers reading values out of the files that GLib writes, like `gresource` bundles.
+//! Values are assumed to be in little-endian byte order.
+
+use std::str;
+
+/// A serialized `GVariant` value.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Variant<'a> {
+    /// The type string of the value.
+    ty: &'a str,
+
+    /// The serialized value.
+    data: &'a [u8],
+}
+
+impl<'a> Variant<'a> {
+    /// Create a variant from its type string and serialized data.
+    pub(super) fn new(ty: &'a str, data: &'a [u8]) -> Self {
+        Self { ty, data }
+    }
+
+    /// Get the type string of the value.
+    pub(super) fn type_str(&self) -> &'a str {
+        self.ty
+    }
+
+    /// Get the serialized data of the value.
+    pub(super) fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Get the value inside of a `v` variant.
+    pub(super) fn unwrap_variant(&self) -> Option<Variant<'a>> {
+        if self.ty != "v" {
+            return None;
+        }
+
+        // The value is followed by a zero byte and its type string.
+        let split = self.data.iter().rposition(|&b| b == 0)?;
+        let ty = str::from_utf8(&self.data[split + 1..]).ok()?;
+
+        match split_type(ty) {
+            Some((_, "")) => Some(Variant::new(ty, &self.data[..split])),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a boolean.
+    pub(super) fn as_bool(&self) -> Option<bool> {
+        match (self.ty, self.data) {
+            ("b", [value]) => Some(*value != 0),
+            _ => None,
+        }
+    }
+
+    /// Get the value of an unsigned 32-bit integer.
+    pub(super) fn as_u32(&self) -> Option<u32> {
+        match self.ty {
+            "u" => Some(u32::from_le_bytes(self.data.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a signed 32-bit integer.
+    pub(super) fn as_i32(&self) -> Option<i32> {
+        match self.ty {
+            "i" => Some(i32::from_le_bytes(self.data.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a double.
+    pub(super) fn as_f64(&self) -> Option<f64> {
+        match self.ty {
+            "d" => Some(f64::from_le_bytes(self.data.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a string, object path or signature.
+    pub(super) fn as_str(&self) -> Option<&'a str> {
+        match self.ty {
+            "s" | "o" | "g" => {
+                let (last, string) = self.data.split_last()?;
+                if *last != 0 {
+                    return None;
+                }
+
+                str::from_utf8(string).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the members of a tuple or dictionary entry.
+    pub(super) fn fields(&self) -> Option<Vec<Variant<'a>>> {
+        if !matches!(self.ty.as_bytes().first(), Some(b'(' | b'{')) {
+            return None;
+        }
+
+        let data = self.data;
+        let members = members(self.ty).collect::<Vec<_>>();
+        let offset_size = offset_size(data.len());
+
+        // The ends of variable-sized members are stored backwards from the end.
+        let mut offsets_end = data.len();
+        let mut start = 0;
+        let mut fields = Vec::with_capacity(members.len());
+
+        for (i, member) in members.iter().enumerate() {
+            let begin = align_up(start, alignment(member));
+            let end = match fixed_size(member) {
+                Some(size) => begin + size,
+                None if i + 1 == members.len() => offsets_end,
+                None => {
+                    offsets_end = offsets_end.checked_sub(offset_size)?;
+                    read_offset(data, offsets_end, offset_size)?
+                }
+            };
+
+            if begin > end || end > data.len() {
+                return None;
+            }
+
+            fields.push(Variant::new(member, &data[begin..end]));
+            start = end;
+        }
+
+        Some(fields)
+    }
+}
+
+/// How deeply types can be nested, like GLib's `G_VARIANT_MAX_RECURSION_DEPTH`.
+const MAX_DEPTH: usize = 128;
+
+/// Split the first complete type off a type string.
+fn split_type(ty: &str) -> Option<(&str, &str)> {
+    let end = type_end(ty.as_bytes(), 0, 0)?;
+    Some(ty.split_at(end))
+}
+
+/// Find the end of the type starting at `start`, nested `depth` levels deep.
+fn type_end(ty: &[u8], start: usize, depth: usize) -> Option<usize> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    match *ty.get(start)? {
+        b'a' | b'm' => type_end(ty, start + 1, depth + 1),
+        b'(' => {
+            let mut end = start + 1;
+            while *ty.get(end)? != b')' {
+                end = type_end(ty, end, depth + 1)?;
+            }
+            Some(end + 1)
+        }
+        b'{' => {
+            let end = type_end(ty, start + 1, depth + 1)?;
+            let end = type_end(ty, end, depth + 1)?;
+            (*ty.get(end)? == b'}').then_some(end + 1)
+        }
+        b'b' | b'y' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'h' | b'd' | b's' | b'o'
+        | b'g' | b'v' => Some(start + 1),
+        _ => None,
+    }
+}
+
+/// Iterate over the member types of a tuple or dictionary entry type.
+fn members(ty: &str) -> impl Iterator<Item = &str> {
+    let mut rest = &ty[1..ty.len() - 1];
+
+    core::iter::from_fn(move || {
+        let (member, after) = split_type(rest)?;
+        rest = after;
+        Some(member)
+    })
+}
+
+/// Get the alignment of a type.
+fn alignment(ty: &str) -> usize {
+    match ty.as_bytes().first() {
+        Some(b'n' | b'q') => 2,
+        Some(b'i' | b'u' | b'h') => 4,
+        Some(b'x' | b't' | b'd' | b'v') => 8,
+        Some(b'a' | b'm') => alignment(&ty[1..]),
+        Some(b'(' | b'{') => members(ty).map(alignment).max().unwrap_or(1),
+        _ => 1,
+    }
+}
+
+/// Get the size of a type, if it is fixed.
+fn fixed_size(ty: &str) -> Option<usize> {
+    match ty.as_bytes().first()? {
+        b'b' | b'y' => Some(1),
+        b'n' | b'q' => Some(2),
+        b'i' | b'u' | b'h' => Some(4),
+        b'x' | b't' | b'd' => Some(8),
+        b'(' | b'{' => {
+            let mut size = 0;
+            for member in members(ty) {
+                size = align_up(size, alignment(member)) + fixed_size(member)?;
+            }
+
+            // Empty tuples still take up a byte.
+            Some(align_up(size.max(1), alignment(ty)))
+        }
+        _ => None,
+    }
+}
+
+/// Round `value` up to a multiple of `align`.
+fn align_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+/// Get the size of the framing offsets in a container of this size.
+fn offset_size(len: usize) -> usize {
+    match len {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFFFF_FFFF => 4,
+        _ => 8,
+    }
+}
+
+/// Read a little-endian framing offset.
+fn read_offset(data: &[u8], pos: usize, size: usize) -> Option<usize> {
+    let bytes = data.get(pos..pos + size)?;
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | byte as usize),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These were serialized by GLib's `g_variant_get_data()`.
+
+    #[test]
+    fn variants() {
+        let string = [
+            0x43, 0x61, 0x6e, 0x74, 0x61, 0x72, 0x65, 0x6c, 0x6c, 0x20, 0x31, 0x31, 0x00, 0x00,
+            0x73,
+        ];
+        let value = Variant::new("v", &string).unwrap_variant().unwrap();
+        assert_eq!(value.as_str(), Some("Cantarell 11"));
+        assert_eq!(value.as_u32(), None);
+
+        let int = [0xfd, 0xff, 0xff, 0xff, 0x00, 0x69];
+        let value = Variant::new("v", &int).unwrap_variant().unwrap();
+        assert_eq!(value.as_i32(), Some(-3));
+
+        let double = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf4, 0x3f, 0x00, 0x64];
+        let value = Variant::new("v", &double).unwrap_variant().unwrap();
+        assert_eq!(value.as_f64(), Some(1.25));
+
+        let boolean = [0x01, 0x00, 0x62];
+        let value = Variant::new("v", &boolean).unwrap_variant().unwrap();
+        assert_eq!(value.as_bool(), Some(true));
+    }
+
+    #[test]
+    fn bad_variants() {
+        // Not a variant, no type string, and more than one type.
+        assert!(Variant::new("s", b"a\0").unwrap_variant().is_none());
+        assert!(Variant::new("v", b"abc").unwrap_variant().is_none());
+        assert!(Variant::new("v", b"\x01\x00bb").unwrap_variant().is_none());
+
+        assert_eq!(Variant::new("s", b"abc").as_str(), None);
+        assert_eq!(Variant::new("u", &[1, 2]).as_u32(), None);
+    }
+
+    #[test]
+    fn tuples() {
+        // `('Adwaita', uint32 7, true, 'dark')`
+        let data = [
+            0x41, 0x64, 0x77, 0x61, 0x69, 0x74, 0x61, 0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0x64,
+            0x61, 0x72, 0x6b, 0x00, 0x08,
+        ];
+        let fields = Variant::new("(subs)", &data).fields().unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0].as_str(), Some("Adwaita"));
+        assert_eq!(fields[1].as_u32(), Some(7));
+        assert_eq!(fields[2].as_bool(), Some(true));
+        assert_eq!(fields[3].as_str(), Some("dark"));
+
+        // `(uint32 5, uint32 0, b'hello')`, a resource in a bundle.
+        let data = [
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x00,
+        ];
+        let fields = Variant::new("(uuay)", &data).fields().unwrap();
+        assert_eq!(fields[0].as_u32(), Some(5));
+        assert_eq!(fields[1].as_u32(), Some(0));
+        assert_eq!(fields[2].data(), b"hello\0");
+
+        // A framing offset past the end.
+        assert!(Variant::new("(ss)", b"a\0b\0\x09").fields().is_none());
+        assert!(Variant::new("s", b"a\0").fields().is_none());
+    }
+
+    #[test]
+    fn deep_types() {
+        let wrap = |ty: String| {
+            let mut data = b"\0".to_vec();
+            data.extend(ty.bytes());
+            data
+        };
+
+        // Types nested as deep as GLib allows are fine.
+        let ty = format!("{}s", "a".repeat(MAX_DEPTH));
+        let data = wrap(ty.clone());
+        let value = Variant::new("v", &data).unwrap_variant().unwrap();
+        assert_eq!(value.type_str(), ty);
+
+        // But deeper ones are turned down instead of overflowing the stack.
+        for ty in [
+            format!("{}s", "a".repeat(MAX_DEPTH + 1)),
+            format!("{}s{}", "(".repeat(500_000), ")".repeat(500_000)),
+            "a".repeat(500_000),
+            "m{".repeat(500_000),
+        ] {
+            let data = wrap(ty);
+            assert!(Variant::new("v", &data).unwrap_variant().is_none());
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A reader for GVDB files.
+//!
+//! GVDB is the on-disk hash table format that GLib uses for `gresource` bundles and dconf
+//! databases. Keys are strings and values are serialized `GVariant`s.
+
+use super::files;
+use super::gvariant::Variant;
+
+use std::io;
+use std::path::Path;
+
+/// The signature at the start of every little-endian GVDB file.
+const SIGNATURE: &[u8; 8] = b"GVariant";
+
+/// The size of the file header.
+const HEADER_SIZE: usize = 24;
+
+/// The size of an item in a hash table.
+const ITEM_SIZE: usize = 24;
+
+/// The parent index of items without a parent.
+const NO_PARENT: u32 = u32::MAX;
+
+/// An open GVDB file.
+pub(super) struct GvdbFile {
+    /// The contents of the file.
+    data: Vec<u8>,
+}
+
+impl GvdbFile {
+    /// Read a GVDB file from disk.
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(files::read(path)?)
+    }
+
+    /// Use the contents of a GVDB file.
+    pub(super) fn from_bytes(data: Vec<u8>) -> io::Result<Self> {
+        if data.len() < HEADER_SIZE || !data.starts_with(SIGNATURE) {
+            return Err(invalid("not a little-endian GVDB file"));
+        }
+
+        let file = Self { data };
+        file.root()?;
+        Ok(file)
+    }
+
+    /// Get the root hash table.
+    pub(super) fn root(&self) -> io::Result<Table<'_>> {
+        let start = read_u32(&self.data, 16).unwrap_or(0) as usize;
+        let end = read_u32(&self.data, 20).unwrap_or(0) as usize;
+
+        Table::new(&self.data, start, end).ok_or_else(|| invalid("corrupt GVDB hash table"))
+    }
+}
+
+/// A hash table in a GVDB file.
+#[derive(Clone, Copy)]
+pub(super) struct Table<'a> {
+    /// The entire file.
+    file: &'a [u8],
+
+    /// The hash buckets, as indices into `items`.
+    buckets: &'a [u8],
+
+    /// The items in the table.
+    items: &'a [u8],
+}
+
+/// An item in a hash table.
+struct Item<'a> {
+    /// The hash of the full key.
+    hash: u32,
+
+    /// The index of the parent item, whose key prefixes this one.
+    parent: u32,
+
+    /// This item's part of the key.
+    key: &'a [u8],
+
+    /// The kind of item: `v` for values, `H` for hash tables and `L` for lists.
+    kind: u8,
+
+    /// The position of the value in the file.
+    value: (usize, usize),
+}
+
+impl<'a> Table<'a> {
+    /// Parse the hash table between `start` and `end`.
+    fn new(file: &'a [u8], start: usize, end: usize) -> Option<Self> {
+        let table = file.get(start..end)?;
+
+        // The header is followed by the bloom filter, then the buckets, then the items.
+        let n_bloom_words = (read_u32(table, 0)? & ((1 << 27) - 1)) as usize;
+        let n_buckets = read_u32(table, 4)? as usize;
+
+        let buckets_start = 8 + n_bloom_words * 4;
+        let items_start = buckets_start + n_buckets * 4;
+        let items = table.get(items_start..)?;
+
+        Some(Self {
+            file,
+            buckets: table.get(buckets_start..items_start)?,
+            items: &items[..items.len() - items.len() % ITEM_SIZE],
+        })
+    }
+
+    /// Look up a value by its key.
+    pub(super) fn get(&self, key: &str) -> Option<Variant<'a>> {
+        let item = self.lookup(key)?;
+        if item.kind != b'v' {
+            return None;
+        }
+
+        Some(Variant::new(
+            "v",
+            self.file.get(item.value.0..item.value.1)?,
+        ))
+    }
+
+    /// Look up a nested hash table by its key.
+    pub(super) fn table(&self, key: &str) -> Option<Table<'a>> {
+        let item = self.lookup(key)?;
+        if item.kind != b'H' {
+            return None;
+        }
+
+        Table::new(self.file, item.value.0, item.value.1)
+    }
+
+    /// Check whether there is an item with a key.
+    pub(super) fn contains(&self, key: &str) -> bool {
+        self.lookup(key).is_some()
+    }
+
+    /// Find the item for a key.
+    fn lookup(&self, key: &str) -> Option<Item<'a>> {
+        let n_buckets = self.buckets.len() / 4;
+        let n_items = self.items.len() / ITEM_SIZE;
+        if n_buckets == 0 {
+            return None;
+        }
+
+        let hash = hash_key(key.as_bytes());
+        let bucket = hash as usize % n_buckets;
+        let start = read_u32(self.buckets, bucket * 4)? as usize;
+        let end = if bucket + 1 == n_buckets {
+            n_items
+        } else {
+            read_u32(self.buckets, (bucket + 1) * 4)? as usize
+        };
+
+        (start..end.min(n_items))
+            .filter_map(|index| self.item(index))
+            .find(|item| item.hash == hash && self.key_matches(item, key.as_bytes()))
+    }
+
+    /// Read the item at an index.
+    fn item(&self, index: usize) -> Option<Item<'a>> {
+        let item = self.items.get(index * ITEM_SIZE..(index + 1) * ITEM_SIZE)?;
+        let key_start = read_u32(item, 8)? as usize;
+        let key_size = u16::from_le_bytes([item[12], item[13]]) as usize;
+
+        Some(Item {
+            hash: read_u32(item, 0)?,
+            parent: read_u32(item, 4)?,
+            key: self.file.get(key_start..key_start + key_size)?,
+            kind: item[14],
+            value: (read_u32(item, 16)? as usize, read_u32(item, 20)? as usize),
+        })
+    }
+
+    /// Check whether the full key of an item, including its parents' keys, is `key`.
+    fn key_matches(&self, item: &Item<'_>, key: &[u8]) -> bool {
+        let mut key = match key.strip_suffix(item.key) {
+            Some(prefix) => prefix,
+            None => return false,
+        };
+        let mut parent = item.parent;
+
+        // A chain of parents can't be longer than the table, unless the file is corrupt and it
+        // loops back on itself.
+        for _ in 0..self.items.len() / ITEM_SIZE {
+            if parent == NO_PARENT {
+                return key.is_empty();
+            }
+
+            let item = match self.item(parent as usize) {
+                Some(item) => item,
+                None => return false,
+            };
+            key = match key.strip_suffix(item.key) {
+                Some(prefix) => prefix,
+                None => return false,
+            };
+            parent = item.parent;
+        }
+
+        false
+    }
+}
+
+/// Hash a key the way GVDB does.
+fn hash_key(key: &[u8]) -> u32 {
+    key.iter().fold(5381u32, |hash, &byte| {
+        hash.wrapping_mul(33).wrapping_add(byte as i8 as u32)
+    })
+}
+
+/// Read a little-endian `u32`.
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bundle written like `glib-compile-resources` does, with a directory item for each level.
+    const BUNDLE: &[u8] = include_bytes!("../../tests/data/theme.gresource");
+
+    #[test]
+    fn nested_keys() {
+        let file = GvdbFile::from_bytes(BUNDLE.to_vec()).unwrap();
+        let root = file.root().unwrap();
+
+        let value = root.get("/org/example/theme/gtk.css").unwrap();
+        assert_eq!(value.type_str(), "v");
+        assert_eq!(value.unwrap_variant().unwrap().type_str(), "(uuay)");
+
+        // Directories are lists rather than values.
+        assert!(root.contains("/org/example/"));
+        assert!(root.get("/org/example/").is_none());
+
+        assert!(root.get("/org/example/theme/missing.css").is_none());
+        assert!(root.get("gtk.css").is_none());
+        assert!(root.get("/org/example/theme/gtk.cs").is_none());
+    }
+
+    #[test]
+    fn not_gvdb() {
+        assert!(GvdbFile::from_bytes(b"GVariant".to_vec()).is_err());
+        assert!(GvdbFile::from_bytes(vec![0; HEADER_SIZE]).is_err());
+    }
+
+    #[test]
+    fn parent_cycle() {
+        // One item with an empty key that is its own parent, so that every step up the chain
+        // leaves the key the same.
+        let key_pos = (HEADER_SIZE + 8 + 4 + ITEM_SIZE) as u32;
+        let mut item = Vec::new();
+        item.extend(hash_key(b"key").to_le_bytes());
+        item.extend(0u32.to_le_bytes());
+        item.extend(key_pos.to_le_bytes());
+        item.extend([0, 0, b'v', 0]);
+        item.extend([0; 8]);
+
+        let mut data = SIGNATURE.to_vec();
+        data.extend([0; 8]);
+        data.extend((HEADER_SIZE as u32).to_le_bytes());
+        data.extend(key_pos.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(item);
+
+        let file = GvdbFile::from_bytes(data).unwrap();
+        assert!(!file.root().unwrap().contains("key"));
+    }
+}
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Icon lookup, following the freedesktop Icon Theme Specification.
+//!
+//! An icon theme is a directory of the same name in any of the base directories, split into
+//! subdirectories for each size that are described by its `index.theme`. Icons that the theme
+//! doesn't have are taken from the themes it inherits from, and then from `hicolor`.
+
+use super::index_theme::IndexTheme;
+use super::{context, files, xdg};
+
+use std::io;
+use std::path::PathBuf;
+
+/// The theme that every icon theme falls back to.
+pub(super) const FALLBACK: &str = "hicolor";
+
+/// The file types that icons can have, in the order they're checked.
+const EXTENSIONS: &[&str] = &["png", "svg", "xpm"];
+
+/// An icon theme, along with every theme that it inherits from.
+#[derive(Debug, Clone)]
+pub(crate) struct IconTheme {
+    /// The themes to look icons up in, in order.
+    chain: Vec<ThemeDirs>,
+
+    /// The base directories, where icons that aren't in any theme can be found.
+    base_dirs: Vec<PathBuf>,
+}
+
+/// The directories of one icon theme.
+#[derive(Debug, Clone)]
+struct ThemeDirs {
+    /// The name of the theme.
+    name: String,
+
+    /// The name of the theme, as shown to the user.
+    display_name: Option<String>,
+
+    /// The copies of the theme in each base directory that has one.
+    roots: Vec<PathBuf>,
+
+    /// The subdirectories that icons are in.
+    subdirs: Vec<SubDir>,
+}
+
+/// A subdirectory of an icon theme, holding icons of one size.
+#[derive(Debug, Clone)]
+struct SubDir {
+    /// The path of the subdirectory within the theme.
+    path: String,
+
+    /// The nominal size of the icons.
+    size: u32,
+
+    /// The scale that the icons are meant for.
+    scale: u32,
+
+    /// How the icons can be scaled.
+    kind: SizeKind,
+
+    /// The smallest size that scalable icons can be used at.
+    min_size: u32,
+
+    /// The largest size that scalable icons can be used at.
+    max_size: u32,
+
+    /// How far from their size thresholded icons can be used.
+    threshold: u32,
+}
+
+/// How the icons in a subdirectory can be scaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeKind {
+    /// The icons are only used at their size.
+    Fixed,
+
+    /// The icons can be used at any size between the minimum and maximum.
+    Scalable,
+
+    /// The icons can be used at sizes within the threshold of their own.
+    Threshold,
+}
+
+impl IconTheme {
+    /// Load an icon theme by its name.
+    ///
+    /// If the theme isn't installed, this only looks icons up in `hicolor`.
+    pub(super) fn load(name: &str) -> io::Result<Self> {
+        let base_dirs = base_dirs();
+        let mut chain = Vec::new();
+
+        add_theme(name, &base_dirs, &mut chain)?;
+        add_theme(FALLBACK, &base_dirs, &mut chain)?;
+
+        Ok(Self { chain, base_dirs })
+    }
+
+    /// Get the name of the theme.
+    pub(crate) fn name(&self) -> &str {
+        self.chain
+            .first()
+            .map_or(FALLBACK, |theme| theme.name.as_str())
+    }
+
+    /// Get the name of the theme, as shown to the user.
+    pub(crate) fn display_name(&self) -> Option<&str> {
+        self.chain.first()?.display_name.as_deref()
+    }
+
+    /// Find the file for an icon, at a size and scale.
+    pub(crate) fn find_icon(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        // Icon names are file names; don't let them point elsewhere.
+        if icon.is_empty() || icon.contains('/') || icon.starts_with('.') {
+            return None;
+        }
+
+        let scale = scale.max(1);
+        self.chain
+            .iter()
+            .find_map(|theme| theme.lookup(icon, size, scale))
+            .or_else(|| self.lookup_fallback(icon))
+    }
+
+    /// Look for an icon that isn't part of any theme.
+    fn lookup_fallback(&self, icon: &str) -> Option<PathBuf> {
+        self.base_dirs
+            .iter()
+            .flat_map(|dir| icon_files(dir.clone(), icon))
+            .find(|path| files::is_file(path))
+    }
+}
+
+impl ThemeDirs {
+    /// Look for an icon in this theme, preferring one made for the size and then the closest.
+    fn lookup(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        for subdir in self.subdirs.iter().filter(|s| s.matches(size, scale)) {
+            if let Some(path) = self.find_in(subdir, icon) {
+                return Some(path);
+            }
+        }
+
+        let mut closest: Option<(u32, PathBuf)> = None;
+        for subdir in &self.subdirs {
+            let distance = subdir.distance(size, scale);
+            if closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+
+            if let Some(path) = self.find_in(subdir, icon) {
+                closest = Some((distance, path));
+            }
+        }
+
+        closest.map(|(_, path)| path)
+    }
+
+    /// Look for an icon in one subdirectory, in any copy of the theme.
+    fn find_in(&self, subdir: &SubDir, icon: &str) -> Option<PathBuf> {
+        self.roots
+            .iter()
+            .flat_map(|root| icon_files(root.join(&subdir.path), icon))
+            .find(|path| files::is_file(path))
+    }
+}
+
+impl SubDir {
+    /// Read the description of a subdirectory out of the theme's index.
+    fn read(index: &IndexTheme, path: &str) -> Option<Self> {
+        let number = |key: &str| index.get_in(path, key)?.trim().parse::<u32>().ok();
+
+        let size = number("Size")?;
+        let kind = match index.get_in(path, "Type").map(str::trim) {
+            Some("Fixed") => SizeKind::Fixed,
+            Some("Scalable") => SizeKind::Scalable,
+            _ => SizeKind::Threshold,
+        };
+
+        Some(Self {
+            path: path.to_string(),
+            size,
+            scale: number("Scale").unwrap_or(1).max(1),
+            kind,
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+        })
+    }
+
+    /// Tell whether the icons in this subdirectory are meant for a size and scale.
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+
+        match self.kind {
+            SizeKind::Fixed => self.size == size,
+            SizeKind::Scalable => (self.min_size..=self.max_size).contains(&size),
+            SizeKind::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    /// Get how far the icons in this subdirectory are from a size and scale, in pixels.
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let (min, max) = match self.kind {
+            SizeKind::Fixed => (self.size, self.size),
+            SizeKind::Scalable => (self.min_size, self.max_size),
+            SizeKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+
+        // At most one side can be out of range.
+        let pixels = size * scale;
+        (min * self.scale).saturating_sub(pixels) + pixels.saturating_sub(max * self.scale)
+    }
+}
+
+/// Add a theme to the chain, followed by the themes it inherits from.
+///
+/// Themes that aren't installed, or are already in the chain, are skipped.
+fn add_theme(name: &str, base_dirs: &[PathBuf], chain: &mut Vec<ThemeDirs>) -> io::Result<()> {
+    if chain.iter().any(|theme| theme.name == name) {
+        return Ok(());
+    }
+
+    let roots = base_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .filter(|root| files::is_dir(root))
+        .collect::<Vec<_>>();
+
+    // The first copy with an index describes the theme.
+    let mut index = None;
+    for root in &roots {
+        index = IndexTheme::open(root)?;
+        if index.is_some() {
+            break;
+        }
+    }
+
+    let index = match index {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+
+    let mut subdirs = Vec::new();
+    for key in ["Directories", "ScaledDirectories"] {
+        for path in index.list_in("Icon Theme", key) {
+            if !subdirs.iter().any(|subdir: &SubDir| subdir.path == path) {
+                subdirs.extend(SubDir::read(&index, path));
+            }
+        }
+    }
+
+    chain.push(ThemeDirs {
+        name: name.to_string(),
+        display_name: index.name().map(str::to_string),
+        roots,
+        subdirs,
+    });
+
+    for parent in index.parents() {
+        add_theme(parent, base_dirs, chain)?;
+    }
+
+    Ok(())
+}
+
+/// Get the directories that icon themes are installed in, from the highest precedence to the
+/// lowest.
+pub(super) fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(data_home) = xdg::data_home() {
+        dirs.push(data_home.join("icons"));
+    }
+
+    if let Some(home_dir) = context::home_dir() {
+        dirs.push(home_dir.join(".icons"));
+    }
+
+    dirs.extend(xdg::data_dirs().map(|data_dir| data_dir.join("icons")));
+    dirs.push(context::path("/usr/share/pixmaps"));
+    dirs
+}
+
+/// Get the paths that an icon could have in a directory, one for each file type.
+fn icon_files(dir: PathBuf, icon: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    EXTENSIONS
+        .iter()
+        .map(move |extension| dir.join(format!("{}.{}", icon, extension)))
+}
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! The `index.theme` files that describe theme directories.
+//!
+//! GTK themes use the `[Desktop Entry]` and `[X-GNOME-Metatheme]` groups, icon and cursor themes
+//! use `[Icon Theme]`, and sound themes use `[Sound Theme]`. All of them are freedesktop key files.
+
+use super::context;
+use super::ini::{optional, KeyFile};
+use crate::ThemeMetadata;
+
+use std::io;
+use std::path::Path;
+
+/// The groups that describe the theme itself, in the order they're checked.
+const GROUPS: &[&str] = &[
+    "Desktop Entry",
+    "X-GNOME-Metatheme",
+    "Icon Theme",
+    "Sound Theme",
+];
+
+/// The group that points to the other themes that make up a desktop theme.
+const METATHEME: &str = "X-GNOME-Metatheme";
+
+/// A parsed `index.theme` file.
+#[derive(Debug)]
+pub(super) struct IndexTheme {
+    /// The underlying key file.
+    file: KeyFile,
+}
+
+impl IndexTheme {
+    /// Read the `index.theme` file in a theme directory, if there is one.
+    pub(super) fn open(theme_dir: &Path) -> io::Result<Option<Self>> {
+        let file = optional(KeyFile::open(&theme_dir.join("index.theme")))?;
+        Ok(file.map(|file| Self { file }))
+    }
+
+    /// Get the value of a key out of the first group that has it.
+    fn get(&self, key: &str) -> Option<&str> {
+        GROUPS.iter().find_map(|group| self.file.get(group, key))
+    }
+
+    /// Get the value of a key in a particular group.
+    pub(super) fn get_in(&self, group: &str, key: &str) -> Option<&str> {
+        self.file.get(group, key)
+    }
+
+    /// Get a comma-separated list out of a key in a particular group.
+    pub(super) fn list_in(&self, group: &str, key: &str) -> Vec<&str> {
+        self.file
+            .get(group, key)
+            .into_iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    /// Get a translatable value, in the user's language if there is a translation.
+    fn get_localized(&self, key: &str) -> Option<&str> {
+        locales()
+            .iter()
+            .find_map(|locale| self.get(&format!("{}[{}]", key, locale)))
+            .or_else(|| self.get(key))
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Get the name of the theme, as shown to the user.
+    pub(super) fn name(&self) -> Option<&str> {
+        self.get_localized("Name")
+    }
+
+    /// Get the description of the theme.
+    pub(super) fn comment(&self) -> Option<&str> {
+        self.get_localized("Comment")
+    }
+
+    /// Get the name of the icon theme that goes with this theme.
+    pub(super) fn icon_theme(&self) -> Option<&str> {
+        self.file
+            .get(METATHEME, "IconTheme")
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Get the name of the cursor theme that goes with this theme.
+    pub(super) fn cursor_theme(&self) -> Option<&str> {
+        self.file
+            .get(METATHEME, "CursorTheme")
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Get the themes that this one builds on, in the order they should be checked.
+    ///
+    /// These are listed in `Inherits`. A metatheme may also use a GTK theme of another name.
+    pub(super) fn parents(&self) -> Vec<&str> {
+        let mut parents = GROUPS
+            .iter()
+            .map(|group| self.list_in(group, "Inherits"))
+            .find(|parents| !parents.is_empty())
+            .unwrap_or_default();
+
+        if let Some(gtk_theme) = self.file.get(METATHEME, "GtkTheme") {
+            if !gtk_theme.is_empty() && !parents.contains(&gtk_theme) {
+                parents.push(gtk_theme);
+            }
+        }
+
+        parents
+    }
+
+    /// Get the metadata to expose on a theme.
+    pub(super) fn metadata(&self) -> ThemeMetadata {
+        let mut metadata = ThemeMetadata::new();
+        metadata.set_display_name(self.name().map(str::to_string));
+        metadata.set_description(self.comment().map(str::to_string));
+        metadata.set_icon_theme(self.icon_theme().map(str::to_string));
+        metadata.set_cursor_theme(self.cursor_theme().map(str::to_string));
+        metadata
+    }
+}
+
+/// Get the locale names to look translations up by, from the most specific to the least.
+///
+/// For `de_AT.UTF-8@euro`, these are `de_AT@euro`, `de_AT`, `de@euro` and `de`.
+pub(super) fn locales() -> Vec<String> {
+    let locale = match ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| context::var(name))
+        .find(|value| !value.is_empty())
+    {
+        Some(locale) => locale,
+        None => return Vec::new(),
+    };
+
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale.as_str(), None),
+    };
+    let locale = locale.split('.').next().unwrap_or(locale);
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut locales = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        locales.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        locales.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        locales.push(format!("{}@{}", lang, modifier));
+    }
+    locales.push(lang.to_string());
+
+    locales
+}
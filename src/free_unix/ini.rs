@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A parser for INI-style key files.
+//!
+//! This covers the flavors used by KDE's `kdeglobals` and color schemes, as well as the
+//! freedesktop key file format. Qt's `QSettings` writes the same layout, but quotes and escapes
+//! values differently.
+
+use super::files;
+use crate::util::HashMap;
+
+use std::io;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+/// A parsed key file.
+#[derive(Debug, Default)]
+pub(super) struct KeyFile {
+    /// The groups in the file, by name.
+    groups: HashMap<String, HashMap<String, String>>,
+}
+
+impl KeyFile {
+    /// Read a key file from disk.
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&files::read_to_string(path)?))
+    }
+
+    /// Read a file written by `QSettings` from disk.
+    pub(super) fn open_qsettings(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse_qsettings(&files::read_to_string(path)?))
+    }
+
+    /// Parse a key file.
+    ///
+    /// Lines that aren't groups, keys or comments are skipped.
+    pub(super) fn parse(text: &str) -> Self {
+        Self::parse_with(text, unescape)
+    }
+
+    /// Parse a file written by `QSettings`.
+    pub(super) fn parse_qsettings(text: &str) -> Self {
+        Self::parse_with(text, unescape_qsettings)
+    }
+
+    fn parse_with(text: &str, unescape: fn(&str) -> String) -> Self {
+        let mut file = Self::default();
+        let mut group = String::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            // KDE writes nested groups as `[Outer][Inner]`, which we keep as one name.
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                group = name.to_string();
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                file.groups
+                    .entry(group.clone())
+                    .or_default()
+                    .insert(strip_flags(key.trim()).to_string(), unescape(value.trim()));
+            }
+        }
+
+        file
+    }
+
+    /// Add the groups and keys of another file, replacing any that are already set.
+    pub(super) fn merge(&mut self, other: KeyFile) {
+        for (name, keys) in other.groups {
+            self.groups.entry(name).or_default().extend(keys);
+        }
+    }
+
+    /// Get the value of a key in a group.
+    pub(super) fn get(&self, group: &str, key: &str) -> Option<&str> {
+        self.groups.get(group)?.get(key).map(String::as_str)
+    }
+
+    /// Check whether the file has a group.
+    pub(super) fn has_group(&self, group: &str) -> bool {
+        self.groups.contains_key(group)
+    }
+}
+
+/// Remove KConfig flags like `[$e]` from a key.
+fn strip_flags(key: &str) -> &str {
+    match key.find("[$") {
+        Some(start) if key.ends_with(']') => key[..start].trim_end(),
+        _ => key,
+    }
+}
+
+/// Replace the escape sequences in a value.
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+/// Replace the quotes and escape sequences in a value written by `QSettings`.
+///
+/// Binary data is written out byte by byte, so each byte becomes one character.
+fn unescape_qsettings(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('a') => result.push('\u{7}'),
+                Some('b') => result.push('\u{8}'),
+                Some('f') => result.push('\u{c}'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some('v') => result.push('\u{b}'),
+                Some('x') => result.push(read_code(&mut chars, 16)),
+                Some('0') => result.push(read_code(&mut chars, 8)),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            },
+            ';' if !quoted => break,
+            c => result.push(c),
+        }
+    }
+
+    result.trim_end().to_string()
+}
+
+/// Read a character code written out in some radix, for as long as there are digits.
+fn read_code(chars: &mut Peekable<Chars<'_>>, radix: u32) -> char {
+    let mut code = 0u32;
+
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(radix)) {
+        code = code.saturating_mul(radix).saturating_add(digit);
+        chars.next();
+    }
+
+    char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Treat a file that doesn't exist or that we can't read as missing.
+pub(super) fn optional<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) => match err.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => Ok(None),
+            _ => Err(err),
+        },
+    }
+}
@@ -0,0 +1,727 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Code for loading a KDE color scheme.
+//!
+//! Plasma keeps the active color scheme in `kdeglobals`, in the same format as the `.colors`
+//! files that the schemes are installed as. The colors are split into sets, each of which has a
+//! background, a foreground and a few decorations. Disabled and inactive widgets use the same sets
+//! with an effect applied to them.
+//!
+//! The fonts are always read from `kdeglobals`, even when another scheme is asked for.
+
+use super::ini::{optional, KeyFile};
+use super::palette::{self, ColorGroup};
+use super::qt_font::QtFont;
+use super::{files, xdg};
+use crate::{
+    Color, ContrastPreference, LoadThemeError, ShadePreference, Theme, ThemeInfo, ThemeSource,
+    Widget, WidgetState, WIDGETS, WIDGET_STATES,
+};
+
+use std::io;
+
+/// The groups that hold the color sets.
+const COLOR_GROUPS: &[&str] = &[
+    "Colors:Window",
+    "Colors:View",
+    "Colors:Button",
+    "Colors:Selection",
+    "Colors:Tooltip",
+    "Colors:Header",
+];
+
+/// Load a KDE color scheme.
+///
+/// If no name is given, the scheme that is currently active in `kdeglobals` is loaded.
+pub(super) fn load_theme(name: Option<&str>) -> Result<Option<Theme>, LoadThemeError> {
+    let globals = read_kdeglobals().map_err(LoadThemeError)?;
+
+    // Find the file for the color scheme.
+    let scheme_name = name
+        .or_else(|| globals.get("General", "ColorScheme"))
+        .map(str::to_string);
+    let mut colors = match &scheme_name {
+        Some(scheme_name) => match read_color_scheme(scheme_name).map_err(LoadThemeError)? {
+            Some(colors) => colors,
+            None if name.is_some() => return Ok(None),
+            None => KeyFile::default(),
+        },
+        None => KeyFile::default(),
+    };
+
+    let fonts = Fonts::read(&globals).map_err(LoadThemeError)?;
+    let display_name = colors.get("General", "Name").map(str::to_string);
+
+    // Plasma copies the active scheme into `kdeglobals`, along with any changes the user made.
+    if name.is_none() {
+        colors.merge(globals);
+    }
+
+    if !COLOR_GROUPS.iter().any(|group| colors.has_group(group)) {
+        return Ok(None);
+    }
+
+    let scheme = Scheme::read(&colors);
+    let shade = match scheme.window.background.luminance() < 0.5 {
+        true => ShadePreference::Dark,
+        false => ShadePreference::Light,
+    };
+
+    let mut theme = Theme::default_theme(shade);
+    theme.set_name(scheme_name.as_deref().unwrap_or("Breeze"));
+    theme.metadata_mut().set_display_name(display_name);
+    scheme.apply(&mut theme);
+    fonts.apply(&mut theme);
+
+    Ok(Some(theme))
+}
+
+/// Get the contrast preference implied by the active color scheme.
+///
+/// Plasma has no setting of its own for high contrast; instead, the user picks one of the high
+/// contrast color schemes, which are named as such.
+pub(super) fn contrast_preference() -> io::Result<Option<ContrastPreference>> {
+    let globals = read_kdeglobals()?;
+
+    Ok(globals
+        .get("General", "ColorScheme")
+        .filter(|name| super::is_high_contrast_name(name))
+        .map(|_| ContrastPreference::High))
+}
+
+/// Get the name of the icon theme that the user picked.
+pub(super) fn icon_theme() -> io::Result<Option<String>> {
+    let globals = read_kdeglobals()?;
+    Ok(globals.get("Icons", "Theme").map(str::to_string))
+}
+
+/// Get the name of the sound theme that the user picked, and whether sounds are enabled.
+pub(super) fn sound_theme() -> io::Result<(Option<String>, Option<bool>)> {
+    let globals = read_kdeglobals()?;
+    let name = globals
+        .get("Sounds", "Theme")
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    let enabled = match globals.get("Sounds", "Enable").map(str::trim) {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    };
+
+    Ok((name, enabled))
+}
+
+/// Get the name and size of the cursor theme that the user picked.
+pub(super) fn cursor_theme() -> io::Result<(Option<String>, Option<u32>)> {
+    let input = read_config("kcminputrc")?;
+    let name = input
+        .get("Mouse", "cursorTheme")
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    let size = input
+        .get("Mouse", "cursorSize")
+        .and_then(|size| size.trim().parse().ok())
+        .filter(|size| *size > 0);
+
+    Ok((name, size))
+}
+
+/// List the color schemes that are installed.
+///
+/// A scheme that is installed in more than one place is listed once, for the file that it would be
+/// loaded from.
+pub(super) fn available() -> io::Result<Vec<ThemeInfo>> {
+    let mut schemes: Vec<ThemeInfo> = Vec::new();
+
+    for dir in xdg::data_home().into_iter().chain(xdg::data_dirs()) {
+        let entries = match optional(files::read_dir(&dir.join("color-schemes")))? {
+            Some(entries) => entries,
+            None => continue,
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            let name = match path.file_stem().and_then(|name| name.to_str()) {
+                Some(name) if path.extension().is_some_and(|ext| ext == "colors") => name,
+                _ => continue,
+            };
+
+            if schemes.iter().any(|scheme| scheme.name() == name) {
+                continue;
+            }
+
+            let file = match optional(KeyFile::open(&path))? {
+                Some(file) => file,
+                None => continue,
+            };
+
+            // Each scheme is either light or dark.
+            let dark = Scheme::read(&file).window.background.luminance() < 0.5;
+            schemes.push(ThemeInfo::new(
+                name,
+                ThemeSource::Kde,
+                Some(path.clone()),
+                !dark,
+                dark,
+            ));
+        }
+    }
+
+    schemes.sort_by(|a, b| a.name().cmp(b.name()));
+    Ok(schemes)
+}
+
+/// Read `kdeglobals` out of every configuration directory, with the user's taking precedence.
+fn read_kdeglobals() -> io::Result<KeyFile> {
+    read_config("kdeglobals")
+}
+
+/// Read a configuration file out of every configuration directory, with the user's taking
+/// precedence.
+fn read_config(name: &str) -> io::Result<KeyFile> {
+    let mut config = KeyFile::default();
+    let config_dirs = xdg::config_dirs().collect::<Vec<_>>();
+
+    for dir in config_dirs.into_iter().rev().chain(xdg::config_home()) {
+        if let Some(file) = optional(KeyFile::open(&dir.join(name)))? {
+            config.merge(file);
+        }
+    }
+
+    Ok(config)
+}
+
+/// Find an installed color scheme by its name.
+fn read_color_scheme(name: &str) -> io::Result<Option<KeyFile>> {
+    let file_name = format!("color-schemes/{}.colors", name);
+
+    for dir in xdg::data_home().into_iter().chain(xdg::data_dirs()) {
+        if let Some(file) = optional(KeyFile::open(&dir.join(&file_name)))? {
+            return Ok(Some(file));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The DPI that font sizes are converted to pixels at, unless the user forces another one.
+const DEFAULT_DPI: f32 = 96.0;
+
+/// The fonts set in `kdeglobals`.
+struct Fonts {
+    /// The font used for most widgets.
+    general: Option<QtFont>,
+
+    /// The font used for menus.
+    menu: Option<QtFont>,
+
+    /// The font used for toolbars.
+    toolbar: Option<QtFont>,
+
+    /// The font used for small text, like status bars.
+    smallest: Option<QtFont>,
+
+    /// The fixed width font.
+    fixed: Option<QtFont>,
+
+    /// The DPI to convert point sizes at.
+    dpi: f32,
+}
+
+impl Fonts {
+    /// Read the fonts out of `kdeglobals`.
+    fn read(globals: &KeyFile) -> io::Result<Self> {
+        let font = |key| globals.get("General", key).and_then(QtFont::parse);
+
+        // The font settings module can force a DPI.
+        let mut dpi = DEFAULT_DPI;
+        if let Some(config_home) = xdg::config_home() {
+            if let Some(kcmfonts) = optional(KeyFile::open(&config_home.join("kcmfonts")))? {
+                if let Some(forced) = kcmfonts
+                    .get("General", "forceFontDPI")
+                    .and_then(|dpi| dpi.trim().parse::<f32>().ok())
+                    .filter(|dpi| *dpi > 0.0)
+                {
+                    dpi = forced;
+                }
+            }
+        }
+
+        Ok(Self {
+            general: font("font"),
+            menu: font("menuFont"),
+            toolbar: font("toolBarFont"),
+            smallest: font("smallestReadableFont"),
+            fixed: font("fixed"),
+            dpi,
+        })
+    }
+
+    /// Apply the fonts on top of a theme.
+    fn apply(&self, theme: &mut Theme) {
+        for widget in WIDGETS {
+            let font = match widget {
+                widget if palette::is_menu(*widget) => self.menu.as_ref(),
+                Widget::ToolbarButton
+                | Widget::ToolbarDropdownButton
+                | Widget::ToolbarSeparator => self.toolbar.as_ref(),
+                Widget::Taskbar => self.smallest.as_ref(),
+                Widget::Editor => self.fixed.as_ref(),
+                _ => None,
+            };
+            let font = match font.or(self.general.as_ref()) {
+                Some(font) => font,
+                None => continue,
+            };
+
+            font.apply_to_widget(theme, *widget, self.dpi);
+        }
+    }
+}
+
+/// A full KDE color scheme.
+struct Scheme {
+    window: ColorSet,
+    view: ColorSet,
+    button: ColorSet,
+    selection: ColorSet,
+    tooltip: ColorSet,
+    header: ColorSet,
+    disabled: StateEffect,
+    inactive: StateEffect,
+}
+
+impl Scheme {
+    /// Read the scheme out of a color scheme file.
+    fn read(file: &KeyFile) -> Self {
+        let window = ColorSet::read(file, "Colors:Window", &BREEZE_WINDOW);
+
+        Self {
+            view: ColorSet::read(file, "Colors:View", &window),
+            button: ColorSet::read(file, "Colors:Button", &window),
+            selection: ColorSet::read(file, "Colors:Selection", &BREEZE_SELECTION),
+            tooltip: ColorSet::read(file, "Colors:Tooltip", &window),
+            header: ColorSet::read(file, "Colors:Header", &window),
+            disabled: StateEffect::read(file, "ColorEffects:Disabled", &DISABLED_EFFECT),
+            inactive: StateEffect::read(file, "ColorEffects:Inactive", &INACTIVE_EFFECT),
+            window,
+        }
+    }
+
+    /// Apply the scheme's colors on top of a theme.
+    fn apply(&self, theme: &mut Theme) {
+        for widget in WIDGETS {
+            for state in WIDGET_STATES {
+                palette::set_colors(theme, *widget, *state, self.colors(*widget, *state));
+            }
+        }
+    }
+
+    /// Get the background, foreground and border colors of a widget in a state.
+    fn colors(&self, widget: Widget, state: WidgetState) -> (Color, Color, Color) {
+        let set = self.set(widget);
+        let mut background = match widget {
+            Widget::ProgressBarChunk => self.selection.background,
+            _ => set.background,
+        };
+        let mut foreground = match widget {
+            Widget::TextHyperlink => set.link,
+            _ => set.foreground,
+        };
+
+        let border = match state {
+            WidgetState::Enabled => background.mix_by(foreground, 0.3),
+            WidgetState::Selected | WidgetState::Checked => {
+                background = self.selection.background;
+                foreground = self.selection.foreground;
+                set.focus
+            }
+            WidgetState::Pressed => {
+                background = background.mix_by(self.selection.background, 0.3);
+                set.focus
+            }
+            WidgetState::Hovered => {
+                if palette::is_item(widget) {
+                    background = background.mix_by(set.hover, 0.3);
+                }
+                set.hover
+            }
+            WidgetState::Focused => set.focus,
+            WidgetState::Disabled | WidgetState::Inactive => {
+                let effect = match state {
+                    WidgetState::Disabled => &self.disabled,
+                    _ => &self.inactive,
+                };
+
+                (background, foreground) = effect.apply(background, foreground);
+                background.mix_by(foreground, 0.3)
+            }
+        };
+
+        (background, foreground, border)
+    }
+
+    /// Get the color set that a widget is drawn with.
+    fn set(&self, widget: Widget) -> &ColorSet {
+        match ColorGroup::of(widget) {
+            ColorGroup::Window => &self.window,
+            ColorGroup::View => &self.view,
+            ColorGroup::Button => &self.button,
+            ColorGroup::Tooltip => &self.tooltip,
+            ColorGroup::Header => &self.header,
+        }
+    }
+}
+
+/// One of the sets of colors in a scheme.
+struct ColorSet {
+    /// The `BackgroundNormal` color.
+    background: Color,
+
+    /// The `ForegroundNormal` color.
+    foreground: Color,
+
+    /// The `ForegroundLink` color.
+    link: Color,
+
+    /// The `DecorationFocus` color.
+    focus: Color,
+
+    /// The `DecorationHover` color.
+    hover: Color,
+}
+
+/// The window colors of Breeze, for schemes that leave them out.
+const BREEZE_WINDOW: ColorSet = ColorSet {
+    background: Color::new(239, 240, 241, 255),
+    foreground: Color::new(35, 38, 39, 255),
+    link: Color::new(41, 128, 185, 255),
+    focus: Color::new(61, 174, 233, 255),
+    hover: Color::new(147, 206, 233, 255),
+};
+
+/// The selection colors of Breeze, for schemes that leave them out.
+const BREEZE_SELECTION: ColorSet = ColorSet {
+    background: Color::new(61, 174, 233, 255),
+    foreground: Color::new(255, 255, 255, 255),
+    link: Color::new(253, 188, 75, 255),
+    focus: Color::new(61, 174, 233, 255),
+    hover: Color::new(147, 206, 233, 255),
+};
+
+impl ColorSet {
+    /// Read a color set, falling back to another set for the colors that aren't there.
+    fn read(file: &KeyFile, group: &str, fallback: &ColorSet) -> Self {
+        let color = |key, fallback| {
+            file.get(group, key)
+                .and_then(parse_color)
+                .unwrap_or(fallback)
+        };
+
+        Self {
+            background: color("BackgroundNormal", fallback.background),
+            foreground: color("ForegroundNormal", fallback.foreground),
+            link: color("ForegroundLink", fallback.link),
+            focus: color("DecorationFocus", fallback.focus),
+            hover: color("DecorationHover", fallback.hover),
+        }
+    }
+}
+
+/// The effect applied to colors when the widget is in a certain state.
+struct StateEffect {
+    /// Whether the effect is applied at all.
+    enabled: bool,
+
+    /// Shade, darken or lighten the colors.
+    intensity_effect: u8,
+    intensity_amount: f32,
+
+    /// Desaturate the colors, or fade or tint them towards `color`.
+    color_effect: u8,
+    color_amount: f32,
+    color: Color,
+
+    /// Fade or tint the foreground towards the background.
+    contrast_effect: u8,
+    contrast_amount: f32,
+}
+
+/// The effect KDE applies to disabled widgets by default.
+const DISABLED_EFFECT: StateEffect = StateEffect {
+    enabled: true,
+    intensity_effect: 2,
+    intensity_amount: 0.1,
+    color_effect: 0,
+    color_amount: 0.0,
+    color: Color::new(56, 56, 56, 255),
+    contrast_effect: 1,
+    contrast_amount: 0.65,
+};
+
+/// The effect KDE applies to inactive windows by default.
+const INACTIVE_EFFECT: StateEffect = StateEffect {
+    enabled: false,
+    intensity_effect: 0,
+    intensity_amount: 0.0,
+    color_effect: 2,
+    color_amount: 0.025,
+    color: Color::new(112, 111, 110, 255),
+    contrast_effect: 2,
+    contrast_amount: 0.1,
+};
+
+impl StateEffect {
+    /// Read an effect, falling back to the defaults for keys that aren't there.
+    fn read(file: &KeyFile, group: &str, default: &StateEffect) -> Self {
+        let number = |key| file.get(group, key).and_then(|v| v.trim().parse().ok());
+        let amount = |key| file.get(group, key).and_then(|v| v.trim().parse().ok());
+
+        Self {
+            enabled: file
+                .get(group, "Enable")
+                .map_or(default.enabled, |v| v.trim() == "true"),
+            intensity_effect: number("IntensityEffect").unwrap_or(default.intensity_effect),
+            intensity_amount: amount("IntensityAmount").unwrap_or(default.intensity_amount),
+            color_effect: number("ColorEffect").unwrap_or(default.color_effect),
+            color_amount: amount("ColorAmount").unwrap_or(default.color_amount),
+            color: file
+                .get(group, "Color")
+                .and_then(parse_color)
+                .unwrap_or(default.color),
+            contrast_effect: number("ContrastEffect").unwrap_or(default.contrast_effect),
+            contrast_amount: amount("ContrastAmount").unwrap_or(default.contrast_amount),
+        }
+    }
+
+    /// Apply the effect to a background and foreground.
+    fn apply(&self, background: Color, foreground: Color) -> (Color, Color) {
+        if !self.enabled {
+            return (background, foreground);
+        }
+
+        // The contrast is reduced against the original background.
+        let foreground = match self.contrast_effect {
+            1 | 2 => foreground.mix_by(background, self.contrast_amount),
+            _ => foreground,
+        };
+
+        (self.adjust(background), self.adjust(foreground))
+    }
+
+    /// Apply the intensity and color effects to a color.
+    fn adjust(&self, color: Color) -> Color {
+        let amount = self.intensity_amount;
+        let color = match self.intensity_effect {
+            1 => color.map_lightness(|l, s| (l + amount, s)),
+            2 => color.map_lightness(|l, s| (l * (1.0 - amount), s)),
+            3 => color.map_lightness(|l, s| (1.0 - (1.0 - l) * (1.0 - amount), s)),
+            _ => color,
+        };
+
+        match self.color_effect {
+            1 => color.map_lightness(|l, s| (l, s * (1.0 - self.color_amount))),
+            2 | 3 => color.mix_by(self.color, self.color_amount),
+            _ => color,
+        }
+    }
+}
+
+/// Parse a color, either as `r,g,b[,a]` or `#rrggbb`.
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::new(channel(0)?, channel(2)?, channel(4)?, 255));
+    }
+
+    let mut channels = value.split(',').map(|c| c.trim().parse::<u8>().ok());
+    let r = channels.next()??;
+    let g = channels.next()??;
+    let b = channels.next()??;
+    let a = match channels.next() {
+        Some(a) => a?,
+        None => 255,
+    };
+
+    match channels.next() {
+        Some(_) => None,
+        None => Some(Color::new(r, g, b, a)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scheme that leaves most colors out, with a disabled effect that is turned off and an
+    /// inactive effect that tints everything red.
+    const SCHEME: &str = "\
+[Colors:Window]
+BackgroundNormal=16,24,32
+ForegroundNormal=#f0e0d0
+
+[Colors:Button]
+BackgroundNormal=48,56,64
+DecorationFocus=1,2,3
+
+[Colors:Selection]
+BackgroundNormal=200,100,0
+ForegroundNormal=0,0,0
+
+[ColorEffects:Disabled]
+Enable=false
+
+[ColorEffects:Inactive]
+Enable=true
+ColorEffect=2
+ColorAmount=1
+Color=255,0,0
+ContrastEffect=0
+";
+
+    const RED: Color = Color::new(255, 0, 0, 255);
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("1, 2, 3"), Some(Color::new(1, 2, 3, 255)));
+        assert_eq!(parse_color("1,2,3,4"), Some(Color::new(1, 2, 3, 4)));
+        assert_eq!(parse_color(" #0a0B0c "), Some(Color::new(10, 11, 12, 255)));
+
+        for value in ["", "1,2", "1,2,3,4,5", "256,0,0", "1,,3", "#abc", "#gg0000"] {
+            assert_eq!(parse_color(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn fallbacks() {
+        let scheme = Scheme::read(&KeyFile::parse(SCHEME));
+
+        // The window falls back to Breeze, and the other sets to the window.
+        assert_eq!(scheme.window.background, Color::new(16, 24, 32, 255));
+        assert_eq!(scheme.window.foreground, Color::new(0xf0, 0xe0, 0xd0, 255));
+        assert_eq!(scheme.window.link, BREEZE_WINDOW.link);
+        assert_eq!(scheme.view.background, scheme.window.background);
+        assert_eq!(scheme.button.background, Color::new(48, 56, 64, 255));
+        assert_eq!(scheme.button.foreground, scheme.window.foreground);
+        assert_eq!(scheme.button.focus, Color::new(1, 2, 3, 255));
+
+        // The selection falls back to Breeze's rather than the window's.
+        assert_eq!(scheme.selection.background, Color::new(200, 100, 0, 255));
+        assert_eq!(scheme.selection.hover, BREEZE_SELECTION.hover);
+    }
+
+    #[test]
+    fn states() {
+        let scheme = Scheme::read(&KeyFile::parse(SCHEME));
+        let selection = Color::new(200, 100, 0, 255);
+
+        // Selected widgets take the selection colors, with the focus decoration around them.
+        assert_eq!(
+            scheme.colors(Widget::Button, WidgetState::Selected),
+            (
+                selection,
+                Color::new(0, 0, 0, 255),
+                Color::new(1, 2, 3, 255)
+            )
+        );
+        assert_eq!(
+            scheme
+                .colors(Widget::ProgressBarChunk, WidgetState::Enabled)
+                .0,
+            selection
+        );
+        assert_eq!(
+            scheme.colors(Widget::TextHyperlink, WidgetState::Enabled).1,
+            scheme.set(Widget::TextHyperlink).link
+        );
+
+        // The disabled effect is turned off, and the inactive one replaces every color.
+        assert_eq!(
+            scheme.colors(Widget::Button, WidgetState::Disabled),
+            scheme.colors(Widget::Button, WidgetState::Enabled)
+        );
+        assert_eq!(
+            scheme.colors(Widget::Button, WidgetState::Inactive),
+            (RED, RED, RED)
+        );
+    }
+
+    #[test]
+    fn default_effects() {
+        let background = Color::new(239, 240, 241, 255);
+        let foreground = Color::new(35, 38, 39, 255);
+
+        // Inactive windows look the same unless the scheme turns the effect on.
+        assert_eq!(
+            INACTIVE_EFFECT.apply(background, foreground),
+            (background, foreground)
+        );
+
+        // Disabled widgets are darker, with the text faded towards the background.
+        let (disabled_background, disabled_foreground) =
+            DISABLED_EFFECT.apply(background, foreground);
+        assert!(disabled_background.luminance() < background.luminance());
+        assert!(disabled_foreground.luminance() > foreground.luminance());
+    }
+
+    #[test]
+    fn fonts() {
+        let font = |desc| QtFont::parse(desc);
+        let fonts = Fonts {
+            general: font("Noto Sans,10,-1,5,50,0,0,0,0,0"),
+            menu: font("Noto Sans,9,-1,5,50,1,0,0,0,0"),
+            toolbar: None,
+            smallest: font("Noto Sans,8,-1,5,50,0,0,0,0,0"),
+            fixed: font("Hack,-1,14,5,50,0,0,0,0,0"),
+            dpi: 144.0,
+        };
+        let mut theme = Theme::default_theme(ShadePreference::Light);
+        fonts.apply(&mut theme);
+
+        let text = |widget| {
+            let props = theme.get(widget, WidgetState::Enabled);
+            let text = props.text_style().unwrap();
+            (text.family().clone(), text.size(), text.italic())
+        };
+        let noto = || crate::FontFamily::Custom("Noto Sans".into());
+
+        // Point sizes go by the DPI, while pixel sizes stay as they are.
+        assert_eq!(text(Widget::Button), (noto(), 20.0, false));
+        assert_eq!(text(Widget::Taskbar), (noto(), 16.0, false));
+        assert_eq!(
+            text(Widget::Editor),
+            (crate::FontFamily::Custom("Hack".into()), 14.0, false)
+        );
+
+        // Toolbars fall back to the general font.
+        assert_eq!(text(Widget::ToolbarButton), text(Widget::Button));
+
+        // Menus get the menu font, for the menu text as well.
+        assert_eq!(text(Widget::PopupMenuItem), (noto(), 18.0, true));
+        let props = theme.get(Widget::MenuBarItem, WidgetState::Enabled);
+        let menu_text = props.menu_text_style().unwrap();
+        assert_eq!((menu_text.size(), menu_text.italic()), (18.0, true));
+    }
+}
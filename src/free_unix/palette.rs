@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Building themes out of color palettes.
+//!
+//! Toolkits like Qt describe their colors as a palette of roles rather than per widget. This maps
+//! those roles onto our widgets.
+
+use crate::{Color, ShadePreference, Theme, Widget, WidgetState, WIDGETS};
+
+/// The group of colors that a widget is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ColorGroup {
+    /// The window itself, and everything without a group of its own.
+    Window,
+
+    /// Views of content, like lists and text editors.
+    View,
+
+    /// Buttons and other controls.
+    Button,
+
+    /// Tooltips.
+    Tooltip,
+
+    /// Headers, like title text and menu bars.
+    Header,
+}
+
+impl ColorGroup {
+    /// Get the group that a widget belongs to.
+    pub(super) fn of(widget: Widget) -> Self {
+        match widget {
+            Widget::DateTimePicker
+            | Widget::Editor
+            | Widget::ListView
+            | Widget::ListViewItem
+            | Widget::TextBody => Self::View,
+
+            Widget::Button
+            | Widget::Checkbox
+            | Widget::RadioButton
+            | Widget::ComboBox
+            | Widget::ComboBoxButton
+            | Widget::ListViewExpandButton
+            | Widget::NavigationBack
+            | Widget::NavigationForward
+            | Widget::NavigationMenu
+            | Widget::NavigationPageDown
+            | Widget::NavigationPageUp
+            | Widget::ScrollBarHandle
+            | Widget::SpinnerDown
+            | Widget::SpinnerUp
+            | Widget::TabItem
+            | Widget::ToolbarButton
+            | Widget::ToolbarDropdownButton => Self::Button,
+
+            Widget::TooltipBalloon | Widget::TooltipBalloonStem => Self::Tooltip,
+
+            Widget::MenuBar | Widget::MenuBarItem | Widget::TextTitle => Self::Header,
+
+            _ => Self::Window,
+        }
+    }
+}
+
+/// Tell whether a widget is an item in a list or menu, which is highlighted on hover.
+pub(super) fn is_item(widget: Widget) -> bool {
+    matches!(
+        widget,
+        Widget::ListViewItem | Widget::MenuBarItem | Widget::PopupMenuItem
+    )
+}
+
+/// Tell whether a widget is part of a menu.
+pub(super) fn is_menu(widget: Widget) -> bool {
+    matches!(
+        widget,
+        Widget::MenuBar
+            | Widget::MenuBarItem
+            | Widget::PopupMenu
+            | Widget::PopupMenuItem
+            | Widget::MenuSeparator
+    )
+}
+
+/// Set the background, text and border colors of a widget in a state.
+///
+/// The border color is only used if the widget already has a border.
+pub(super) fn set_colors(
+    theme: &mut Theme,
+    widget: Widget,
+    state: WidgetState,
+    (background, foreground, border_color): (Color, Color, Color),
+) {
+    let props = theme.get_mut(widget, state);
+
+    props.set_background(background);
+
+    if let Some(mut text) = props.text_style().cloned() {
+        text.set_color(foreground);
+        props.set_text_style(text);
+    }
+
+    if let Some(mut border) = props.border().cloned() {
+        border.set_color(border_color);
+        props.set_border(border);
+    }
+}
+
+/// The colors of one group of a palette, like the active or disabled colors.
+#[derive(Debug, Clone)]
+pub(super) struct Palette {
+    /// The background of windows.
+    pub(super) window: Color,
+
+    /// The text on windows.
+    pub(super) window_text: Color,
+
+    /// The background of views.
+    pub(super) base: Color,
+
+    /// The text in views.
+    pub(super) text: Color,
+
+    /// The background of buttons.
+    pub(super) button: Color,
+
+    /// The text on buttons.
+    pub(super) button_text: Color,
+
+    /// The background of selected items.
+    pub(super) highlight: Color,
+
+    /// The text on selected items.
+    pub(super) highlighted_text: Color,
+
+    /// The background of tooltips.
+    pub(super) tooltip_base: Color,
+
+    /// The text in tooltips.
+    pub(super) tooltip_text: Color,
+
+    /// The color of hyperlinks.
+    pub(super) link: Color,
+}
+
+impl Palette {
+    /// Tell whether this is a light or a dark palette.
+    pub(super) fn shade(&self) -> ShadePreference {
+        match self.window.luminance() < 0.5 {
+            true => ShadePreference::Dark,
+            false => ShadePreference::Light,
+        }
+    }
+
+    /// Apply the palette to widgets in the given states.
+    pub(super) fn apply(&self, theme: &mut Theme, states: &[WidgetState]) {
+        for widget in WIDGETS {
+            for state in states {
+                set_colors(theme, *widget, *state, self.colors(*widget, *state));
+            }
+        }
+    }
+
+    /// Get the background, foreground and border colors of a widget in a state.
+    fn colors(&self, widget: Widget, state: WidgetState) -> (Color, Color, Color) {
+        let (mut background, mut foreground) = match ColorGroup::of(widget) {
+            ColorGroup::View => (self.base, self.text),
+            ColorGroup::Button => (self.button, self.button_text),
+            ColorGroup::Tooltip => (self.tooltip_base, self.tooltip_text),
+            ColorGroup::Window | ColorGroup::Header => (self.window, self.window_text),
+        };
+
+        match widget {
+            Widget::ProgressBarChunk => background = self.highlight,
+            Widget::TextHyperlink => foreground = self.link,
+            _ => {}
+        }
+
+        let border = match state {
+            WidgetState::Selected | WidgetState::Checked => {
+                background = self.highlight;
+                foreground = self.highlighted_text;
+                self.highlight
+            }
+            WidgetState::Pressed => {
+                background = background.mix_by(self.highlight, 0.3);
+                self.highlight
+            }
+            WidgetState::Hovered => {
+                if is_item(widget) {
+                    background = background.mix_by(self.highlight, 0.2);
+                }
+                background.mix_by(self.highlight, 0.5)
+            }
+            WidgetState::Focused => self.highlight,
+            WidgetState::Enabled | WidgetState::Disabled | WidgetState::Inactive => {
+                background.mix_by(foreground, 0.3)
+            }
+        };
+
+        (background, foreground, border)
+    }
+}
@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A parser for Pango font descriptions, like `Cantarell Bold Italic 11`.
+//!
+//! These are a comma-separated list of families, followed by words for the style, weight, stretch,
+//! variant and gravity, then the size. The size is in points, or in pixels with a `px` suffix.
+//! Font variations and features may come last, starting with `@` and `#` respectively.
+
+use crate::text::{FontFamily, FontStretch, TextStyle};
+
+/// A font read from a Pango font description.
+///
+/// Fields that the description leaves out are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct PangoFont {
+    /// The first font family.
+    family: Option<String>,
+
+    /// The size of the font, either in points or in pixels.
+    size: Option<FontSize>,
+
+    /// The weight, on the CSS scale.
+    weight: Option<u16>,
+
+    /// The font is italic or oblique.
+    italic: Option<bool>,
+
+    /// The width of the font.
+    stretch: Option<FontStretch>,
+}
+
+/// The size of a Pango font.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FontSize {
+    /// The size is in points.
+    Points(f32),
+
+    /// The size is in pixels.
+    Pixels(f32),
+}
+
+/// The number of pixels in a point, at the 96 DPI that GTK assumes.
+const PIXELS_PER_POINT: f32 = 96.0 / 72.0;
+
+impl PangoFont {
+    /// Parse a font description.
+    ///
+    /// Returns `None` if the description is empty.
+    pub(super) fn parse(desc: &str) -> Option<Self> {
+        let mut font = Self::default();
+        let mut rest = desc.trim();
+
+        // We don't use the variations or features.
+        while let Some((head, word)) = last_word(rest) {
+            if !word.starts_with('@') && !word.starts_with('#') {
+                break;
+            }
+            rest = head;
+        }
+
+        if let Some((head, word)) = last_word(rest) {
+            if let Some(size) = parse_size(word) {
+                font.size = Some(size);
+                rest = head;
+            }
+        }
+
+        // Like Pango, take style words from the end until one isn't a style word.
+        while let Some((head, word)) = last_word(rest) {
+            if !font.parse_style_word(word) {
+                break;
+            }
+            rest = head;
+        }
+
+        font.family = rest
+            .split(',')
+            .map(str::trim)
+            .find(|family| !family.is_empty())
+            .map(str::to_string);
+
+        if font == Self::default() {
+            return None;
+        }
+
+        Some(font)
+    }
+
+    /// Set the field that a style word stands for.
+    ///
+    /// Returns `false` if it isn't a style word. Words are matched without regard to case or
+    /// dashes, so `Semi-Bold` and `semibold` are the same.
+    fn parse_style_word(&mut self, word: &str) -> bool {
+        // Numeric weights are checked before dashes are dropped, so that `-3` isn't one.
+        let numeric = word.parse::<u16>().ok().filter(|w| (1..=1000).contains(w));
+        let word = word.replace('-', "").to_ascii_lowercase();
+
+        let weight = match word.as_str() {
+            "thin" => Some(100),
+            "ultralight" | "extralight" => Some(200),
+            "light" => Some(300),
+            "semilight" | "demilight" => Some(350),
+            "book" => Some(380),
+            "regular" => Some(400),
+            "medium" => Some(500),
+            "semibold" | "demibold" => Some(600),
+            "bold" => Some(700),
+            "ultrabold" | "extrabold" => Some(800),
+            "heavy" | "black" => Some(900),
+            "ultraheavy" | "ultrablack" | "extrablack" => Some(1000),
+            _ => numeric,
+        };
+        if let Some(weight) = weight {
+            self.weight = Some(weight);
+            return true;
+        }
+
+        let stretch = match word.as_str() {
+            "ultracondensed" => Some(FontStretch::UltraCondensed),
+            "extracondensed" => Some(FontStretch::ExtraCondensed),
+            "condensed" => Some(FontStretch::Condensed),
+            "semicondensed" => Some(FontStretch::SemiCondensed),
+            "semiexpanded" => Some(FontStretch::SemiExpanded),
+            "expanded" => Some(FontStretch::Expanded),
+            "extraexpanded" => Some(FontStretch::ExtraExpanded),
+            "ultraexpanded" => Some(FontStretch::UltraExpanded),
+            _ => None,
+        };
+        if let Some(stretch) = stretch {
+            self.stretch = Some(stretch);
+            return true;
+        }
+
+        match word.as_str() {
+            // `Normal` resets every field in Pango, but it only ever comes up for the style.
+            "normal" | "roman" => self.italic = Some(false),
+            "italic" | "oblique" => self.italic = Some(true),
+
+            // We don't track the variant or gravity.
+            "smallcaps" | "allsmallcaps" | "petitecaps" | "allpetitecaps" | "unicase"
+            | "titlecaps" | "notrotated" | "south" | "upsidedown" | "north" | "rotatedleft"
+            | "east" | "rotatedright" | "west" => {}
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Set the font of a text style to this font, keeping whatever the description leaves out.
+    pub(super) fn apply(&self, text: &mut TextStyle) {
+        if let Some(family) = &self.family {
+            text.set_family(FontFamily::from_name(family));
+        }
+
+        if let Some(size) = self.size {
+            text.set_size(match size {
+                FontSize::Points(points) => points * PIXELS_PER_POINT,
+                FontSize::Pixels(pixels) => pixels,
+            });
+        }
+
+        if let Some(weight) = self.weight {
+            text.set_weight(weight);
+        }
+
+        if let Some(italic) = self.italic {
+            text.set_italic(italic);
+        }
+
+        if let Some(stretch) = self.stretch {
+            text.set_stretch(stretch);
+        }
+    }
+}
+
+/// Split off the last word of a description, which is separated by spaces or commas.
+fn last_word(desc: &str) -> Option<(&str, &str)> {
+    let desc = desc.trim_end();
+    let start = desc
+        .rfind(|c: char| c.is_whitespace() || c == ',')
+        .map_or(0, |sep| sep + 1);
+
+    let word = &desc[start..];
+    if word.is_empty() {
+        return None;
+    }
+
+    Some((&desc[..start], word))
+}
+
+/// Parse the size of a font, like `11` for points or `14px` for pixels.
+fn parse_size(word: &str) -> Option<FontSize> {
+    let (number, pixels) = match word.strip_suffix("px") {
+        Some(number) => (number, true),
+        None => (word, false),
+    };
+
+    let size = number
+        .parse::<f32>()
+        .ok()
+        .filter(|size| size.is_finite() && *size > 0.0)?;
+    Some(match pixels {
+        true => FontSize::Pixels(size),
+        false => FontSize::Points(size),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_and_points() {
+        let font = PangoFont::parse("Cantarell Bold Italic 11").unwrap();
+        assert_eq!(font.family.as_deref(), Some("Cantarell"));
+        assert_eq!(font.size, Some(FontSize::Points(11.0)));
+        assert_eq!(font.weight, Some(700));
+        assert_eq!(font.italic, Some(true));
+        assert_eq!(font.stretch, None);
+
+        let mut text = TextStyle::new(1.0, FontFamily::SansSerif);
+        font.apply(&mut text);
+        assert_eq!(text.family(), &FontFamily::Custom("Cantarell".into()));
+        assert!((text.size() - 11.0 * PIXELS_PER_POINT).abs() < 1e-4);
+        assert_eq!(text.weight(), 700);
+        assert!(text.italic());
+    }
+
+    #[test]
+    fn family_with_spaces() {
+        let font = PangoFont::parse("Source Code Pro 10").unwrap();
+        assert_eq!(font.family.as_deref(), Some("Source Code Pro"));
+        assert_eq!(font.size, Some(FontSize::Points(10.0)));
+        assert_eq!((font.weight, font.italic, font.stretch), (None, None, None));
+    }
+
+    #[test]
+    fn dashes_and_pixels() {
+        let font = PangoFont::parse("Sans Semi-Bold Condensed 12px").unwrap();
+        assert_eq!(font.family.as_deref(), Some("Sans"));
+        assert_eq!(font.size, Some(FontSize::Pixels(12.0)));
+        assert_eq!(font.weight, Some(600));
+        assert_eq!(font.stretch, Some(FontStretch::Condensed));
+
+        let mut text = TextStyle::new(1.0, FontFamily::Serif);
+        font.apply(&mut text);
+        assert_eq!(text.family(), &FontFamily::SansSerif);
+        assert_eq!(text.size(), 12.0);
+    }
+
+    #[test]
+    fn bad_sizes() {
+        // A size that isn't a finite, positive number is part of the family.
+        for desc in ["Sans inf", "Sans NaN", "Sans -3", "Sans 0"] {
+            let font = PangoFont::parse(desc).unwrap();
+            assert_eq!(font.family.as_deref(), Some(desc), "{}", desc);
+            assert_eq!(font.size, None, "{}", desc);
+        }
+
+        assert_eq!(PangoFont::parse(""), None);
+        assert_eq!(PangoFont::parse("  "), None);
+    }
+}
@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Reading settings through the XDG desktop portal.
+//!
+//! The `org.freedesktop.portal.Settings` interface is the only way to read the desktop's settings
+//! from inside a sandbox like Flatpak, and works outside of one as well. We read every namespace
+//! we care about with one `ReadAll` call, and listen to `SettingChanged` to find out when the
+//! theme needs to be loaded again.
+
+use crate::{Color, ContrastPreference, LoadContext, ShadePreference};
+
+use futures_lite::{future, StreamExt};
+use zbus::{CacheProperties, Connection, ConnectionBuilder, Proxy, ProxyBuilder, SignalStream};
+use zvariant::{OwnedValue, Value};
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+
+/// The bus name of the portal.
+const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+
+/// The object that the portal's interfaces live on.
+const PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// The interface for reading settings.
+const SETTINGS: &str = "org.freedesktop.portal.Settings";
+
+/// The namespace of the cross-desktop appearance settings.
+pub(super) const APPEARANCE: &str = "org.freedesktop.appearance";
+
+/// The namespace of the GNOME interface settings.
+pub(super) const INTERFACE: &str = "org.gnome.desktop.interface";
+
+/// The namespace of the GNOME sound settings.
+pub(super) const SOUND: &str = "org.gnome.desktop.sound";
+
+/// Settings read from the portal, by namespace and key.
+#[derive(Debug, Default)]
+pub(super) struct Settings {
+    namespaces: HashMap<String, HashMap<String, OwnedValue>>,
+}
+
+/// The settings that the theme depends on, by namespace and key.
+const WATCHED: &[(&str, &str)] = &[
+    (APPEARANCE, "color-scheme"),
+    (APPEARANCE, "accent-color"),
+    (APPEARANCE, "contrast"),
+    (INTERFACE, "gtk-theme"),
+];
+
+/// A connection to the portal.
+pub(super) struct Portal {
+    conn: Connection,
+}
+
+impl Portal {
+    /// Connect to the portal over the session bus of a context.
+    pub(super) async fn connect(context: &LoadContext) -> io::Result<Self> {
+        let builder = match (context.bus_address(), context.inherits_env()) {
+            (Some(address), _) => ConnectionBuilder::address(address),
+            (None, true) => ConnectionBuilder::session(),
+            (None, false) => match context.var("DBUS_SESSION_BUS_ADDRESS") {
+                Some(address) => ConnectionBuilder::address(address.as_str()),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "no session bus in the environment",
+                    ))
+                }
+            },
+        };
+
+        // Open a ZBus connection, but make sure we poll the executor ourselves.
+        let conn = builder
+            .map_err(io::Error::other)?
+            .internal_executor(false)
+            .build()
+            .await
+            .map_err(io::Error::other)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Run a future, polling the executor of the connection as we go.
+    async fn run<T>(&self, f: impl Future<Output = T>) -> T {
+        let poll_executor = async {
+            loop {
+                self.conn.executor().tick().await;
+            }
+        };
+
+        future::or(f, poll_executor).await
+    }
+
+    /// Read the settings from the portal.
+    pub(super) async fn read_all(&self) -> io::Result<Settings> {
+        self.run(async {
+            let reply = self
+                .conn
+                .call_method(
+                    Some(DESTINATION),
+                    PATH,
+                    Some(SETTINGS),
+                    "ReadAll",
+                    &(&[INTERFACE, APPEARANCE, SOUND][..],),
+                )
+                .await
+                .map_err(io::Error::other)?;
+
+            let namespaces = reply.body().map_err(io::Error::other)?;
+            Ok(Settings { namespaces })
+        })
+        .await
+    }
+}
+
+/// A subscription to the changes of the settings that the theme depends on.
+pub(super) struct Watcher {
+    /// The connection that the signals arrive on.
+    portal: Portal,
+
+    /// The `SettingChanged` signals.
+    signals: SignalStream<'static>,
+}
+
+impl Watcher {
+    /// Subscribe to the `SettingChanged` signal.
+    pub(super) async fn new(context: &LoadContext) -> io::Result<Self> {
+        let portal = Portal::connect(context).await?;
+
+        let signals = portal
+            .run(async {
+                let proxy: Proxy<'static> = ProxyBuilder::new_bare(&portal.conn)
+                    .destination(DESTINATION)?
+                    .path(PATH)?
+                    .interface(SETTINGS)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                proxy.receive_signal("SettingChanged").await
+            })
+            .await
+            .map_err(io::Error::other)?;
+
+        Ok(Self { portal, signals })
+    }
+
+    /// Get the connection to the portal.
+    pub(super) fn portal(&self) -> &Portal {
+        &self.portal
+    }
+
+    /// Wait until one of the settings that the theme depends on changes.
+    ///
+    /// Returns `false` once the connection is closed.
+    pub(super) async fn changed(&mut self) -> bool {
+        let Self { portal, signals } = self;
+
+        portal
+            .run(async {
+                while let Some(message) = signals.next().await {
+                    let (namespace, key, _) = match message.body::<(String, String, OwnedValue)>() {
+                        Ok(body) => body,
+                        Err(_) => continue,
+                    };
+
+                    if WATCHED.contains(&(namespace.as_str(), key.as_str())) {
+                        return true;
+                    }
+                }
+
+                false
+            })
+            .await
+    }
+}
+
+impl Settings {
+    /// Read the settings from the portal.
+    pub(super) async fn read_all(context: &LoadContext) -> io::Result<Self> {
+        Portal::connect(context).await?.read_all().await
+    }
+
+    /// Get a setting.
+    fn get(&self, namespace: &str, key: &str) -> Option<&Value<'static>> {
+        let mut value: &Value<'static> = self.namespaces.get(namespace)?.get(key)?;
+
+        // Some portals wrap values in an extra variant.
+        while let Value::Value(inner) = value {
+            value = inner;
+        }
+
+        Some(value)
+    }
+
+    /// Get a string setting.
+    pub(super) fn string(&self, namespace: &str, key: &str) -> Option<&str> {
+        match self.get(namespace, key)? {
+            Value::Str(string) => Some(string.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Get a floating point setting.
+    pub(super) fn double(&self, namespace: &str, key: &str) -> Option<f64> {
+        match self.get(namespace, key)? {
+            Value::F64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get a boolean setting.
+    pub(super) fn bool(&self, namespace: &str, key: &str) -> Option<bool> {
+        match self.get(namespace, key)? {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get an integer setting.
+    pub(super) fn int(&self, namespace: &str, key: &str) -> Option<i32> {
+        match self.get(namespace, key)? {
+            Value::I32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the accent color the user picked, if any.
+    pub(super) fn accent_color(&self) -> Option<Color> {
+        let fields = match self.get(APPEARANCE, "accent-color")? {
+            Value::Structure(structure) => structure.fields(),
+            _ => return None,
+        };
+
+        // Channels outside of the range mean that there is no accent color.
+        let mut channels = [0; 3];
+        if fields.len() != channels.len() {
+            return None;
+        }
+
+        for (channel, field) in channels.iter_mut().zip(fields) {
+            match field {
+                Value::F64(value) if (0.0..=1.0).contains(value) => {
+                    *channel = (value * 255.0).round() as u8;
+                }
+                _ => return None,
+            }
+        }
+
+        let [r, g, b] = channels;
+        Some(Color::new(r, g, b, 255))
+    }
+
+    /// Get the user's light/dark mode preference, if any.
+    pub(super) fn shade_preference(&self) -> Option<ShadePreference> {
+        const DARK_MODE: u32 = 1;
+        const LIGHT_MODE: u32 = 2;
+
+        match self.get(APPEARANCE, "color-scheme")? {
+            Value::U32(DARK_MODE) => Some(ShadePreference::Dark),
+            Value::U32(LIGHT_MODE) => Some(ShadePreference::Light),
+            _ => None,
+        }
+    }
+
+    /// Get the user's contrast preference, if any.
+    pub(super) fn contrast_preference(&self) -> Option<ContrastPreference> {
+        const HIGH_CONTRAST: u32 = 1;
+
+        match self.get(APPEARANCE, "contrast")? {
+            Value::U32(HIGH_CONTRAST) => Some(ContrastPreference::High),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A parser for the font descriptions that Qt writes out with `QFont::toString()`.
+//!
+//! These are a comma-separated list that starts with the family, point size, pixel size, style
+//! hint, weight, style, underline, strikeout and fixed pitch. Qt 5 uses its own 0 to 99 scale for
+//! the weight, while Qt 6 writes out more fields and uses the same weights as CSS.
+//!
+//! `QSettings` may also store a font as a serialized `QVariant`, which we read as well.
+
+use super::palette;
+use crate::text::{FontFamily, TextStyle};
+use crate::{Theme, Widget, WIDGET_STATES};
+
+/// A font read from a Qt font description.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct QtFont {
+    /// The font family.
+    family: String,
+
+    /// The size of the font, either in points or in pixels.
+    size: FontSize,
+
+    /// The weight, on the CSS scale.
+    weight: u16,
+
+    /// The font is italic or oblique.
+    italic: bool,
+
+    /// The font is underlined.
+    underline: bool,
+
+    /// The font is struck out.
+    strikethrough: bool,
+}
+
+/// The size of a Qt font.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FontSize {
+    /// The size is in points.
+    Points(f32),
+
+    /// The size is in pixels.
+    Pixels(f32),
+}
+
+/// The field count of a Qt 6 font description; Qt 5 writes out ten or eleven.
+const QT6_FIELDS: usize = 16;
+
+/// Qt 5's named weights and their CSS equivalents.
+const QT5_WEIGHTS: &[(u16, u16)] = &[
+    (0, 100),
+    (12, 200),
+    (25, 300),
+    (50, 400),
+    (57, 500),
+    (63, 600),
+    (75, 700),
+    (81, 800),
+    (87, 900),
+];
+
+impl QtFont {
+    /// Parse a font description.
+    pub(super) fn parse(desc: &str) -> Option<Self> {
+        let fields = desc.split(',').map(str::trim).collect::<Vec<_>>();
+
+        let family = fields.first().filter(|family| !family.is_empty())?;
+        let point_size = fields.get(1).and_then(|size| size.parse::<f32>().ok());
+        let pixel_size = fields.get(2).and_then(|size| size.parse::<f32>().ok());
+        let size = match (point_size, pixel_size) {
+            (Some(points), _) if points > 0.0 => FontSize::Points(points),
+            (_, Some(pixels)) if pixels > 0.0 => FontSize::Pixels(pixels),
+            _ => return None,
+        };
+
+        let weight = match fields.get(4).and_then(|weight| weight.parse::<u16>().ok()) {
+            Some(weight) if fields.len() >= QT6_FIELDS || weight > 99 => weight.clamp(1, 1000),
+            Some(weight) => qt5_weight(weight),
+            None => 400,
+        };
+        let flag = |index: usize| fields.get(index).is_some_and(|flag| *flag != "0");
+
+        Some(Self {
+            family: family.to_string(),
+            size,
+            weight,
+            italic: flag(5),
+            underline: flag(6),
+            strikethrough: flag(7),
+        })
+    }
+
+    /// Parse a font as `QSettings` stores it, either as a description or a serialized `QFont`.
+    pub(super) fn from_setting(value: &str) -> Option<Self> {
+        match value
+            .strip_prefix("@Variant(")
+            .and_then(|data| data.strip_suffix(')'))
+        {
+            Some(data) => {
+                // Every byte was written out as one character.
+                let data = data
+                    .chars()
+                    .map(|c| u8::try_from(c).ok())
+                    .collect::<Option<Vec<_>>>()?;
+                Self::from_variant(&data)
+            }
+            None => Self::parse(value),
+        }
+    }
+
+    /// Read a `QVariant` holding a `QFont`.
+    ///
+    /// `QSettings` always writes these at version 4.0 of the `QDataStream` format.
+    fn from_variant(data: &[u8]) -> Option<Self> {
+        /// The type ID of `QFont`.
+        const QFONT: u32 = 64;
+
+        /// The bits for the font's style.
+        const ITALIC: u8 = 0x01;
+        const UNDERLINE: u8 = 0x02;
+        const STRIKEOUT: u8 = 0x08;
+
+        let mut stream = DataStream(data);
+        if stream.read_u32()? != QFONT {
+            return None;
+        }
+
+        let family = stream.read_string()?;
+        let point_size = stream.read_f64()?;
+        let pixel_size = stream.read_u32()? as i32;
+        let size = match (point_size, pixel_size) {
+            (points, _) if points > 0.0 => FontSize::Points(points as f32),
+            (_, pixels) if pixels > 0 => FontSize::Pixels(pixels as f32),
+            _ => return None,
+        };
+
+        // Skip the style hint, style strategy and a reserved byte.
+        stream.take(3)?;
+
+        let weight = qt5_weight(stream.read_u8()?.into());
+        let bits = stream.read_u8()?;
+
+        Some(Self {
+            family,
+            size,
+            weight,
+            italic: bits & ITALIC != 0,
+            underline: bits & UNDERLINE != 0,
+            strikethrough: bits & STRIKEOUT != 0,
+        })
+    }
+
+    /// Get the size of the font in pixels, at the given DPI.
+    fn pixel_size(&self, dpi: f32) -> f32 {
+        match self.size {
+            FontSize::Points(points) => points * dpi / 72.0,
+            FontSize::Pixels(pixels) => pixels,
+        }
+    }
+
+    /// Set the font of a text style to this font, keeping its color and alignment.
+    fn apply(&self, text: &mut TextStyle, dpi: f32) {
+        text.set_family(FontFamily::from_name(&self.family))
+            .set_size(self.pixel_size(dpi))
+            .set_weight(self.weight)
+            .set_italic(self.italic)
+            .set_underline(self.underline)
+            .set_strikethrough(self.strikethrough);
+    }
+
+    /// Set the font of a widget in every state, including its menu text if it's a menu.
+    pub(super) fn apply_to_widget(&self, theme: &mut Theme, widget: Widget, dpi: f32) {
+        for state in WIDGET_STATES {
+            let props = theme.get_mut(widget, *state);
+
+            if let Some(mut text) = props.text_style().cloned() {
+                self.apply(&mut text, dpi);
+
+                if palette::is_menu(widget) {
+                    props.set_menu_text_style(text.clone());
+                }
+                props.set_text_style(text);
+            }
+        }
+    }
+}
+
+/// Convert a Qt 5 weight to a CSS weight, using the closest named weight.
+fn qt5_weight(weight: u16) -> u16 {
+    QT5_WEIGHTS
+        .iter()
+        .min_by_key(|(qt, _)| qt.abs_diff(weight))
+        .map_or(400, |(_, css)| *css)
+}
+
+/// Reads big-endian values out of a `QDataStream`.
+struct DataStream<'a>(&'a [u8]);
+
+impl<'a> DataStream<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn read_f64(&mut self) -> Option<f64> {
+        Some(f64::from_be_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    /// Read a `QString`, which is stored as UTF-16 after its length in bytes.
+    fn read_string(&mut self) -> Option<String> {
+        let len = self.read_u32()?;
+
+        // A null string has all bits set in its length.
+        if len == u32::MAX {
+            return Some(String::new());
+        }
+
+        let units = self
+            .take(len as usize)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+        char::decode_utf16(units).collect::<Result<_, _>>().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write out a `QFont` the way `QSettings` does, one character for every byte.
+    fn variant(family: &str, points: f64, weight: u8, bits: u8) -> String {
+        let mut data = 64u32.to_be_bytes().to_vec();
+        let family = family.encode_utf16().collect::<Vec<_>>();
+        data.extend((family.len() as u32 * 2).to_be_bytes());
+        data.extend(family.iter().flat_map(|unit| unit.to_be_bytes()));
+        data.extend(points.to_be_bytes());
+        data.extend((-1i32).to_be_bytes());
+        data.extend([5, 0, 0, weight, bits]);
+
+        let chars = data.into_iter().map(char::from).collect::<String>();
+        format!("@Variant({})", chars)
+    }
+
+    #[test]
+    fn qt5() {
+        let font = QtFont::parse("Noto Sans,10,-1,5,50,0,0,0,0,0").unwrap();
+        assert_eq!(font.family, "Noto Sans");
+        assert_eq!(font.size, FontSize::Points(10.0));
+        assert_eq!(font.weight, 400);
+        assert!(!font.italic && !font.underline && !font.strikethrough);
+
+        let font = QtFont::parse("Noto Sans,10,-1,5,75,1,1,1,0,0").unwrap();
+        assert_eq!(font.weight, 700);
+        assert!(font.italic && font.underline && font.strikethrough);
+
+        // Weights in between go to the closest named one.
+        assert_eq!(QtFont::parse("Sans,10,-1,5,62,0").unwrap().weight, 600);
+        assert_eq!(QtFont::parse("Sans,10").unwrap().weight, 400);
+    }
+
+    #[test]
+    fn qt6() {
+        // Qt 6 writes out CSS weights, which can be told apart by the number of fields.
+        let font = QtFont::parse("Inter,11,-1,5,700,0,0,0,0,0,0,0,0,0,0,1").unwrap();
+        assert_eq!(font.weight, 700);
+        let font = QtFont::parse("Inter,11,-1,5,50,0,0,0,0,0,0,0,0,0,0,1").unwrap();
+        assert_eq!(font.weight, 50);
+
+        // A weight over Qt 5's scale can only be a CSS weight.
+        assert_eq!(QtFont::parse("Inter,11,-1,5,400,0").unwrap().weight, 400);
+    }
+
+    #[test]
+    fn sizes() {
+        let font = QtFont::parse("Hack,-1,14,5,50,0,0,0,0,0").unwrap();
+        assert_eq!(font.size, FontSize::Pixels(14.0));
+        assert_eq!(font.pixel_size(120.0), 14.0);
+
+        let font = QtFont::parse("Hack,9,-1,5,50,0,0,0,0,0").unwrap();
+        assert_eq!(font.pixel_size(96.0), 12.0);
+        assert_eq!(font.pixel_size(144.0), 18.0);
+
+        for desc in ["", ",10,-1", "Sans", "Sans,-1,-1", "Sans,abc,0", "Sans,0,0"] {
+            assert_eq!(QtFont::parse(desc), None, "{:?}", desc);
+        }
+    }
+
+    #[test]
+    fn settings() {
+        // Descriptions may be stored as they are.
+        let font = QtFont::from_setting("Noto Sans,10,-1,5,50,0,0,0,0,0").unwrap();
+        assert_eq!(font.family, "Noto Sans");
+
+        let font = QtFont::from_setting(&variant("Fira Sans", 10.5, 75, 0x01 | 0x08)).unwrap();
+        assert_eq!(font.family, "Fira Sans");
+        assert_eq!(font.size, FontSize::Points(10.5));
+        assert_eq!(font.weight, 700);
+        assert!(font.italic && !font.underline && font.strikethrough);
+
+        // Other types of variants, cut off variants and bytes that don't fit in one aren't fonts.
+        let other = variant("Sans", 10.0, 50, 0).replacen("(\0\0\0@", "(\0\0\0A", 1);
+        assert_eq!(QtFont::from_setting(&other), None);
+        let cut = variant("Sans", 10.0, 50, 0);
+        assert_eq!(QtFont::from_setting(&cut[..cut.len() - 3]), None);
+        assert_eq!(QtFont::from_setting("@Variant(\u{100})"), None);
+    }
+
+    #[test]
+    fn menus() {
+        let font = QtFont::parse("Noto Sans,9,-1,5,75,1,0,0,0,0").unwrap();
+        let mut theme = Theme::default_theme(crate::ShadePreference::Light);
+        font.apply_to_widget(&mut theme, Widget::PopupMenuItem, 96.0);
+
+        let props = theme.get(Widget::PopupMenuItem, crate::WidgetState::Hovered);
+        for text in [props.text_style(), props.menu_text_style()] {
+            let text = text.unwrap();
+            assert_eq!(text.family(), &FontFamily::Custom("Noto Sans".into()));
+            assert_eq!(text.size(), 12.0);
+            assert_eq!(text.weight(), 700);
+            assert!(text.italic());
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Code for loading the palette and fonts set through `qt5ct` or `qt6ct`.
+//!
+//! These tools keep their settings in `qt5ct.conf` or `qt6ct.conf`, which points to a color
+//! scheme file holding a `QPalette` for each of the active, disabled and inactive states.
+
+use super::ini::{optional, KeyFile};
+use super::palette::Palette;
+use super::qt_font::QtFont;
+use super::{context, files, xdg};
+use crate::{Color, LoadThemeError, ShadePreference, Theme, Widget, WidgetState};
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The DPI that font sizes are converted to pixels at.
+const DPI: f32 = 96.0;
+
+/// The states that use the active colors of the palette.
+const ACTIVE_STATES: &[WidgetState] = &[
+    WidgetState::Enabled,
+    WidgetState::Focused,
+    WidgetState::Selected,
+    WidgetState::Hovered,
+    WidgetState::Pressed,
+    WidgetState::Checked,
+];
+
+/// Load the theme configured through a tool like `qt5ct`.
+///
+/// If a name is given, the color scheme by that name is loaded instead of the configured one.
+pub(super) fn load_theme(tool: &str, name: Option<&str>) -> Result<Option<Theme>, LoadThemeError> {
+    let config_dir = match xdg::config_home() {
+        Some(config_home) => config_home.join(tool),
+        None => return Ok(None),
+    };
+    let config = optional(KeyFile::open_qsettings(
+        &config_dir.join(format!("{}.conf", tool)),
+    ))
+    .map_err(LoadThemeError)?
+    .unwrap_or_default();
+
+    // Find the color scheme.
+    let scheme_path = match name {
+        Some(name) => match find_color_scheme(tool, &config_dir, name) {
+            Some(path) => Some(path),
+            None => return Ok(None),
+        },
+        None => config
+            .get("Appearance", "color_scheme_path")
+            .filter(|_| config.get("Appearance", "custom_palette") == Some("true"))
+            .map(|path| match Path::new(path).is_absolute() {
+                true => context::path(path),
+                false => config_dir.join(path),
+            }),
+    };
+    let scheme = match &scheme_path {
+        Some(path) => read_color_scheme(path).map_err(LoadThemeError)?,
+        None => None,
+    };
+
+    let general_font = config
+        .get("Fonts", "general")
+        .and_then(QtFont::from_setting);
+    let fixed_font = config.get("Fonts", "fixed").and_then(QtFont::from_setting);
+
+    if scheme.is_none() && general_font.is_none() && fixed_font.is_none() {
+        return Ok(None);
+    }
+
+    let shade = scheme
+        .as_ref()
+        .map_or(ShadePreference::Light, |scheme| scheme.active.shade());
+    let mut theme = Theme::default_theme(shade);
+    theme.set_name(
+        scheme_path
+            .as_deref()
+            .and_then(Path::file_stem)
+            .map_or_else(
+                || tool.to_string(),
+                |stem| stem.to_string_lossy().into_owned(),
+            ),
+    );
+
+    if let Some(scheme) = scheme {
+        scheme.active.apply(&mut theme, ACTIVE_STATES);
+        scheme.disabled.apply(&mut theme, &[WidgetState::Disabled]);
+        scheme.inactive.apply(&mut theme, &[WidgetState::Inactive]);
+    }
+
+    if let Some(font) = &general_font {
+        for widget in crate::WIDGETS {
+            font.apply_to_widget(&mut theme, *widget, DPI);
+        }
+    }
+
+    if let Some(font) = &fixed_font {
+        font.apply_to_widget(&mut theme, Widget::Editor, DPI);
+    }
+
+    Ok(Some(theme))
+}
+
+/// Find a color scheme by its name, in the tool's own directory or the data directories.
+fn find_color_scheme(tool: &str, config_dir: &Path, name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}.conf", name);
+
+    let data_dirs = xdg::data_home()
+        .into_iter()
+        .chain(xdg::data_dirs())
+        .map(|dir| dir.join(tool));
+
+    Some(config_dir.to_path_buf())
+        .into_iter()
+        .chain(data_dirs)
+        .map(|dir| dir.join("colors").join(&file_name))
+        .find(|path| files::is_file(path))
+}
+
+/// The palettes in a color scheme.
+struct ColorScheme {
+    active: Palette,
+    disabled: Palette,
+    inactive: Palette,
+}
+
+/// Read a color scheme file.
+fn read_color_scheme(path: &Path) -> io::Result<Option<ColorScheme>> {
+    let file = match optional(KeyFile::open_qsettings(path))? {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let palette = |key| file.get("ColorScheme", key).and_then(parse_palette);
+
+    let active = match palette("active_colors") {
+        Some(active) => active,
+        None => return Ok(None),
+    };
+
+    Ok(Some(ColorScheme {
+        disabled: palette("disabled_colors").unwrap_or_else(|| active.clone()),
+        inactive: palette("inactive_colors").unwrap_or_else(|| active.clone()),
+        active,
+    }))
+}
+
+/// Parse a list of colors, in the order of `QPalette::ColorRole`.
+fn parse_palette(value: &str) -> Option<Palette> {
+    const WINDOW_TEXT: usize = 0;
+    const BUTTON: usize = 1;
+    const TEXT: usize = 6;
+    const BUTTON_TEXT: usize = 8;
+    const BASE: usize = 9;
+    const WINDOW: usize = 10;
+    const HIGHLIGHT: usize = 12;
+    const HIGHLIGHTED_TEXT: usize = 13;
+    const LINK: usize = 14;
+    const TOOLTIP_BASE: usize = 18;
+    const TOOLTIP_TEXT: usize = 19;
+
+    let colors = value
+        .split(',')
+        .map(|color| parse_color(color.trim()))
+        .collect::<Option<Vec<_>>>()?;
+    let role = |index: usize, fallback: usize| colors.get(index).or(colors.get(fallback)).copied();
+
+    Some(Palette {
+        window: role(WINDOW, WINDOW)?,
+        window_text: role(WINDOW_TEXT, WINDOW_TEXT)?,
+        base: role(BASE, BASE)?,
+        text: role(TEXT, TEXT)?,
+        button: role(BUTTON, BUTTON)?,
+        button_text: role(BUTTON_TEXT, BUTTON_TEXT)?,
+        highlight: role(HIGHLIGHT, HIGHLIGHT)?,
+        highlighted_text: role(HIGHLIGHTED_TEXT, HIGHLIGHTED_TEXT)?,
+        tooltip_base: role(TOOLTIP_BASE, WINDOW)?,
+        tooltip_text: role(TOOLTIP_TEXT, WINDOW_TEXT)?,
+        link: role(LINK, HIGHLIGHT)?,
+    })
+}
+
+/// Parse a color as `#rrggbb` or `#aarrggbb`.
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        6 => Some(Color::new(channel(0)?, channel(2)?, channel(4)?, 255)),
+        8 => Some(Color::new(
+            channel(2)?,
+            channel(4)?,
+            channel(6)?,
+            channel(0)?,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Fill;
+
+    #[test]
+    fn colors() {
+        assert_eq!(
+            parse_color("#306294"),
+            Some(Color::new(0x30, 0x62, 0x94, 255))
+        );
+        assert_eq!(
+            parse_color("#80306294"),
+            Some(Color::new(0x30, 0x62, 0x94, 0x80))
+        );
+
+        for value in [
+            "306294", "#3062", "#3062945", "#30629g", "#+12345", "#ffé000",
+        ] {
+            assert_eq!(parse_color(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn palettes() {
+        let gray = |level| format!("#{0:02x}{0:02x}{0:02x}", level);
+        let colors = |count: u8| (0..count).map(gray).collect::<Vec<_>>().join(", ");
+        let level = |color: Color| color.r();
+
+        let palette = parse_palette(&colors(21)).unwrap();
+        assert_eq!(level(palette.window_text), 0);
+        assert_eq!(level(palette.button), 1);
+        assert_eq!(level(palette.text), 6);
+        assert_eq!(level(palette.button_text), 8);
+        assert_eq!(level(palette.base), 9);
+        assert_eq!(level(palette.window), 10);
+        assert_eq!(level(palette.highlight), 12);
+        assert_eq!(level(palette.highlighted_text), 13);
+        assert_eq!(level(palette.link), 14);
+        assert_eq!(level(palette.tooltip_base), 18);
+        assert_eq!(level(palette.tooltip_text), 19);
+
+        // Older schemes leave out the later roles, which then follow the window and highlight.
+        let palette = parse_palette(&colors(14)).unwrap();
+        assert_eq!(level(palette.link), 12);
+        assert_eq!(level(palette.tooltip_base), 10);
+        assert_eq!(level(palette.tooltip_text), 0);
+
+        // But the basic roles have to be there, and every color has to parse.
+        assert!(parse_palette(&colors(13)).is_none());
+        assert!(parse_palette(&format!("{}, red", colors(21))).is_none());
+    }
+
+    #[test]
+    fn named_schemes() {
+        let fixture = context::fixture_context("qt5ct", &[]);
+
+        let (theme, missing) = context::enter(&fixture, || {
+            (
+                load_theme("qt5ct", Some("fixture")).unwrap().unwrap(),
+                load_theme("qt5ct", Some("missing")).unwrap(),
+            )
+        });
+        assert!(missing.is_none());
+
+        // The scheme in the data directories is found by its name.
+        assert_eq!(theme.name(), "fixture");
+        let background = |state| theme.get(Widget::Button, state).background().cloned();
+        assert_eq!(
+            background(WidgetState::Enabled),
+            Some(Fill::Color(Color::new(0x42, 0x42, 0x45, 0xff)))
+        );
+
+        // The scheme has no inactive colors, so the active ones are used.
+        assert_eq!(
+            background(WidgetState::Inactive),
+            background(WidgetState::Enabled)
+        );
+        let text = |state| {
+            theme
+                .get(Widget::Editor, state)
+                .text_style()
+                .unwrap()
+                .color()
+        };
+        assert_eq!(
+            text(WidgetState::Disabled),
+            Color::new(0x80, 0x80, 0x80, 0xff)
+        );
+        assert_eq!(
+            text(WidgetState::Inactive),
+            Color::new(0xff, 0xff, 0xff, 0xff)
+        );
+    }
+}
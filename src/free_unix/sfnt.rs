@@ -0,0 +1,265 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Reading the names and styles of the faces in OpenType and TrueType fonts.
+//!
+//! Only the tables that describe a face are read, so that large fonts don't have to be read in
+//! full. Collections hold several faces, each with its own table directory.
+
+use super::files;
+
+use std::fs::File;
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+/// The tag that starts a font collection.
+const COLLECTION_TAG: &[u8; 4] = b"ttcf";
+
+/// The name IDs of the family names, the legacy one and the typographic one.
+const FAMILY_NAME_IDS: &[u16] = &[1, 16];
+
+/// The largest table that we read, to guard against broken files.
+const MAX_TABLE_LEN: u32 = 1 << 20;
+
+/// The weight of faces that don't say.
+const NORMAL_WEIGHT: u16 = 400;
+
+/// A face in a font file.
+#[derive(Debug, Clone)]
+pub(super) struct Face {
+    /// The index of the face in a collection, or zero.
+    pub(super) index: u32,
+
+    /// The family names of the face.
+    pub(super) families: Vec<String>,
+
+    /// The weight, from 100 to 900.
+    pub(super) weight: u16,
+
+    /// Whether the face is italic or oblique.
+    pub(super) italic: bool,
+}
+
+/// Read the faces of a font file.
+pub(super) fn read_faces(path: &Path) -> io::Result<Vec<Face>> {
+    let file = files::open(path)?;
+
+    let mut tag = [0; 4];
+    file.read_exact_at(&mut tag, 0)?;
+
+    if &tag != COLLECTION_TAG {
+        return Ok(vec![read_face(&file, 0, 0)?]);
+    }
+
+    let count = read_u32(&file, 8)?;
+    let mut faces = Vec::new();
+    for index in 0..count.min(256) {
+        let offset = read_u32(&file, 12 + u64::from(index) * 4)?;
+        faces.push(read_face(&file, index, offset.into())?);
+    }
+
+    Ok(faces)
+}
+
+/// Read the face whose table directory is at an offset.
+fn read_face(file: &File, index: u32, offset: u64) -> io::Result<Face> {
+    let count = read_u16(file, offset + 4)?;
+    let mut records = vec![0; usize::from(count) * 16];
+    file.read_exact_at(&mut records, offset + 12)?;
+
+    let table = |tag: &[u8; 4]| {
+        records.chunks_exact(16).find_map(|record| {
+            (&record[..4] == tag).then(|| (be_u32(&record[8..]), be_u32(&record[12..])))
+        })
+    };
+    let read_table = |tag: &[u8; 4]| -> io::Result<Option<Vec<u8>>> {
+        match table(tag) {
+            Some((offset, len)) if len <= MAX_TABLE_LEN => {
+                let mut data = vec![0; len as usize];
+                file.read_exact_at(&mut data, offset.into())?;
+                Ok(Some(data))
+            }
+            _ => Ok(None),
+        }
+    };
+
+    let families = match read_table(b"name")? {
+        Some(name) => family_names(&name),
+        None => Vec::new(),
+    };
+
+    // Prefer the style from `OS/2`, and fall back to `head`.
+    let (weight, italic) = match read_table(b"OS/2")? {
+        Some(os2) if os2.len() >= 64 => {
+            let weight = be_u16(&os2[4..]);
+            let selection = be_u16(&os2[62..]);
+            (weight, selection & 0x201 != 0)
+        }
+        _ => match read_table(b"head")? {
+            Some(head) if head.len() >= 46 => {
+                let style = be_u16(&head[44..]);
+                (NORMAL_WEIGHT, style & 0x2 != 0)
+            }
+            _ => (NORMAL_WEIGHT, false),
+        },
+    };
+
+    Ok(Face {
+        index,
+        families,
+        weight: match weight {
+            1..=1000 => weight,
+            _ => NORMAL_WEIGHT,
+        },
+        italic,
+    })
+}
+
+/// Read the family names out of a `name` table, in every language that it has them in.
+fn family_names(table: &[u8]) -> Vec<String> {
+    let mut names = Vec::new();
+    if table.len() < 6 {
+        return names;
+    }
+
+    let count = usize::from(be_u16(&table[2..]));
+    let storage = usize::from(be_u16(&table[4..]));
+
+    for record in table[6..].chunks_exact(12).take(count) {
+        let platform = be_u16(record);
+        let encoding = be_u16(&record[2..]);
+        let name_id = be_u16(&record[6..]);
+        let len = usize::from(be_u16(&record[8..]));
+        let offset = storage + usize::from(be_u16(&record[10..]));
+
+        if !FAMILY_NAME_IDS.contains(&name_id) {
+            continue;
+        }
+
+        let bytes = match table.get(offset..offset + len) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+
+        let name = match (platform, encoding) {
+            // Unicode and Windows names are UTF-16.
+            (0, _) | (3, 0) | (3, 1) | (3, 10) => {
+                let units = bytes.chunks_exact(2).map(be_u16).collect::<Vec<_>>();
+                String::from_utf16_lossy(&units)
+            }
+
+            // Of the Macintosh encodings, only take Roman names in ASCII.
+            (1, 0) if bytes.is_ascii() => bytes.iter().map(|&b| char::from(b)).collect(),
+            _ => continue,
+        };
+
+        let name = name.trim();
+        if !name.is_empty() && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+}
+
+/// Read a big endian 16-bit integer from a file.
+fn read_u16(file: &File, offset: u64) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    file.read_exact_at(&mut bytes, offset)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+/// Read a big endian 32-bit integer from a file.
+fn read_u32(file: &File, offset: u64) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    file.read_exact_at(&mut bytes, offset)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// Read a big endian 16-bit integer from the start of some bytes.
+fn be_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+/// Read a big endian 32-bit integer from the start of some bytes.
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Read the faces of a font in the fixtures.
+    fn read(path: &str) -> io::Result<Vec<Face>> {
+        read_faces(&Path::new(env!("CARGO_MANIFEST_DIR")).join(path))
+    }
+
+    #[test]
+    fn names_and_styles() {
+        let faces = read("tests/fixtures/fonts/usr/share/fonts/fixture/FixtureSans-BoldItalic.ttf")
+            .unwrap();
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].index, 0);
+        assert_eq!(faces[0].families, ["Fixture Sans Bold", "Fixture Sans"]);
+        assert_eq!(faces[0].weight, 700);
+        assert!(faces[0].italic);
+
+        let faces =
+            read("tests/fixtures/fonts/usr/share/fonts/fixture/FixtureSans-Regular.ttf").unwrap();
+        assert_eq!(faces[0].families, ["Fixture Sans"]);
+        assert_eq!((faces[0].weight, faces[0].italic), (400, false));
+    }
+
+    #[test]
+    fn without_os2() {
+        // The name is a Macintosh one, and the slant comes from `head`.
+        let faces =
+            read("tests/fixtures/fonts/home/user/.local/share/fonts/FixtureMono-Oblique.otf")
+                .unwrap();
+        assert_eq!(faces[0].families, ["Fixture Mono"]);
+        assert_eq!((faces[0].weight, faces[0].italic), (NORMAL_WEIGHT, true));
+    }
+
+    #[test]
+    fn collection() {
+        let faces = read("tests/fixtures/fonts/usr/share/fonts/fixture/FixtureSerif.ttc").unwrap();
+        assert_eq!(faces.len(), 2);
+
+        for (index, (face, weight)) in faces.iter().zip([400, 900]).enumerate() {
+            assert_eq!(face.index, index as u32);
+            assert_eq!(face.families, ["Fixture Serif"]);
+            assert_eq!(face.weight, weight);
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let err = read("tests/data/truncated.ttf").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // A name table that is cut short only loses the names that don't fit.
+        let mut table = vec![0, 0, 0, 2, 0, 30];
+        table.extend([0, 3, 0, 1, 4, 9, 0, 1, 0, 2, 0, 0]);
+        table.extend([0, 3, 0, 1, 4, 9, 0, 1, 0, 4, 0, 2]);
+        table.extend([0, b'A', 0]);
+        assert_eq!(family_names(&table), ["A"]);
+        assert!(family_names(&table[..4]).is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Sound lookup, following the freedesktop Sound Theme Specification.
+//!
+//! A sound theme is a directory of the same name in any of the `sounds` data directories, split
+//! into subdirectories for each output profile that are described by its `index.theme`. Sounds
+//! that the theme doesn't have are taken from the themes it inherits from, and then from
+//! `freedesktop`.
+
+use super::index_theme::{self, IndexTheme};
+use super::{files, xdg};
+
+use std::io;
+use std::path::PathBuf;
+
+/// The theme that every sound theme falls back to.
+pub(super) const FALLBACK: &str = "freedesktop";
+
+/// The output profile that every theme has, and that we look sounds up for.
+const PROFILE: &str = "stereo";
+
+/// The file types that sounds can have, in the order they're checked.
+///
+/// A `.disabled` file turns the sound off, rather than falling back to another theme.
+const EXTENSIONS: &[&str] = &["disabled", "oga", "ogg", "wav"];
+
+/// A sound theme, along with every theme that it inherits from.
+#[derive(Debug, Clone)]
+pub(crate) struct SoundTheme {
+    /// The themes to look sounds up in, in order.
+    chain: Vec<ThemeDirs>,
+
+    /// The base directories, where sounds that aren't in any theme can be found.
+    base_dirs: Vec<PathBuf>,
+
+    /// The locales to prefer sounds for, from the most specific to the least.
+    locales: Vec<String>,
+
+    /// Whether the user wants sounds for events.
+    enabled: bool,
+}
+
+/// The directories of one sound theme.
+#[derive(Debug, Clone)]
+struct ThemeDirs {
+    /// The name of the theme.
+    name: String,
+
+    /// The name of the theme, as shown to the user.
+    display_name: Option<String>,
+
+    /// The directories of the theme's copies that hold sounds for our output profile.
+    dirs: Vec<PathBuf>,
+}
+
+/// What looking a sound up in a directory found.
+enum Found {
+    /// The sound file.
+    Sound(PathBuf),
+
+    /// The sound is turned off.
+    Disabled,
+}
+
+impl SoundTheme {
+    /// Load a sound theme by its name.
+    ///
+    /// If the theme isn't installed, this only looks sounds up in `freedesktop`.
+    pub(super) fn load(name: &str, enabled: bool) -> io::Result<Self> {
+        let base_dirs = base_dirs();
+        let mut chain = Vec::new();
+
+        add_theme(name, &base_dirs, &mut chain)?;
+        add_theme(FALLBACK, &base_dirs, &mut chain)?;
+
+        Ok(Self {
+            chain,
+            base_dirs,
+            locales: index_theme::locales(),
+            enabled,
+        })
+    }
+
+    /// Get the name of the theme.
+    pub(crate) fn name(&self) -> &str {
+        self.chain
+            .first()
+            .map_or(FALLBACK, |theme| theme.name.as_str())
+    }
+
+    /// Get the name of the theme, as shown to the user.
+    pub(crate) fn display_name(&self) -> Option<&str> {
+        self.chain.first()?.display_name.as_deref()
+    }
+
+    /// Tell whether the user wants sounds for events.
+    pub(crate) fn event_sounds_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Find the file for a sound, like `dialog-warning`.
+    ///
+    /// A sound that isn't found falls back to a more generic one, by dropping the last part of
+    /// its name; `dialog-warning-auth` falls back to `dialog-warning`, and then to `dialog`.
+    pub(crate) fn find_sound(&self, sound: &str) -> Option<PathBuf> {
+        // Sound names are file names; don't let them point elsewhere.
+        if sound.is_empty() || sound.contains('/') || sound.starts_with('.') {
+            return None;
+        }
+
+        let mut sound = sound;
+        loop {
+            match self.lookup(sound) {
+                Some(Found::Sound(path)) => return Some(path),
+                Some(Found::Disabled) => return None,
+                None => {}
+            }
+
+            sound = &sound[..sound.rfind('-')?];
+        }
+    }
+
+    /// Look for a sound in the themes, and then outside of any theme.
+    fn lookup(&self, sound: &str) -> Option<Found> {
+        let theme_dirs = self.chain.iter().flat_map(|theme| &theme.dirs);
+
+        for dir in theme_dirs {
+            let localized = self.locales.iter().map(|locale| dir.join(locale));
+            for dir in localized.chain(Some(dir.clone())) {
+                if let Some(found) = find_in(dir, sound) {
+                    return Some(found);
+                }
+            }
+        }
+
+        self.base_dirs
+            .iter()
+            .find_map(|dir| find_in(dir.clone(), sound))
+    }
+}
+
+/// Look for a sound in a directory.
+fn find_in(dir: PathBuf, sound: &str) -> Option<Found> {
+    EXTENSIONS.iter().find_map(|extension| {
+        let path = dir.join(format!("{}.{}", sound, extension));
+
+        match files::is_file(&path) {
+            true if *extension == "disabled" => Some(Found::Disabled),
+            true => Some(Found::Sound(path)),
+            false => None,
+        }
+    })
+}
+
+/// Add a theme to the chain, followed by the themes it inherits from.
+///
+/// Themes that aren't installed, or are already in the chain, are skipped.
+fn add_theme(name: &str, base_dirs: &[PathBuf], chain: &mut Vec<ThemeDirs>) -> io::Result<()> {
+    if chain.iter().any(|theme| theme.name == name) {
+        return Ok(());
+    }
+
+    let roots = base_dirs
+        .iter()
+        .map(|dir| dir.join(name))
+        .filter(|root| files::is_dir(root))
+        .collect::<Vec<_>>();
+
+    // The first copy with an index describes the theme.
+    let mut index = None;
+    for root in &roots {
+        index = IndexTheme::open(root)?;
+        if index.is_some() {
+            break;
+        }
+    }
+
+    let index = match index {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+
+    // Directories are for stereo unless they say otherwise.
+    let subdirs = index
+        .list_in("Sound Theme", "Directories")
+        .into_iter()
+        .filter(|subdir| index.get_in(subdir, "OutputProfile").unwrap_or(PROFILE) == PROFILE)
+        .collect::<Vec<_>>();
+
+    chain.push(ThemeDirs {
+        name: name.to_string(),
+        display_name: index.name().map(str::to_string),
+        dirs: subdirs
+            .iter()
+            .flat_map(|subdir| roots.iter().map(move |root| root.join(subdir)))
+            .filter(|dir| files::is_dir(dir))
+            .collect(),
+    });
+
+    for parent in index.parents() {
+        add_theme(parent, base_dirs, chain)?;
+    }
+
+    Ok(())
+}
+
+/// Get the directories that sound themes are installed in, from the highest precedence to the
+/// lowest.
+fn base_dirs() -> Vec<PathBuf> {
+    xdg::data_home()
+        .into_iter()
+        .chain(xdg::data_dirs())
+        .map(|dir| dir.join("sounds"))
+        .collect()
+}
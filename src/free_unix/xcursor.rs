@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! A parser for the Xcursor file format.
+//!
+//! An Xcursor file starts with a table of contents pointing to its chunks, which are comments or
+//! images. Every image has a nominal size, which is the size that the cursor theme was designed
+//! for, and the frames of animated cursors are consecutive images of the same size.
+
+use crate::{Cursor, CursorImage};
+
+use std::collections::HashSet;
+use std::io;
+use std::time::Duration;
+
+/// The magic number at the start of every file, `Xcur`.
+const MAGIC: u32 = 0x7275_6358;
+
+/// The type of image chunks.
+const IMAGE_TYPE: u32 = 0xfffd_0002;
+
+/// The size of the header of an image chunk.
+const IMAGE_HEADER: u32 = 36;
+
+/// The most chunks that the table of contents can list, like in libXcursor.
+const MAX_CHUNKS: u32 = 0x10000;
+
+/// The largest width or height that an image can have.
+const MAX_DIMENSION: u32 = 0x7fff;
+
+/// Parse an Xcursor file.
+pub(super) fn parse(data: &[u8]) -> io::Result<Cursor> {
+    let mut reader = Reader { data, offset: 0 };
+
+    if reader.u32()? != MAGIC {
+        return Err(invalid("not an Xcursor file"));
+    }
+
+    let header = reader.u32()?;
+    let _version = reader.u32()?;
+    let count = reader.u32()?;
+
+    if count > MAX_CHUNKS {
+        return Err(invalid("too many chunks in Xcursor file"));
+    }
+
+    // Entries can point at the same image, which is only read once.
+    let mut images = Vec::new();
+    let mut positions = HashSet::new();
+    for index in 0..count {
+        reader.seek(header as usize + index as usize * 12)?;
+        let (kind, _subtype, position) = (reader.u32()?, reader.u32()?, reader.u32()?);
+
+        if kind == IMAGE_TYPE && positions.insert(position) {
+            reader.seek(position as usize)?;
+            images.push(read_image(&mut reader)?);
+        }
+    }
+
+    if images.is_empty() {
+        return Err(invalid("no images in Xcursor file"));
+    }
+
+    Ok(Cursor::new(images))
+}
+
+/// Read an image chunk.
+fn read_image(reader: &mut Reader<'_>) -> io::Result<CursorImage> {
+    let header = reader.u32()?;
+    let kind = reader.u32()?;
+    let size = reader.u32()?;
+    let _version = reader.u32()?;
+
+    if header != IMAGE_HEADER || kind != IMAGE_TYPE {
+        return Err(invalid("malformed image in Xcursor file"));
+    }
+
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let hotspot = (reader.u32()?, reader.u32()?);
+    let delay = Duration::from_millis(reader.u32()?.into());
+
+    if width > MAX_DIMENSION || height > MAX_DIMENSION || hotspot.0 > width || hotspot.1 > height {
+        return Err(invalid("image in Xcursor file is out of range"));
+    }
+
+    // The pixels are ARGB with premultiplied alpha, in little endian.
+    let bytes = reader.bytes(width as usize * height as usize * 4)?;
+    let mut pixels = Vec::with_capacity(bytes.len());
+    for pixel in bytes.chunks_exact(4) {
+        let [b, g, r, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        let straight = |channel: u8| match a {
+            0 => 0,
+            a => (u32::from(channel) * 255 / u32::from(a)).min(255) as u8,
+        };
+
+        pixels.extend([straight(r), straight(g), straight(b), a]);
+    }
+
+    Ok(CursorImage::new(
+        size,
+        (width, height),
+        hotspot,
+        delay,
+        pixels,
+    ))
+}
+
+/// Reads little endian values out of a file.
+struct Reader<'a> {
+    /// The whole file.
+    data: &'a [u8],
+
+    /// Where the next value is read from.
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Move to a position in the file.
+    fn seek(&mut self, offset: usize) -> io::Result<()> {
+        if offset > self.data.len() {
+            return Err(invalid("Xcursor file is truncated"));
+        }
+
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Read some bytes.
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or_else(|| invalid("Xcursor file is truncated"))?;
+
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    /// Read a 32-bit integer.
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Create an error for a malformed file.
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cursor with a comment and one 2x2 image, like `xcursorgen` writes for a still cursor.
+    const ARROW: &[u8] = include_bytes!("../../tests/data/arrow.xcursor");
+
+    /// An animated cursor with two 1x1 frames of the same size.
+    const WATCH: &[u8] = include_bytes!("../../tests/data/watch.xcursor");
+
+    #[test]
+    fn one_frame() {
+        let cursor = parse(ARROW).unwrap();
+        assert_eq!(cursor.images().len(), 1);
+        assert_eq!(cursor.sizes(), [8]);
+
+        let image = &cursor.images()[0];
+        assert_eq!((image.width(), image.height()), (2, 2));
+        assert_eq!(image.hotspot(), (1, 0));
+        assert_eq!(image.delay(), Duration::ZERO);
+
+        // The pixels are unpremultiplied into RGBA.
+        assert_eq!(
+            image.pixels(),
+            [
+                0x10, 0x20, 0x30, 0xff, //
+                0x7f, 0x3f, 0x1f, 0x80, //
+                0x00, 0x00, 0x00, 0x00, //
+                0xff, 0xff, 0xff, 0xff,
+            ]
+        );
+    }
+
+    #[test]
+    fn two_frames() {
+        let cursor = parse(WATCH).unwrap();
+        assert_eq!(cursor.sizes(), [4]);
+
+        let frames = cursor.frames(24).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay(), Duration::from_millis(50));
+        assert_eq!(frames[0].pixels(), [0x00, 0x00, 0xff, 0xff]);
+        assert_eq!(frames[1].delay(), Duration::from_millis(100));
+        assert_eq!(frames[1].pixels(), [0xff, 0x00, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse(b"").is_err());
+        assert!(parse(b"Xcui\x10\0\0\0\0\0\x01\0\0\0\0\0").is_err());
+
+        // The table of contents is missing.
+        assert!(parse(&WATCH[..16]).is_err());
+
+        // No images at all.
+        let mut empty = WATCH[..16].to_vec();
+        empty[12] = 0;
+        assert!(parse(&empty).is_err());
+
+        // Cut off in the middle of the pixels.
+        assert!(parse(&ARROW[..ARROW.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn repeated_images() {
+        // Move the table of contents to the end, with every entry pointing at the first frame.
+        let repeat = |count: u32| {
+            let mut data = WATCH.to_vec();
+            data[4..8].copy_from_slice(&(WATCH.len() as u32).to_le_bytes());
+            data[12..16].copy_from_slice(&count.to_le_bytes());
+            for _ in 0..count {
+                data.extend([IMAGE_TYPE, 4, 0x28].iter().flat_map(|n| n.to_le_bytes()));
+            }
+            data
+        };
+
+        let cursor = parse(&repeat(MAX_CHUNKS)).unwrap();
+        assert_eq!(cursor.images().len(), 1);
+        assert_eq!(cursor.images()[0].delay(), Duration::from_millis(50));
+
+        // Past libXcursor's limit, the file is turned down.
+        assert!(parse(&repeat(MAX_CHUNKS + 1)).is_err());
+    }
+}
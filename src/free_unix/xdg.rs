@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! The XDG base directories.
+
+use super::context;
+
+use std::path::PathBuf;
+
+/// Get the user's data directory.
+pub(super) fn data_home() -> Option<PathBuf> {
+    context::var("XDG_DATA_HOME")
+        .map(context::path)
+        .or_else(|| {
+            context::home_dir().map(|mut p| {
+                p.push(".local/share");
+                p
+            })
+        })
+}
+
+/// Get the system data directories, in order of preference.
+pub(super) fn data_dirs() -> impl Iterator<Item = PathBuf> {
+    split_paths("XDG_DATA_DIRS", "/usr/local/share:/usr/share")
+}
+
+/// Get the user's configuration directory.
+pub(super) fn config_home() -> Option<PathBuf> {
+    context::var("XDG_CONFIG_HOME")
+        .map(context::path)
+        .or_else(|| {
+            context::home_dir().map(|mut p| {
+                p.push(".config");
+                p
+            })
+        })
+}
+
+/// Get the system configuration directories, in order of preference.
+pub(super) fn config_dirs() -> impl Iterator<Item = PathBuf> {
+    split_paths("XDG_CONFIG_DIRS", "/etc/xdg")
+}
+
+/// Split a colon-separated list of paths from an environment variable.
+fn split_paths(var: &str, default: &str) -> impl Iterator<Item = PathBuf> {
+    context::var(var)
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| default.into())
+        .split(':')
+        .filter(|path| !path.is_empty())
+        .map(context::path)
+        .collect::<Vec<PathBuf>>()
+        .into_iter()
+}
@@ -0,0 +1,597 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Code for building a theme out of the user's X resources.
+//!
+//! Window managers without a desktop environment leave theming to `~/.Xresources`, which usually
+//! holds a terminal palette: `*background`, `*foreground` and `*color0` through `*color15`.
+//! `xrdb` runs the file through the C preprocessor first, so we handle the directives that
+//! people actually use: `#define`, `#undef`, `#include` and the conditionals.
+
+use super::palette::Palette;
+use super::{context, files};
+use crate::util::HashMap;
+use crate::{Color, LoadThemeError, Theme, WidgetState, WIDGETS, WIDGET_STATES};
+
+use std::io;
+use std::path::Path;
+
+/// The deepest that `#include` directives can be nested.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// The deepest that macros can expand into other macros.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// The DPI that the default text sizes are meant for.
+const DEFAULT_DPI: f32 = 96.0;
+
+/// The states that use the normal colors.
+const ACTIVE_STATES: &[WidgetState] = &[
+    WidgetState::Enabled,
+    WidgetState::Focused,
+    WidgetState::Selected,
+    WidgetState::Hovered,
+    WidgetState::Pressed,
+    WidgetState::Checked,
+    WidgetState::Inactive,
+];
+
+/// Load a theme from `~/.Xresources`, or `~/.Xdefaults` if there isn't one.
+pub(super) fn load_theme() -> Result<Option<Theme>, LoadThemeError> {
+    let home = match context::home_dir() {
+        Some(home) => home,
+        None => return Ok(None),
+    };
+
+    let mut resources = None;
+    for file_name in [".Xresources", ".Xdefaults"] {
+        match Resources::open(&home.join(file_name)) {
+            Ok(file) => {
+                resources = Some(file);
+                break;
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(LoadThemeError(err)),
+        }
+    }
+
+    Ok(resources.and_then(|resources| resources.theme()))
+}
+
+/// The resources set in a file.
+#[derive(Debug, Default)]
+struct Resources {
+    /// Values by their resource name, with loose bindings normalized to `*name`.
+    values: HashMap<String, String>,
+}
+
+impl Resources {
+    /// Preprocess and parse a resource file.
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut preprocessor = Preprocessor::default();
+        preprocessor.include(path, 0)?;
+        Ok(Self::parse(&preprocessor.output))
+    }
+
+    /// Parse preprocessed resources.
+    fn parse(text: &str) -> Self {
+        let mut resources = Self::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('!') {
+                continue;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                // Later values replace earlier ones, like they do in `xrdb`.
+                let name = name
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>()
+                    .replace("*.", "*");
+                resources.values.insert(name, value.trim().to_string());
+            }
+        }
+
+        resources
+    }
+
+    /// Get a resource that applies to every program, like `*background` or `Xft.dpi`.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .or_else(|| self.values.get(&format!("*{}", name)))
+            .map(String::as_str)
+    }
+
+    /// Get a color resource.
+    fn color(&self, name: &str) -> Option<Color> {
+        self.get(name).and_then(parse_color)
+    }
+
+    /// Build a theme out of the palette.
+    fn theme(&self) -> Option<Theme> {
+        let background = self.color("background")?;
+        let foreground = self.color("foreground")?;
+
+        // Terminal palettes put blue at 4 and bright blue at 12.
+        let ansi = |index: usize| self.color(&format!("color{}", index));
+        let highlight = ansi(4).unwrap_or(foreground);
+
+        let active = Palette {
+            window: background,
+            window_text: foreground,
+            base: background,
+            text: foreground,
+            button: background.mix_by(foreground, 0.1),
+            button_text: foreground,
+            highlight,
+            highlighted_text: background,
+            tooltip_base: ansi(0).unwrap_or(background),
+            tooltip_text: foreground,
+            link: ansi(12).unwrap_or(highlight),
+        };
+
+        let disabled = Palette {
+            window_text: active.window_text.mix_by(background, 0.5),
+            text: active.text.mix_by(background, 0.5),
+            button_text: active.button_text.mix_by(background, 0.5),
+            tooltip_text: active.tooltip_text.mix_by(background, 0.5),
+            ..active.clone()
+        };
+
+        let mut theme = Theme::default_theme(active.shade());
+        theme.set_name("Xresources");
+        active.apply(&mut theme, ACTIVE_STATES);
+        disabled.apply(&mut theme, &[WidgetState::Disabled]);
+
+        // Scale the text for the screen's DPI.
+        if let Some(dpi) = self
+            .get("Xft.dpi")
+            .and_then(|dpi| dpi.parse::<f32>().ok())
+            .filter(|dpi| *dpi > 0.0)
+        {
+            for widget in WIDGETS {
+                for state in WIDGET_STATES {
+                    let props = theme.get_mut(*widget, *state);
+
+                    if let Some(mut text) = props.text_style().cloned() {
+                        text.set_size(text.size() * dpi / DEFAULT_DPI);
+                        props.set_text_style(text);
+                    }
+                }
+            }
+        }
+
+        Some(theme)
+    }
+}
+
+/// A subset of the C preprocessor.
+#[derive(Debug, Default)]
+struct Preprocessor {
+    /// Object-like macros, by name.
+    macros: HashMap<String, String>,
+
+    /// The preprocessed text.
+    output: String,
+}
+
+/// The state of one `#if` block.
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+    /// Lines in the current branch are kept.
+    active: bool,
+
+    /// One of the branches has already been kept.
+    taken: bool,
+
+    /// The block is inside of a branch that isn't kept.
+    skipped: bool,
+}
+
+impl Preprocessor {
+    /// Preprocess a file, adding it to the output.
+    fn include(&mut self, path: &Path, depth: usize) -> io::Result<()> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("#include nested too deeply at {:?}", path),
+            ));
+        }
+
+        let text = files::read_to_string(path)?;
+        self.process(&text, path, depth)
+    }
+
+    /// Preprocess the text of the file at `path`, adding it to the output.
+    fn process(&mut self, text: &str, path: &Path, depth: usize) -> io::Result<()> {
+        let text = strip_comments(text).replace("\\\n", "");
+        let mut conditionals: Vec<Conditional> = Vec::new();
+
+        for line in text.lines() {
+            let active = conditionals.last().is_none_or(|cond| cond.active);
+            let directive = match line.trim_start().strip_prefix('#') {
+                Some(directive) => directive.trim_start(),
+                None => {
+                    if active {
+                        let line = self.expand(line, 0);
+                        self.output.push_str(&line);
+                        self.output.push('\n');
+                    }
+                    continue;
+                }
+            };
+
+            let (name, rest) = directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""));
+            let rest = rest.trim();
+
+            match name {
+                "if" | "ifdef" | "ifndef" => {
+                    let condition = active
+                        && match name {
+                            "ifdef" => self.macros.contains_key(rest),
+                            "ifndef" => !self.macros.contains_key(rest),
+                            _ => self.evaluate(rest),
+                        };
+
+                    conditionals.push(Conditional {
+                        active: condition,
+                        taken: condition,
+                        skipped: !active,
+                    });
+                }
+                "elif" | "else" => {
+                    if let Some(cond) = conditionals.last().copied() {
+                        let condition =
+                            !cond.skipped && !cond.taken && (name == "else" || self.evaluate(rest));
+
+                        *conditionals.last_mut().unwrap() = Conditional {
+                            active: condition,
+                            taken: cond.taken || condition,
+                            ..cond
+                        };
+                    }
+                }
+                "endif" => {
+                    conditionals.pop();
+                }
+                _ if !active => {}
+                "define" => {
+                    let (macro_name, value) =
+                        rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+                    // Function-like macros aren't supported.
+                    if !macro_name.contains('(') {
+                        self.macros
+                            .insert(macro_name.to_string(), value.trim().to_string());
+                    }
+                }
+                "undef" => {
+                    self.macros.remove(rest);
+                }
+                "include" => {
+                    // Only local includes are supported, since there are no system headers for
+                    // resources.
+                    if let Some(file) = rest.strip_prefix('"').and_then(|r| r.split('"').next()) {
+                        let include = match Path::new(file).is_absolute() {
+                            true => context::path(file),
+                            false => path.parent().unwrap_or(Path::new("")).join(file),
+                        };
+
+                        match self.include(&include, depth + 1) {
+                            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                            result => result?,
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the macros in a line.
+    fn expand(&self, line: &str, depth: usize) -> String {
+        if depth > MAX_EXPANSION_DEPTH || self.macros.is_empty() {
+            return line.to_string();
+        }
+
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+
+        while let Some(c) = rest.chars().next() {
+            // Numbers and identifiers are whole tokens, so `#1abc` never expands `abc`.
+            if c.is_ascii_alphanumeric() || c == '_' {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let (token, after) = rest.split_at(end);
+
+                match self.macros.get(token) {
+                    Some(value) if !c.is_ascii_digit() => {
+                        result.push_str(&self.expand(value, depth + 1))
+                    }
+                    _ => result.push_str(token),
+                }
+
+                rest = after;
+            } else {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+
+        result
+    }
+
+    /// Evaluate the condition of an `#if` or `#elif`.
+    ///
+    /// Only numbers, `defined` and `!` are understood; anything else is false.
+    fn evaluate(&self, condition: &str) -> bool {
+        let condition = condition.trim();
+
+        if let Some(rest) = condition.strip_prefix('!') {
+            return !self.evaluate(rest);
+        }
+
+        if let Some(rest) = condition.strip_prefix("defined") {
+            let name = rest.trim().trim_start_matches('(').trim_end_matches(')');
+            return self.macros.contains_key(name.trim());
+        }
+
+        self.expand(condition, 0)
+            .trim()
+            .parse::<i64>()
+            .is_ok_and(|value| value != 0)
+    }
+}
+
+/// Remove C-style comments from a file.
+fn strip_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+
+        match rest[start + 2..].find("*/") {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => rest = "",
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Parse an X color, as `#rgb` in up to four digits per channel or as `rgb:r/g/b`.
+fn parse_color(value: &str) -> Option<Color> {
+    // Scale a channel of up to four hex digits down to eight bits.
+    let channel = |hex: &str| {
+        if hex.is_empty() || hex.len() > 4 {
+            return None;
+        }
+
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = (1u32 << (hex.len() * 4)) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    };
+
+    if let Some(spec) = value.strip_prefix("rgb:") {
+        let mut channels = spec.split('/');
+        let color = Color::new(
+            channel(channels.next()?)?,
+            channel(channels.next()?)?,
+            channel(channels.next()?)?,
+            255,
+        );
+        return channels.next().is_none().then_some(color);
+    }
+
+    let hex = value.strip_prefix('#')?;
+    if !hex.is_ascii() || hex.is_empty() || hex.len() % 3 != 0 {
+        return None;
+    }
+
+    // The old syntax keeps the most significant digits.
+    let len = hex.len() / 3;
+    let digits = len.min(2);
+    Some(Color::new(
+        channel(&hex[..digits])?,
+        channel(&hex[len..len + digits])?,
+        channel(&hex[2 * len..2 * len + digits])?,
+        255,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The directory with the files to include.
+    const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/xresources");
+
+    /// Preprocess some text as if it were in the data directory.
+    fn preprocess(text: &str) -> String {
+        let mut preprocessor = Preprocessor::default();
+        preprocessor
+            .process(text, &Path::new(DATA).join("test"), 0)
+            .unwrap();
+        preprocessor.output
+    }
+
+    #[test]
+    fn macros() {
+        let output = preprocess(
+            "#define BG #101010\n\
+             #define ALIAS BG\n\
+             *background: ALIAS\n\
+             *foreground: BGX\n\
+             #undef BG\n\
+             *color0: BG\n",
+        );
+        assert_eq!(
+            output,
+            "*background: #101010\n*foreground: BGX\n*color0: BG\n"
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let output = preprocess(
+            "#define DARK\n\
+             #define LEVEL 2\n\
+             #ifdef DARK\n\
+             #if LEVEL == 1\n\
+             a: 1\n\
+             #elif LEVEL\n\
+             a: 2\n\
+             #else\n\
+             a: 3\n\
+             #endif\n\
+             #ifndef DARK\n\
+             b: 1\n\
+             #elif 1\n\
+             b: 2\n\
+             #endif\n\
+             #else\n\
+             #if 1\n\
+             c: 1\n\
+             #else\n\
+             c: 2\n\
+             #endif\n\
+             #endif\n\
+             #if !defined(LIGHT)\n\
+             d: 1\n\
+             #endif\n",
+        );
+        assert_eq!(output, "a: 2\nb: 2\nd: 1\n");
+    }
+
+    #[test]
+    fn includes() {
+        // Missing files are skipped, like `xrdb` does with a warning.
+        let output = preprocess("#include \"colors\"\n#include \"missing\"\n*color0: #000\n");
+        assert_eq!(output, "*color4: #5f819d\n*color0: #000\n");
+
+        let mut preprocessor = Preprocessor::default();
+        let err = preprocessor
+            .include(&Path::new(DATA).join("cycle"), 0)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#abc"), Some(Color::new(0xaa, 0xbb, 0xcc, 255)));
+        assert_eq!(
+            parse_color("#102030"),
+            Some(Color::new(0x10, 0x20, 0x30, 255))
+        );
+        assert_eq!(
+            parse_color("#123456789"),
+            Some(Color::new(0x12, 0x45, 0x78, 255))
+        );
+        assert_eq!(
+            parse_color("rgb:f/80/ffff"),
+            Some(Color::new(0xff, 0x80, 0xff, 255))
+        );
+        assert_eq!(parse_color("rgb:0/8/0"), Some(Color::new(0, 0x88, 0, 255)));
+
+        for bad in [
+            "#12345",
+            "#",
+            "rgb:1/2",
+            "rgb:1/2/3/4",
+            "rgb:12345/0/0",
+            "red",
+        ] {
+            assert_eq!(parse_color(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let resources = Resources::parse(
+            "URxvt.background: #ff0000\n\
+             *background: #000000\n\
+             XTerm*foreground: #00ff00\n\
+             *.foreground: #ffffff\n\
+             ! *color1: #123456\n\
+             *color0: #111111\n\
+             *color15: #eeeeee\n\
+             *color0: #222222\n",
+        );
+
+        // Resources for one program don't apply to the theme.
+        assert_eq!(
+            resources.color("background"),
+            Some(Color::new(0, 0, 0, 255))
+        );
+        assert_eq!(
+            resources.color("foreground"),
+            Some(Color::new(255, 255, 255, 255))
+        );
+
+        // Later values win, and comments are skipped.
+        assert_eq!(
+            resources.color("color0"),
+            Some(Color::new(0x22, 0x22, 0x22, 255))
+        );
+        assert_eq!(
+            resources.color("color15"),
+            Some(Color::new(0xee, 0xee, 0xee, 255))
+        );
+        assert_eq!(resources.color("color1"), None);
+
+        // A fully bound resource wins over a loose one, wherever it is in the file.
+        let resources = Resources::parse("Xft.dpi: 192\n*Xft.dpi: 96\n*dpi: 72\n");
+        assert_eq!(resources.get("Xft.dpi"), Some("192"));
+        assert_eq!(resources.get("dpi"), Some("72"));
+
+        // Without a foreground, there's no theme.
+        assert!(Resources::parse("*background: #000000\n").theme().is_none());
+    }
+
+    #[test]
+    fn dpi() {
+        let text_size = |text: &str| {
+            let theme = Resources::parse(text).theme().unwrap();
+            theme
+                .get(crate::Widget::Button, WidgetState::Enabled)
+                .text_style()
+                .unwrap()
+                .size()
+        };
+
+        let palette = "*background: #000000\n*foreground: #ffffff\n";
+        let normal = text_size(palette);
+        assert_eq!(text_size(&format!("{}Xft.dpi: 96\n", palette)), normal);
+        assert_eq!(
+            text_size(&format!("{}Xft.dpi: 144\n", palette)),
+            normal * 1.5
+        );
+        assert_eq!(text_size(&format!("{}Xft.dpi: 0\n", palette)), normal);
+        assert_eq!(text_size(&format!("{}Xft.dpi: big\n", palette)), normal);
+    }
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Icons from the system's icon theme.
+
+use crate::{platform, LoadContext, LoadThemeError};
+
+use alloc::string::String;
+use std::path::PathBuf;
+
+/// An icon theme, used to find the files for icons by their names.
+///
+/// Icons that the theme doesn't have are taken from the themes that it builds on.
+#[derive(Debug, Clone)]
+pub struct IconTheme {
+    inner: platform::IconTheme,
+}
+
+impl IconTheme {
+    /// Load the icon theme that the user picked, or the one with the provided name.
+    ///
+    /// Returns `None` on platforms that don't have icon themes.
+    pub async fn load(name: impl Into<Option<&str>>) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_icon_theme(&LoadContext::new(), name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load an icon theme using the blocking API.
+    pub fn load_blocking<'a>(
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_icon_theme_blocking(&LoadContext::new(), name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load an icon theme, as seen from the provided context.
+    pub async fn load_with_context(
+        context: &LoadContext,
+        name: impl Into<Option<&str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_icon_theme(context, name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load an icon theme, as seen from the provided context, using the blocking API.
+    pub fn load_blocking_with_context<'a>(
+        context: &LoadContext,
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_icon_theme_blocking(context, name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Get the name of the icon theme.
+    ///
+    /// If the theme that was asked for isn't installed, this is the theme that is used instead.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Get the name of the icon theme, as shown to the user.
+    pub fn display_name(&self) -> Option<&str> {
+        self.inner.display_name()
+    }
+
+    /// Find the file for an icon, like `document-open`.
+    ///
+    /// The `size` is in logical pixels, and is multiplied by `scale` for HiDPI screens. An icon
+    /// made for the size is preferred, and the closest size is used otherwise. The file may be an
+    /// SVG, PNG or XPM image.
+    pub async fn find_icon(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let inner = self.inner.clone();
+        let icon = String::from(icon);
+        blocking::unblock(move || inner.find_icon(&icon, size, scale)).await
+    }
+
+    /// Find the file for an icon using the blocking API.
+    pub fn find_icon_blocking(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        self.inner.find_icon(icon, size, scale)
+    }
+}
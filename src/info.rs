@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Information about the themes that are installed.
+
+use alloc::string::String;
+use std::path::{Path, PathBuf};
+
+/// A theme that can be loaded, as listed by [`Theme::available`].
+///
+/// [`Theme::available`]: crate::Theme::available
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeInfo {
+    /// The name to load the theme by.
+    name: String,
+
+    /// Where the theme comes from.
+    source: ThemeSource,
+
+    /// The file or directory the theme is in.
+    path: Option<PathBuf>,
+
+    /// Whether there is a light variant.
+    light: bool,
+
+    /// Whether there is a dark variant.
+    dark: bool,
+}
+
+impl ThemeInfo {
+    /// Describe a theme.
+    pub(crate) fn new(
+        name: impl Into<String>,
+        source: ThemeSource,
+        path: Option<PathBuf>,
+        light: bool,
+        dark: bool,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            path,
+            light,
+            dark,
+        }
+    }
+
+    /// Get the name to load the theme by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get where the theme comes from.
+    pub fn source(&self) -> ThemeSource {
+        self.source
+    }
+
+    /// Get the file or directory that the theme is in.
+    ///
+    /// This is `None` for the built-in themes.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Tell whether the theme has a light variant.
+    pub fn has_light_variant(&self) -> bool {
+        self.light
+    }
+
+    /// Tell whether the theme has a dark variant.
+    pub fn has_dark_variant(&self) -> bool {
+        self.dark
+    }
+}
+
+/// Where a theme comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThemeSource {
+    /// The theme is built into this crate.
+    BuiltIn,
+
+    /// The theme is a GTK theme.
+    Gtk,
+
+    /// The theme is a KDE color scheme.
+    Kde,
+}
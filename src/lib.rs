@@ -24,11 +24,24 @@ extern crate alloc;
 
 mod border;
 mod color;
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "std")]
+mod cursor;
 mod default_theme;
 mod fill;
+#[cfg(feature = "std")]
+mod font;
+#[cfg(feature = "std")]
+mod icon;
+#[cfg(feature = "std")]
+mod info;
 mod margin;
+mod metadata;
 mod properties;
 mod shadow;
+#[cfg(feature = "std")]
+mod sound;
 mod text;
 mod util;
 mod widget;
@@ -50,16 +63,34 @@ cfg_if::cfg_if! {
 
 use core::fmt;
 use core::hash::Hash;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 
+use alloc::boxed::Box;
 use alloc::string::String;
 
+use futures_lite::Stream;
+
 pub use border::Border;
 pub use color::Color;
+#[cfg(feature = "std")]
+pub use context::{GtkVersion, LoadContext};
+#[cfg(feature = "std")]
+pub use cursor::{Cursor, CursorImage, CursorTheme};
 pub use fill::Fill;
+#[cfg(feature = "std")]
+pub use font::{FontResolver, ResolvedFont};
+#[cfg(feature = "std")]
+pub use icon::IconTheme;
+#[cfg(feature = "std")]
+pub use info::{ThemeInfo, ThemeSource};
 pub use margin::Margin;
+pub use metadata::ThemeMetadata;
 pub use properties::WidgetProperties;
 pub use shadow::Shadow;
-pub use text::{FontFamily, TextAlignment, TextStyle};
+#[cfg(feature = "std")]
+pub use sound::SoundTheme;
+pub use text::{FontFamily, FontStretch, TextAlignment, TextStyle};
 pub use widget::{Widget, WidgetState};
 
 use util::{HashMap, HashMapExt};
@@ -72,6 +103,10 @@ pub struct Theme {
     /// The name of the theme.
     name: String,
 
+    /// Information about the theme, like where it comes from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    metadata: ThemeMetadata,
+
     /// Widgets, their states and their properties.
     properties: HashMap<Key, WidgetProperties>,
 }
@@ -90,7 +125,7 @@ impl Theme {
         name: impl Into<Option<&str>>,
         shade: ShadePreference,
     ) -> Result<Self, LoadThemeError> {
-        platform::load_theme(name.into(), shade).await
+        platform::load_theme(&LoadContext::new(), name.into(), shade).await
     }
 
     /// Load a theme from the system using the blocking API.
@@ -98,7 +133,105 @@ impl Theme {
         name: impl Into<Option<&'a str>>,
         shade: ShadePreference,
     ) -> Result<Self, LoadThemeError> {
-        platform::load_theme_blocking(name.into(), shade)
+        platform::load_theme_blocking(&LoadContext::new(), name.into(), shade)
+    }
+
+    /// Load a theme from the system, as seen from the provided context.
+    pub async fn load_with_context(
+        context: &LoadContext,
+        name: impl Into<Option<&str>>,
+        shade: ShadePreference,
+    ) -> Result<Self, LoadThemeError> {
+        platform::load_theme(context, name.into(), shade).await
+    }
+
+    /// Load a theme from the system, as seen from the provided context, using the blocking API.
+    pub fn load_blocking_with_context<'a>(
+        context: &LoadContext,
+        name: impl Into<Option<&'a str>>,
+        shade: ShadePreference,
+    ) -> Result<Self, LoadThemeError> {
+        platform::load_theme_blocking(context, name.into(), shade)
+    }
+
+    /// List the themes that are installed on the system, along with the built-in ones.
+    ///
+    /// A theme that is installed more than once is listed once, for the copy that would be
+    /// loaded.
+    pub async fn available() -> Result<Vec<ThemeInfo>, LoadThemeError> {
+        platform::available_themes(&LoadContext::new()).await
+    }
+
+    /// List the themes that are installed on the system using the blocking API.
+    pub fn available_blocking() -> Result<Vec<ThemeInfo>, LoadThemeError> {
+        platform::available_themes_blocking(&LoadContext::new())
+    }
+
+    /// List the themes that are installed on the system, as seen from the provided context.
+    pub async fn available_with_context(
+        context: &LoadContext,
+    ) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+        platform::available_themes(context).await
+    }
+
+    /// List the themes that are installed on the system, as seen from the provided context, using
+    /// the blocking API.
+    pub fn available_blocking_with_context(
+        context: &LoadContext,
+    ) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+        platform::available_themes_blocking(context)
+    }
+
+    /// Get the accent color that the user picked for the system, if there is one.
+    pub async fn system_accent_color() -> Result<Option<Color>, LoadThemeError> {
+        platform::accent_color(&LoadContext::new()).await
+    }
+
+    /// Get the accent color that the user picked for the system using the blocking API.
+    pub fn system_accent_color_blocking() -> Result<Option<Color>, LoadThemeError> {
+        platform::accent_color_blocking(&LoadContext::new())
+    }
+
+    /// Get the accent color that the user picked, as seen from the provided context.
+    pub async fn system_accent_color_with_context(
+        context: &LoadContext,
+    ) -> Result<Option<Color>, LoadThemeError> {
+        platform::accent_color(context).await
+    }
+
+    /// Get the accent color that the user picked, as seen from the provided context, using the
+    /// blocking API.
+    pub fn system_accent_color_blocking_with_context(
+        context: &LoadContext,
+    ) -> Result<Option<Color>, LoadThemeError> {
+        platform::accent_color_blocking(context)
+    }
+
+    /// Watch the system for changes to its theme.
+    ///
+    /// The returned stream yields the theme, loaded the same way as [`Theme::load`], every time
+    /// that the user changes a setting that it depends on, like switching to dark mode, or edits
+    /// one of the files that it was loaded from. Changes that come in quick succession only load
+    /// the theme once. It never yields on platforms where we can't tell when the theme changes.
+    pub async fn watch(
+        name: impl Into<Option<&str>>,
+        shade: ShadePreference,
+    ) -> Result<ThemeChanges, LoadThemeError> {
+        Self::watch_with_context(&LoadContext::new(), name, shade).await
+    }
+
+    /// Watch the system, as seen from the provided context, for changes to its theme.
+    pub async fn watch_with_context(
+        context: &LoadContext,
+        name: impl Into<Option<&str>>,
+        shade: ShadePreference,
+    ) -> Result<ThemeChanges, LoadThemeError> {
+        let name = name.into().map(String::from);
+        let inner = platform::watch(context, name, shade).await?;
+
+        Ok(ThemeChanges {
+            inner: Box::pin(inner),
+        })
     }
 
     /// Load the default theme.
@@ -106,9 +239,18 @@ impl Theme {
         default_theme::default_theme(shade)
     }
 
+    /// Load the default theme, or its high contrast variant.
+    pub fn default_theme_with_contrast(
+        shade: ShadePreference,
+        contrast: ContrastPreference,
+    ) -> Self {
+        default_theme::default_theme_with(shade, contrast, None)
+    }
+
     fn empty(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            metadata: ThemeMetadata::default(),
             properties: {
                 let mut map = HashMap::with_capacity(WIDGETS.len() * WIDGET_STATES.len());
 
@@ -134,6 +276,16 @@ impl Theme {
         self.name = name.into();
     }
 
+    /// Get the information that describes the theme.
+    pub fn metadata(&self) -> &ThemeMetadata {
+        &self.metadata
+    }
+
+    /// Get a mutable reference to the information that describes the theme.
+    pub fn metadata_mut(&mut self) -> &mut ThemeMetadata {
+        &mut self.metadata
+    }
+
     /// Get the properties of a widget.
     pub fn get(&self, widget: Widget, state: WidgetState) -> &WidgetProperties {
         // First, try with the state.
@@ -153,9 +305,28 @@ impl Theme {
     ///
     /// This will insert the properties if they don't exist.
     pub fn get_mut(&mut self, widget: Widget, state: WidgetState) -> &mut WidgetProperties {
-        self.properties
-            .entry((widget, state))
-            .or_insert_with(WidgetProperties::default)
+        self.properties.entry((widget, state)).or_default()
+    }
+}
+
+/// A stream of themes, loaded whenever the system's theme changes.
+///
+/// This is returned by [`Theme::watch`].
+pub struct ThemeChanges {
+    inner: Pin<Box<dyn Stream<Item = Result<Theme, LoadThemeError>> + Send + 'static>>,
+}
+
+impl fmt::Debug for ThemeChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThemeChanges").finish_non_exhaustive()
+    }
+}
+
+impl Stream for ThemeChanges {
+    type Item = Result<Theme, LoadThemeError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
     }
 }
 
@@ -170,6 +341,17 @@ pub enum ShadePreference {
     Dark,
 }
 
+/// Whether or not to prefer high contrast themes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContrastPreference {
+    /// Prefer themes with the usual contrast.
+    Normal,
+
+    /// Prefer high contrast themes.
+    High,
+}
+
 /// The error associated with loading a theme.
 pub struct LoadThemeError(ErrorImpl);
 
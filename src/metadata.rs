@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Descriptive information that comes with a theme.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Information about a theme that isn't about how widgets look.
+///
+/// Themes on disk usually describe themselves in a file next to their stylesheets. Any of these
+/// may be missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThemeMetadata {
+    /// The name of the theme, as shown to the user.
+    display_name: Option<String>,
+
+    /// A description of the theme.
+    description: Option<String>,
+
+    /// The icon theme that goes with the theme.
+    icon_theme: Option<String>,
+
+    /// The cursor theme that goes with the theme.
+    cursor_theme: Option<String>,
+
+    /// Problems found in the theme that didn't stop it from loading.
+    #[cfg_attr(feature = "serde", serde(default))]
+    diagnostics: Vec<String>,
+}
+
+impl ThemeMetadata {
+    /// Create empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the name of the theme, as shown to the user.
+    pub fn display_name(&self) -> Option<&str> {
+        self.display_name.as_deref()
+    }
+
+    /// Set the name of the theme, as shown to the user.
+    pub fn set_display_name(&mut self, name: Option<String>) {
+        self.display_name = name;
+    }
+
+    /// Get the description of the theme.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Set the description of the theme.
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+
+    /// Get the name of the icon theme that goes with the theme.
+    pub fn icon_theme(&self) -> Option<&str> {
+        self.icon_theme.as_deref()
+    }
+
+    /// Set the name of the icon theme that goes with the theme.
+    pub fn set_icon_theme(&mut self, icon_theme: Option<String>) {
+        self.icon_theme = icon_theme;
+    }
+
+    /// Get the name of the cursor theme that goes with the theme.
+    pub fn cursor_theme(&self) -> Option<&str> {
+        self.cursor_theme.as_deref()
+    }
+
+    /// Set the name of the cursor theme that goes with the theme.
+    pub fn set_cursor_theme(&mut self, cursor_theme: Option<String>) {
+        self.cursor_theme = cursor_theme;
+    }
+
+    /// Get the problems found in the theme that didn't stop it from loading, like a cycle in the
+    /// colors that a stylesheet defines.
+    ///
+    /// The parts of the theme with these problems are left out, like the theme's platform would.
+    pub fn diagnostics(&self) -> &[String] {
+        &self.diagnostics
+    }
+
+    /// Set the problems found in the theme that didn't stop it from loading.
+    pub fn set_diagnostics(&mut self, diagnostics: Vec<String>) {
+        self.diagnostics = diagnostics;
+    }
+
+    /// Fill in anything that is missing from another theme's metadata.
+    #[cfg_attr(not(free_unix), allow(dead_code))]
+    pub(crate) fn inherit(&mut self, parent: &ThemeMetadata) {
+        macro_rules! inherit {
+            ($($field:ident),*) => {$(
+                if self.$field.is_none() {
+                    self.$field.clone_from(&parent.$field);
+                }
+            )*};
+        }
+
+        inherit!(display_name, description, icon_theme, cursor_theme);
+
+        // A theme without a stylesheet of its own has the problems of its parent's.
+        if self.diagnostics.is_empty() {
+            self.diagnostics.clone_from(&parent.diagnostics);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Event sounds from the system's sound theme.
+
+use crate::{platform, LoadContext, LoadThemeError};
+
+use alloc::string::String;
+use std::path::PathBuf;
+
+/// A sound theme, used to find the files for event sounds by their names.
+///
+/// Sounds that the theme doesn't have are taken from the themes that it builds on.
+#[derive(Debug, Clone)]
+pub struct SoundTheme {
+    inner: platform::SoundTheme,
+}
+
+impl SoundTheme {
+    /// Load the sound theme that the user picked, or the one with the provided name.
+    ///
+    /// Returns `None` on platforms that don't have sound themes.
+    pub async fn load(name: impl Into<Option<&str>>) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_sound_theme(&LoadContext::new(), name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a sound theme using the blocking API.
+    pub fn load_blocking<'a>(
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_sound_theme_blocking(&LoadContext::new(), name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a sound theme, as seen from the provided context.
+    pub async fn load_with_context(
+        context: &LoadContext,
+        name: impl Into<Option<&str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_sound_theme(context, name.into()).await?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Load a sound theme, as seen from the provided context, using the blocking API.
+    pub fn load_blocking_with_context<'a>(
+        context: &LoadContext,
+        name: impl Into<Option<&'a str>>,
+    ) -> Result<Option<Self>, LoadThemeError> {
+        let inner = platform::load_sound_theme_blocking(context, name.into())?;
+        Ok(inner.map(|inner| Self { inner }))
+    }
+
+    /// Get the name of the sound theme.
+    ///
+    /// If the theme that was asked for isn't installed, this is the theme that is used instead.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Get the name of the sound theme, as shown to the user.
+    pub fn display_name(&self) -> Option<&str> {
+        self.inner.display_name()
+    }
+
+    /// Tell whether the user wants sounds to be played for events.
+    ///
+    /// Sounds are still found when this is off, so that they can be played for things that the
+    /// user asked for.
+    pub fn event_sounds_enabled(&self) -> bool {
+        self.inner.event_sounds_enabled()
+    }
+
+    /// Find the file for a sound, like `dialog-warning` or `bell`.
+    ///
+    /// A sound that the themes don't have falls back to a more generic one, by dropping the
+    /// last part of its name. Returns `None` if the theme turns the sound off.
+    pub async fn find_sound(&self, sound: &str) -> Option<PathBuf> {
+        let inner = self.inner.clone();
+        let sound = String::from(sound);
+        blocking::unblock(move || inner.find_sound(&sound)).await
+    }
+
+    /// Find the file for a sound using the blocking API.
+    pub fn find_sound_blocking(&self, sound: &str) -> Option<PathBuf> {
+        self.inner.find_sound(sound)
+    }
+}
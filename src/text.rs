@@ -37,6 +37,10 @@ pub struct TextStyle {
     /// The font is italic.
     italic: bool,
 
+    /// How wide the font is.
+    #[cfg_attr(feature = "serde", serde(default))]
+    stretch: FontStretch,
+
     /// The font is underlined.
     underline: bool,
 
@@ -62,6 +66,7 @@ impl TextStyle {
             orientation: 0.0,
             weight: 400,
             italic: false,
+            stretch: FontStretch::Normal,
             underline: false,
             strikethrough: false,
             color: Color::new(0, 0, 0, 0xFF),
@@ -125,6 +130,17 @@ impl TextStyle {
         self
     }
 
+    /// Get the font stretch.
+    pub fn stretch(&self) -> FontStretch {
+        self.stretch
+    }
+
+    /// Set the font stretch.
+    pub fn set_stretch(&mut self, stretch: FontStretch) -> &mut Self {
+        self.stretch = stretch;
+        self
+    }
+
     /// Get the underline flag.
     pub fn underline(&self) -> bool {
         self.underline
@@ -198,6 +214,18 @@ pub enum FontFamily {
     Custom(String),
 }
 
+impl FontFamily {
+    /// Get the font family for a family name, recognizing the generic family names.
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "monospace" | "Monospace" => Self::Monospace,
+            "sans-serif" | "sans" | "Sans" => Self::SansSerif,
+            "serif" | "Serif" => Self::Serif,
+            name => Self::Custom(name.into()),
+        }
+    }
+}
+
 impl From<String> for FontFamily {
     fn from(family: String) -> Self {
         Self::Custom(family)
@@ -210,6 +238,39 @@ impl From<&str> for FontFamily {
     }
 }
 
+/// How wide the glyphs of a font are, relative to its normal width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontStretch {
+    /// 50% of the normal width.
+    UltraCondensed,
+
+    /// 62.5% of the normal width.
+    ExtraCondensed,
+
+    /// 75% of the normal width.
+    Condensed,
+
+    /// 87.5% of the normal width.
+    SemiCondensed,
+
+    /// The normal width.
+    #[default]
+    Normal,
+
+    /// 112.5% of the normal width.
+    SemiExpanded,
+
+    /// 125% of the normal width.
+    Expanded,
+
+    /// 150% of the normal width.
+    ExtraExpanded,
+
+    /// 200% of the normal width.
+    UltraExpanded,
+}
+
 /// Text alignment.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -183,7 +183,7 @@ pub(crate) const WIDGETS: &[Widget] = &[
 ];
 
 /// Widget states.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum WidgetState {
@@ -191,6 +191,7 @@ pub enum WidgetState {
     Disabled,
 
     /// The widget is enabled.
+    #[default]
     Enabled,
 
     /// The widget is focused.
@@ -207,6 +208,9 @@ pub enum WidgetState {
 
     /// The widget is checked.
     Checked,
+
+    /// The widget is in a window that isn't focused.
+    Inactive,
 }
 
 pub(crate) const WIDGET_STATES: &[WidgetState] = &[
@@ -217,10 +221,5 @@ pub(crate) const WIDGET_STATES: &[WidgetState] = &[
     WidgetState::Hovered,
     WidgetState::Pressed,
     WidgetState::Checked,
+    WidgetState::Inactive,
 ];
-
-impl Default for WidgetState {
-    fn default() -> Self {
-        Self::Enabled
-    }
-}
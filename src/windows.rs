@@ -25,11 +25,20 @@ use std::io;
 use std::sync::atomic::{AtomicIsize, Ordering};
 use std::sync::Once;
 
-use crate::{LoadThemeError, ShadePreference, Theme};
+use crate::{Color, LoadContext, LoadThemeError, ShadePreference, Theme, ThemeInfo};
+
+use futures_lite::Stream;
+
+pub(super) use crate::default_theme::{
+    load_cursor_theme, load_cursor_theme_blocking, load_font_resolver, load_font_resolver_blocking,
+    load_icon_theme, load_icon_theme_blocking, load_sound_theme, load_sound_theme_blocking,
+    CursorTheme, FontResolver, IconTheme, SoundTheme,
+};
 
 // TODO: wintheme
 
 pub(super) fn load_theme_blocking(
+    _context: &LoadContext,
     name: Option<&str>,
     shade: ShadePreference,
 ) -> Result<Theme, LoadThemeError> {
@@ -37,10 +46,44 @@ pub(super) fn load_theme_blocking(
 }
 
 pub(super) fn load_theme(
+    context: &LoadContext,
     name: Option<&str>,
     shade: ShadePreference,
 ) -> impl Future<Output = Result<Theme, LoadThemeError>> + Send {
     // load_theme_blocking reads from files, so we need to unblock it.
+    let context = context.clone();
     let name = name.map(|s| s.to_owned());
-    blocking::unblock(move || load_theme_blocking(name.as_deref(), shade))
+    blocking::unblock(move || load_theme_blocking(&context, name.as_deref(), shade))
+}
+
+pub(super) async fn watch(
+    _context: &LoadContext,
+    _name: Option<String>,
+    _shade: ShadePreference,
+) -> Result<impl Stream<Item = Result<Theme, LoadThemeError>> + Send + 'static, LoadThemeError> {
+    // TODO: Listen for WM_SETTINGCHANGE.
+    Ok(futures_lite::stream::pending())
+}
+
+pub(super) fn available_themes_blocking(
+    _context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    Ok(crate::default_theme::built_in_themes())
+}
+
+pub(super) async fn available_themes(
+    context: &LoadContext,
+) -> Result<Vec<ThemeInfo>, LoadThemeError> {
+    available_themes_blocking(context)
+}
+
+pub(super) fn accent_color_blocking(
+    _context: &LoadContext,
+) -> Result<Option<Color>, LoadThemeError> {
+    // TODO: Read the accent color from DWM.
+    Ok(None)
+}
+
+pub(super) async fn accent_color(context: &LoadContext) -> Result<Option<Color>, LoadThemeError> {
+    accent_color_blocking(context)
 }
@@ -0,0 +1,680 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Loading themes out of the directory trees in `tests/fixtures`, which stand in for the root
+//! filesystem of a desktop.
+
+#![cfg(free_unix)]
+
+use ui_theme::{
+    Color, CursorTheme, Fill, FontFamily, FontResolver, GtkVersion, IconTheme, LoadContext,
+    ShadePreference, SoundTheme, TextStyle, Theme, ThemeSource, Widget, WidgetState,
+};
+
+use std::path::PathBuf;
+
+/// Get the path of a fixture.
+fn fixture(fixture: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", fixture]
+        .iter()
+        .collect()
+}
+
+/// Get a context that reads out of a fixture, with the given environment variables.
+fn context(fixture: &str, env: &[(&str, &str)]) -> LoadContext {
+    let mut context = LoadContext::new();
+    context
+        .set_env(env.iter().copied().chain([("HOME", "/home/user")]))
+        .set_sysroot(self::fixture(fixture));
+    context
+}
+
+/// Load the theme out of a fixture, with the given environment variables.
+fn load(fixture: &str, env: &[(&str, &str)]) -> Theme {
+    Theme::load_blocking_with_context(&context(fixture, env), None, ShadePreference::Light).unwrap()
+}
+
+/// Get the background color of a button.
+fn button_background(theme: &Theme) -> Option<&Fill> {
+    theme.get(Widget::Button, WidgetState::Enabled).background()
+}
+
+#[test]
+fn gnome_dconf() {
+    let theme = load("gnome", &[("XDG_CURRENT_DESKTOP", "GNOME")]);
+
+    // The theme is locked in the `local` database, ahead of the user's choice of `Other`.
+    assert_eq!(theme.name(), "Fixture");
+    assert_eq!(theme.metadata().display_name(), Some("Fixture Theme"));
+
+    let button = theme.get(Widget::Button, WidgetState::Enabled);
+    assert_eq!(
+        button.background(),
+        Some(&Fill::Color(Color::new(0x20, 0x30, 0x40, 0xff)))
+    );
+
+    // The font is the user's, scaled by the factor from the `site` database.
+    let text = button.text_style().unwrap();
+    assert_eq!(text.color(), Color::new(0xf0, 0xe0, 0xd0, 0xff));
+    assert_eq!(text.family(), &FontFamily::Custom("Cantarell".into()));
+    assert_eq!(text.weight(), 700);
+    assert_eq!(text.size(), 24.0);
+}
+
+#[test]
+fn gtk_theme_env() {
+    // The environment beats the theme locked in dconf, and picks the variant.
+    let theme = load(
+        "gnome",
+        &[
+            ("XDG_CURRENT_DESKTOP", "GNOME"),
+            ("GTK_THEME", "Other:dark"),
+        ],
+    );
+    assert_eq!(theme.name(), "Other");
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0x40, 0x00, 0x00, 0xff)))
+    );
+
+    // Without a variant, the preferred shade decides.
+    let theme = load(
+        "gnome",
+        &[("XDG_CURRENT_DESKTOP", "GNOME"), ("GTK_THEME", "Other")],
+    );
+    assert_eq!(theme.name(), "Other");
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0xff, 0x00, 0x00, 0xff)))
+    );
+
+    // The settings still apply on top, like the font from dconf.
+    let text = theme
+        .get(Widget::Button, WidgetState::Enabled)
+        .text_style()
+        .unwrap();
+    assert_eq!(text.family(), &FontFamily::Custom("Cantarell".into()));
+
+    // A theme that isn't installed is ignored.
+    let theme = load(
+        "gnome",
+        &[
+            ("XDG_CURRENT_DESKTOP", "GNOME"),
+            ("GTK_THEME", "Missing:dark"),
+        ],
+    );
+    assert_eq!(theme.name(), "Fixture");
+}
+
+#[test]
+fn kde() {
+    let theme = load("kde", &[("XDG_CURRENT_DESKTOP", "KDE")]);
+
+    // The user's `kdeglobals` takes precedence over the system's.
+    assert_eq!(theme.name(), "FixtureDark");
+    assert_eq!(theme.metadata().display_name(), Some("Fixture Dark"));
+
+    let button = theme.get(Widget::Button, WidgetState::Enabled);
+    assert_eq!(
+        button.background(),
+        Some(&Fill::Color(Color::new(16, 24, 32, 255)))
+    );
+
+    let text = button.text_style().unwrap();
+    assert_eq!(text.color(), Color::new(255, 255, 255, 255));
+    assert_eq!(text.family(), &FontFamily::Custom("Inter".into()));
+    assert_eq!(text.weight(), 700);
+    assert!(text.italic());
+
+    // `kcmfonts` forces a DPI of 120 instead of 96.
+    assert_eq!(text.size(), 20.0);
+}
+
+#[test]
+fn settings_ini() {
+    // Window managers like sway name themselves as the desktop, but those started without one
+    // read `settings.ini` all the same.
+    let desktops: [&[(&str, &str)]; 2] = [&[("XDG_CURRENT_DESKTOP", "sway")], &[]];
+    for env in desktops {
+        let theme = load("settings-ini", env);
+
+        // The user asked for the dark variant of the theme.
+        assert_eq!(theme.name(), "Fixture", "{:?}", env);
+        let button = theme.get(Widget::Button, WidgetState::Enabled);
+        assert_eq!(
+            button.background(),
+            Some(&Fill::Color(Color::new(0x10, 0x18, 0x20, 0xff)))
+        );
+
+        // The font comes from the system's `settings.ini`, since the user's doesn't set one.
+        let text = button.text_style().unwrap();
+        assert_eq!(text.family(), &FontFamily::Custom("DejaVu Sans".into()));
+        assert_eq!(text.size(), 12.0);
+    }
+}
+
+#[test]
+fn qt5ct() {
+    // The color scheme is at an absolute path, which is inside of the fixture.
+    let theme = load("qt5ct", &[("QT_QPA_PLATFORMTHEME", "qt5ct")]);
+
+    assert_eq!(theme.name(), "fixture");
+    let button = theme.get(Widget::Button, WidgetState::Enabled);
+    assert_eq!(
+        button.background(),
+        Some(&Fill::Color(Color::new(0x42, 0x42, 0x45, 0xff)))
+    );
+}
+
+#[test]
+fn nested_imports() {
+    let context = context("gtk", &[]);
+    let theme = Theme::load_blocking_with_context(&context, Some("Nested"), ShadePreference::Light)
+        .unwrap();
+
+    // Each import is relative to the stylesheet it's in, and missing ones are skipped.
+    assert_eq!(theme.name(), "Nested");
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0x44, 0x55, 0x66, 0xff)))
+    );
+
+    let text = theme
+        .get(Widget::Button, WidgetState::Enabled)
+        .text_style()
+        .unwrap();
+    assert_eq!(text.color(), Color::new(0x11, 0x22, 0x33, 0xff));
+}
+
+#[test]
+fn gtk_versions() {
+    let load_both = |context: &LoadContext| {
+        let theme =
+            Theme::load_blocking_with_context(context, Some("Both"), ShadePreference::Light)
+                .unwrap();
+        let button = theme.get(Widget::Button, WidgetState::Enabled);
+        (
+            button.background().cloned(),
+            button.text_style().unwrap().color(),
+        )
+    };
+
+    // GTK 3 is preferred by default, and mixes colors without premultiplying them.
+    let gtk3 = (
+        Some(Fill::Color(Color::new(0x33, 0x33, 0x33, 0xff))),
+        Color::new(128, 0, 128, 192),
+    );
+    let gtk4 = (
+        Some(Fill::Color(Color::new(0x44, 0x44, 0x44, 0xff))),
+        Color::new(85, 0, 170, 192),
+    );
+    let mut context = context("gtk", &[]);
+    assert_eq!(load_both(&context), gtk3);
+    assert_eq!(load_both(context.set_gtk_version(GtkVersion::Gtk4)), gtk4);
+    assert_eq!(load_both(context.set_gtk_version(GtkVersion::Gtk3)), gtk3);
+
+    // A theme for only the other version is still used.
+    let theme =
+        Theme::load_blocking_with_context(&context, Some("Gtk4Only"), ShadePreference::Light)
+            .unwrap();
+    assert_eq!(theme.name(), "Gtk4Only");
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0x44, 0x44, 0x44, 0xff)))
+    );
+    // The user's copy of a theme beats the system's, even if only the system's is for the
+    // preferred version.
+    let theme =
+        Theme::load_blocking_with_context(&context, Some("Split"), ShadePreference::Light).unwrap();
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0x55, 0x55, 0x55, 0xff)))
+    );
+}
+
+#[test]
+fn color_cycle() {
+    let context = context("gtk", &[]);
+    let theme =
+        Theme::load_blocking_with_context(&context, Some("ColorCycle"), ShadePreference::Light)
+            .unwrap();
+
+    // The declaration with a color in a cycle is dropped, and the rest still apply.
+    let button = theme.get(Widget::Button, WidgetState::Enabled);
+    assert_eq!(
+        button.background(),
+        Some(&Fill::Color(Color::new(0x33, 0x33, 0x33, 0xff)))
+    );
+    assert_eq!(
+        button.text_style().unwrap().color(),
+        Color::new(0x10, 0x20, 0x30, 0xff)
+    );
+
+    // The cycle is reported along with the theme.
+    assert_eq!(
+        theme.metadata().diagnostics(),
+        ["cycle in @define-color: @bg_color -> @base_color -> @bg_color"]
+    );
+}
+
+#[test]
+fn resource_imports() {
+    let context = context("gtk", &[]);
+    let theme =
+        Theme::load_blocking_with_context(&context, Some("Bundled"), ShadePreference::Light)
+            .unwrap();
+
+    // `gtk.css` imports a stylesheet out of `gtk.gresource`, which imports another one next to it
+    // in the bundle.
+    assert_eq!(theme.name(), "Bundled");
+    assert_eq!(
+        button_background(&theme),
+        Some(&Fill::Color(Color::new(0xdd, 0xee, 0xff, 0xff)))
+    );
+
+    let text = theme
+        .get(Widget::Button, WidgetState::Enabled)
+        .text_style()
+        .unwrap();
+    assert_eq!(text.color(), Color::new(0x33, 0x66, 0x99, 0xff));
+
+    // Images are read out of the bundle too, whether the stylesheet is in it or not.
+    let png = b"\x89PNG\r\n\x1a\nfixture check".to_vec();
+    for widget in [Widget::Checkbox, Widget::RadioButton] {
+        assert_eq!(
+            theme.get(widget, WidgetState::Enabled).background(),
+            Some(&Fill::Image(png.clone())),
+            "{:?}",
+            widget
+        );
+    }
+}
+
+#[test]
+fn xresources() {
+    // i3 names itself as the desktop, but has no settings of its own, so the resources are used
+    // like they are without a desktop.
+    for desktop in ["i3", ""] {
+        let theme = load("xresources", &[("XDG_CURRENT_DESKTOP", desktop)]);
+        assert_eq!(theme.name(), "Xresources", "{:?}", desktop);
+
+        let text = theme
+            .get(Widget::Button, WidgetState::Enabled)
+            .text_style()
+            .unwrap();
+        assert_eq!(text.color(), Color::new(0xc5, 0xc8, 0xc6, 0xff));
+
+        // `Xft.dpi` doubles the text size of the default theme.
+        let default = Theme::default_theme(ShadePreference::Dark);
+        let default_text = default
+            .get(Widget::Button, WidgetState::Enabled)
+            .text_style()
+            .unwrap();
+        assert_eq!(text.size(), default_text.size() * 2.0);
+    }
+}
+
+#[test]
+fn fontconfig() {
+    let resolver = FontResolver::load_blocking_with_context(&context("fonts", &[]))
+        .unwrap()
+        .unwrap();
+    let fonts = |family: FontFamily, weight: u16, italic: bool| {
+        let mut style = TextStyle::new(12.0, family);
+        style.set_weight(weight).set_italic(italic);
+
+        resolver
+            .resolve(&style)
+            .into_iter()
+            .map(|font| {
+                let path = font.path().to_path_buf();
+                let file = path.file_name().unwrap().to_string_lossy().into_owned();
+                (font.family().to_string(), file, font.index())
+            })
+            .collect::<Vec<_>>()
+    };
+    let font = |family: &str, file: &str, index: u32| (family.to_string(), file.to_string(), index);
+
+    // The alias in `conf.d` prefers one family and falls back to another.
+    assert_eq!(
+        fonts(FontFamily::SansSerif, 400, false),
+        [
+            font("Fixture Sans", "FixtureSans-Regular.ttf", 0),
+            font("Fixture Serif", "FixtureSerif.ttc", 0),
+        ]
+    );
+
+    // The closest face is picked, by its typographic family name or out of a collection.
+    assert_eq!(
+        fonts(FontFamily::SansSerif, 800, true),
+        [
+            font("Fixture Sans", "FixtureSans-BoldItalic.ttf", 0),
+            font("Fixture Serif", "FixtureSerif.ttc", 1),
+        ]
+    );
+
+    // The user's configuration is included through `prefix="xdg"`, and so are their fonts.
+    assert_eq!(
+        fonts(FontFamily::Monospace, 400, true),
+        [
+            font("Fixture Mono", "FixtureMono-Oblique.otf", 0),
+            font("Fixture Sans", "FixtureSans-BoldItalic.ttf", 0),
+        ]
+    );
+
+    // Families that aren't installed don't resolve to anything.
+    assert_eq!(fonts(FontFamily::Serif, 400, false), []);
+}
+
+#[test]
+fn available() {
+    let list = |fixture: &str| {
+        Theme::available_blocking_with_context(&context(fixture, &[]))
+            .unwrap()
+            .into_iter()
+            .map(|info| {
+                let path = info.path().map(|path| {
+                    path.strip_prefix(env!("CARGO_MANIFEST_DIR"))
+                        .unwrap()
+                        .to_path_buf()
+                });
+                (
+                    info.name().to_string(),
+                    info.source(),
+                    path,
+                    info.has_light_variant(),
+                    info.has_dark_variant(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let built_in = [
+        (
+            "Default".to_string(),
+            ThemeSource::BuiltIn,
+            None,
+            true,
+            true,
+        ),
+        (
+            "Default_HighContrast".to_string(),
+            ThemeSource::BuiltIn,
+            None,
+            true,
+            true,
+        ),
+    ];
+    let theme = |name: &str, source, path: &str, light, dark| {
+        (
+            name.to_string(),
+            source,
+            Some(PathBuf::from(path)),
+            light,
+            dark,
+        )
+    };
+
+    // The user's copy of a theme hides the system's, and the variants are found in any GTK
+    // version's directory.
+    let mut expected = built_in.to_vec();
+    expected.extend([
+        theme(
+            "Fixture",
+            ThemeSource::Gtk,
+            "tests/fixtures/settings-ini/home/user/.local/share/themes/Fixture",
+            true,
+            true,
+        ),
+        theme(
+            "Midnight",
+            ThemeSource::Gtk,
+            "tests/fixtures/settings-ini/usr/share/themes/Midnight",
+            false,
+            true,
+        ),
+    ]);
+    assert_eq!(list("settings-ini"), expected);
+
+    // Color schemes are light or dark by their window background.
+    let mut expected = built_in.to_vec();
+    expected.extend([
+        theme(
+            "FixtureDark",
+            ThemeSource::Kde,
+            "tests/fixtures/kde/usr/share/color-schemes/FixtureDark.colors",
+            false,
+            true,
+        ),
+        theme(
+            "FixtureLight",
+            ThemeSource::Kde,
+            "tests/fixtures/kde/home/user/.local/share/color-schemes/FixtureLight.colors",
+            true,
+            false,
+        ),
+    ]);
+    assert_eq!(list("kde"), expected);
+}
+
+#[test]
+fn icon_theme() {
+    let root = fixture("icons");
+    let context = context("icons", &[("XDG_CURRENT_DESKTOP", "sway")]);
+    let icons = IconTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    let find = |icon: &str, size: u32, scale: u32| {
+        icons
+            .find_icon_blocking(icon, size, scale)
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+    };
+
+    // The theme comes from `settings.ini`.
+    assert_eq!(icons.name(), "Fixture");
+    assert_eq!(icons.display_name(), Some("Fixture Icons"));
+
+    // Icons made for the size and scale come first, then the closest.
+    let icon = |path: &str| Some(PathBuf::from(path));
+    let fixture = "usr/share/icons/Fixture";
+    assert_eq!(
+        find("go-previous", 16, 1),
+        icon(&format!("{}/16x16/actions/go-previous.png", fixture))
+    );
+    assert_eq!(
+        find("go-previous", 16, 2),
+        icon(&format!("{}/16x16@2/actions/go-previous.png", fixture))
+    );
+    assert_eq!(
+        find("go-previous", 64, 1),
+        icon(&format!("{}/scalable/actions/go-previous.svg", fixture))
+    );
+    assert_eq!(
+        find("go-previous", 20, 1),
+        icon(&format!("{}/16x16/actions/go-previous.png", fixture))
+    );
+
+    // The user's copy of the theme adds to the system's.
+    assert_eq!(
+        find("document-open", 16, 1),
+        icon("home/user/.local/share/icons/Fixture/16x16/actions/document-open.png")
+    );
+
+    // Then come the inherited themes, `hicolor` and the icons outside of any theme.
+    assert_eq!(
+        find("folder", 22, 1),
+        icon("usr/share/icons/Parent/24x24/places/folder.png")
+    );
+    assert_eq!(
+        find("fixture-app", 48, 1),
+        icon("usr/share/icons/hicolor/48x48/apps/fixture-app.png")
+    );
+    assert_eq!(find("loose", 16, 1), icon("usr/share/pixmaps/loose.xpm"));
+
+    assert_eq!(find("missing", 16, 1), None);
+    assert_eq!(find("../pixmaps/loose", 16, 1), None);
+
+    // The name is translated, and a theme can be picked by name.
+    let context = self::context(
+        "icons",
+        &[("XDG_CURRENT_DESKTOP", "sway"), ("LANG", "de_DE.UTF-8")],
+    );
+    let icons = IconTheme::load_blocking_with_context(&context, "Parent")
+        .unwrap()
+        .unwrap();
+    assert_eq!(icons.name(), "Parent");
+    assert_eq!(icons.find_icon_blocking("go-previous", 16, 1), None);
+    let icons = IconTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(icons.display_name(), Some("Testsymbole"));
+
+    // Without the theme, only `hicolor` is left.
+    let icons = IconTheme::load_blocking_with_context(&context, "Missing")
+        .unwrap()
+        .unwrap();
+    assert_eq!(icons.name(), "hicolor");
+    assert!(icons.find_icon_blocking("fixture-app", 48, 1).is_some());
+}
+
+#[test]
+fn cursor_theme() {
+    let context = context("cursors", &[("XDG_CURRENT_DESKTOP", "sway")]);
+    let cursors = CursorTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    let sizes = |cursors: &CursorTheme, name: &str| {
+        cursors
+            .load_cursor_blocking(name)
+            .unwrap()
+            .map(|cursor| cursor.sizes())
+    };
+
+    // The theme and size come from `settings.ini`.
+    assert_eq!(cursors.name(), "FixtureCursors");
+    assert_eq!(cursors.size(), 32);
+
+    // The CSS names find cursors by their X11 names, in the theme and then in the ones it
+    // inherits from.
+    assert_eq!(sizes(&cursors, "default"), Some(vec![8]));
+    assert_eq!(sizes(&cursors, "left_ptr"), Some(vec![8]));
+    assert_eq!(sizes(&cursors, "wait"), Some(vec![4]));
+    assert_eq!(sizes(&cursors, "col-resize"), None);
+    assert_eq!(sizes(&cursors, "../cursors/left_ptr"), None);
+
+    // A broken cursor file is an error.
+    assert!(cursors.load_cursor_blocking("text").is_err());
+
+    // The environment wins over the settings, and may look in other directories.
+    let context = self::context(
+        "cursors",
+        &[
+            ("XDG_CURRENT_DESKTOP", "sway"),
+            ("XCURSOR_THEME", "Local"),
+            ("XCURSOR_SIZE", "48"),
+        ],
+    );
+    let cursors = CursorTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(cursors.name(), "Local");
+    assert_eq!(cursors.size(), 48);
+    assert_eq!(sizes(&cursors, "pointer"), Some(vec![4]));
+    assert_eq!(sizes(&cursors, "default"), None);
+
+    // `XCURSOR_PATH` replaces the directories, and without a desktop the size still comes from
+    // `settings.ini`.
+    let context = self::context(
+        "cursors",
+        &[
+            ("XCURSOR_THEME", "Local"),
+            ("XCURSOR_PATH", "/usr/share/icons"),
+        ],
+    );
+    let cursors = CursorTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(cursors.size(), 32);
+    assert_eq!(sizes(&cursors, "pointer"), None);
+}
+
+#[test]
+fn sound_theme() {
+    let root = fixture("sounds");
+    let find = |sounds: &SoundTheme, sound: &str| {
+        sounds
+            .find_sound_blocking(sound)
+            .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+    };
+    let sound = |path: &str| Some(PathBuf::from(path));
+
+    let context = context("sounds", &[("XDG_CURRENT_DESKTOP", "sway")]);
+    let sounds = SoundTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+
+    // The theme and whether to play event sounds come from `settings.ini`.
+    assert_eq!(sounds.name(), "FixtureSounds");
+    assert_eq!(sounds.display_name(), Some("Fixture Sounds"));
+    assert!(!sounds.event_sounds_enabled());
+
+    // Sounds come from the theme, then the ones it inherits from, then outside of any theme.
+    assert_eq!(
+        find(&sounds, "dialog-warning"),
+        sound("usr/share/sounds/FixtureSounds/stereo/dialog-warning.oga")
+    );
+    assert_eq!(
+        find(&sounds, "message"),
+        sound("usr/share/sounds/freedesktop/stereo/message.oga")
+    );
+    assert_eq!(
+        find(&sounds, "loose"),
+        sound("home/user/.local/share/sounds/loose.wav")
+    );
+
+    // Missing sounds fall back to more generic ones, but disabled sounds stay off.
+    assert_eq!(
+        find(&sounds, "dialog-error"),
+        sound("usr/share/sounds/freedesktop/stereo/dialog.ogg")
+    );
+    assert_eq!(find(&sounds, "bell"), None);
+    assert_eq!(find(&sounds, "missing"), None);
+    assert_eq!(find(&sounds, "../freedesktop/stereo/bell"), None);
+
+    // Sounds for the user's language come first.
+    let context = self::context(
+        "sounds",
+        &[("XDG_CURRENT_DESKTOP", "sway"), ("LANG", "de_DE.UTF-8")],
+    );
+    let sounds = SoundTheme::load_blocking_with_context(&context, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        find(&sounds, "dialog-warning"),
+        sound("usr/share/sounds/FixtureSounds/stereo/de/dialog-warning.oga")
+    );
+
+    // A theme can be picked by name.
+    let sounds = SoundTheme::load_blocking_with_context(&context, "freedesktop")
+        .unwrap()
+        .unwrap();
+    assert_eq!(sounds.name(), "freedesktop");
+    assert_eq!(
+        find(&sounds, "bell"),
+        sound("usr/share/sounds/freedesktop/stereo/bell.oga")
+    );
+}
@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: LGPL-3.0-or-later OR MPL-2.0
+// This file is a part of `ui-theme`.
+//
+// `ui-theme` is free software: you can redistribute it and/or modify it under the terms of
+// either:
+//
+// * GNU Lesser General Public License as published by the Free Software Foundation, either
+// version 3 of the License, or (at your option) any later version.
+// * Mozilla Public License as published by the Mozilla Foundation, version 2.
+//
+// `ui-theme` is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+// See the GNU Lesser General Public License or the Mozilla Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License and the Mozilla
+// Public License along with `ui-theme`. If not, see <https://www.gnu.org/licenses/> or
+// <https://www.mozilla.org/en-US/MPL/2.0/>.
+
+//! Watching for theme changes, both through a stand-in for the settings portal on a private bus
+//! and through the files that the theme was loaded from.
+
+#![cfg(free_unix)]
+
+use ui_theme::{
+    Color, Fill, LoadContext, ShadePreference, Theme, ThemeChanges, Widget, WidgetState,
+};
+
+use async_io::Timer;
+use futures_lite::{future, StreamExt};
+use zbus::{dbus_interface, ConnectionBuilder, SignalContext};
+use zvariant::{OwnedValue, Value};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// The object that the portal's interfaces live on.
+const PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// The namespace of the cross-desktop appearance settings.
+const APPEARANCE: &str = "org.freedesktop.appearance";
+
+/// A private `dbus-daemon`, which is stopped when dropped.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// Start a bus listening in `dir`, or return `None` if `dbus-daemon` isn't installed.
+    fn start(dir: &Path) -> Option<Self> {
+        let config = dir.join("bus.conf");
+        fs::write(
+            &config,
+            format!(
+                "<busconfig>\
+                    <type>session</type>\
+                    <listen>unix:dir={}</listen>\
+                    <auth>EXTERNAL</auth>\
+                    <policy context=\"default\">\
+                        <allow send_destination=\"*\" eavesdrop=\"true\"/>\
+                        <allow eavesdrop=\"true\"/>\
+                        <allow own=\"*\"/>\
+                    </policy>\
+                </busconfig>",
+                dir.display()
+            ),
+        )
+        .unwrap();
+
+        let mut daemon = match Command::new("dbus-daemon")
+            .arg("--nofork")
+            .arg("--print-address")
+            .arg(format!("--config-file={}", config.display()))
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => panic!("failed to start dbus-daemon: {}", err),
+        };
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        self.daemon.kill().ok();
+        self.daemon.wait().ok();
+    }
+}
+
+/// A stand-in for the settings portal, with the appearance settings.
+struct Portal {
+    color_scheme: u32,
+    accent_color: (f64, f64, f64),
+    contrast: u32,
+}
+
+impl Portal {
+    /// A portal with the given color scheme, and no accent color or contrast preference.
+    fn new(color_scheme: u32) -> Self {
+        Self {
+            color_scheme,
+            // Channels out of range mean that there is no accent color.
+            accent_color: (-1.0, -1.0, -1.0),
+            contrast: 0,
+        }
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.portal.Settings")]
+impl Portal {
+    fn read_all(&self, _namespaces: Vec<String>) -> HashMap<String, HashMap<String, OwnedValue>> {
+        let mut appearance = HashMap::new();
+        appearance.insert("color-scheme".to_string(), self.color_scheme.into());
+        appearance.insert(
+            "accent-color".to_string(),
+            Value::from(self.accent_color).into(),
+        );
+        appearance.insert("contrast".to_string(), self.contrast.into());
+        HashMap::from([(APPEARANCE.to_string(), appearance)])
+    }
+
+    #[dbus_interface(signal)]
+    async fn setting_changed(
+        ctxt: &SignalContext<'_>,
+        namespace: &str,
+        key: &str,
+        value: Value<'_>,
+    ) -> zbus::Result<()>;
+}
+
+/// Make an empty directory for this test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ui-theme-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(dir.join("root/home")).unwrap();
+    dir
+}
+
+#[test]
+fn reload_on_setting_changed() {
+    let dir = test_dir("watch");
+    let bus = match Bus::start(&dir) {
+        Some(bus) => bus,
+        None => {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        }
+    };
+
+    future::block_on(async {
+        let portal = ConnectionBuilder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.portal.Desktop")
+            .unwrap()
+            .serve_at(PATH, Portal::new(2))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let mut context = LoadContext::new();
+        context
+            .set_env([("HOME", "/home")])
+            .set_sysroot(dir.join("root"))
+            .set_bus_address(bus.address.as_str());
+
+        let theme = Theme::load_with_context(&context, None, ShadePreference::Dark)
+            .await
+            .unwrap();
+        assert_eq!(theme.name(), "Default_Light");
+
+        let mut changes = Theme::watch_with_context(&context, None, ShadePreference::Light)
+            .await
+            .unwrap();
+
+        // Switch to dark mode.
+        let iface = portal
+            .object_server()
+            .interface::<_, Portal>(PATH)
+            .await
+            .unwrap();
+        iface.get_mut().await.color_scheme = 1;
+        Portal::setting_changed(
+            iface.signal_context(),
+            APPEARANCE,
+            "color-scheme",
+            Value::U32(1),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(next_theme(&mut changes).await.name(), "Default_Dark");
+    });
+
+    drop(bus);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn accent_and_contrast() {
+    let dir = test_dir("appearance");
+    let bus = match Bus::start(&dir) {
+        Some(bus) => bus,
+        None => {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        }
+    };
+
+    let red = Color::new(255, 0, 0, 255);
+    let selected_background = |theme: &Theme| {
+        theme
+            .get(Widget::Button, WidgetState::Selected)
+            .background()
+            .cloned()
+    };
+
+    future::block_on(async {
+        let portal = ConnectionBuilder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.portal.Desktop")
+            .unwrap()
+            .serve_at(
+                PATH,
+                Portal {
+                    accent_color: (1.0, 0.0, 0.0),
+                    ..Portal::new(2)
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let mut context = LoadContext::new();
+        context
+            .set_env([("HOME", "/home")])
+            .set_sysroot(dir.join("root"))
+            .set_bus_address(bus.address.as_str());
+
+        // The accent color is read on its own, and colors the selection of the default theme.
+        assert_eq!(
+            Theme::system_accent_color_with_context(&context)
+                .await
+                .unwrap(),
+            Some(red)
+        );
+        let theme = Theme::load_with_context(&context, None, ShadePreference::Dark)
+            .await
+            .unwrap();
+        assert_eq!(theme.name(), "Default_Light");
+        assert_eq!(selected_background(&theme), Some(Fill::Color(red)));
+
+        // Ask for high contrast.
+        let iface = portal
+            .object_server()
+            .interface::<_, Portal>(PATH)
+            .await
+            .unwrap();
+        iface.get_mut().await.contrast = 1;
+
+        let theme = Theme::load_with_context(&context, None, ShadePreference::Dark)
+            .await
+            .unwrap();
+        assert_eq!(theme.name(), "Default_HighContrast_Light");
+        assert_eq!(selected_background(&theme), Some(Fill::Color(red)));
+
+        // Without an accent color, the default one is used.
+        iface.get_mut().await.accent_color = (-1.0, -1.0, -1.0);
+        assert_eq!(
+            Theme::system_accent_color_with_context(&context)
+                .await
+                .unwrap(),
+            None
+        );
+        let theme = Theme::load_with_context(&context, None, ShadePreference::Dark)
+            .await
+            .unwrap();
+        assert_ne!(selected_background(&theme), Some(Fill::Color(red)));
+    });
+
+    drop(bus);
+    fs::remove_dir_all(&dir).ok();
+}
+
+/// Wait for the next theme from a watch, giving up after a while.
+async fn next_theme(changes: &mut ThemeChanges) -> Theme {
+    let timeout = async {
+        Timer::after(Duration::from_secs(10)).await;
+        None
+    };
+    future::or(changes.next(), timeout)
+        .await
+        .expect("no theme after the change")
+        .unwrap()
+}
+
+/// Write a file, creating the directories above it.
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Watch the theme in the GNOME desktop under `dir/root`, without a bus so that only the files
+/// are watched.
+async fn watch_files(dir: &Path) -> ThemeChanges {
+    let mut context = LoadContext::new();
+    context
+        .set_env([("HOME", "/home"), ("XDG_CURRENT_DESKTOP", "GNOME")])
+        .set_sysroot(dir.join("root"))
+        .set_bus_address(format!("unix:path={}", dir.join("no-bus").display()));
+
+    Theme::watch_with_context(&context, None, ShadePreference::Light)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn reload_on_file_created() {
+    let dir = test_dir("files");
+    let root = dir.join("root");
+    write(&root.join("etc/dconf/profile/user"), "system-db:local\n");
+    for name in ["First", "Second"] {
+        write(
+            &root
+                .join("usr/share/themes")
+                .join(name)
+                .join("gtk-3.0/gtk.css"),
+            "button { background-color: #102030; }\n",
+        );
+    }
+
+    future::block_on(async {
+        let mut changes = watch_files(&dir).await;
+
+        // Neither the key files nor the directories above them exist yet.
+        write(
+            &root.join("etc/dconf/db/local.d/00-theme"),
+            "[org/gnome/desktop/interface]\ngtk-theme='First'\n",
+        );
+        assert_eq!(next_theme(&mut changes).await.name(), "First");
+
+        // A new key file in the directory overrides the one before it.
+        write(
+            &root.join("etc/dconf/db/local.d/01-theme"),
+            "[org/gnome/desktop/interface]\ngtk-theme='Second'\n",
+        );
+        assert_eq!(next_theme(&mut changes).await.name(), "Second");
+    });
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn reload_on_dir_removed() {
+    let dir = test_dir("removed");
+    let root = dir.join("root");
+    write(
+        &root.join("usr/share/themes/First/gtk-3.0/gtk.css"),
+        "button { background-color: #102030; }\n",
+    );
+    let settings = root.join("home/.config/glib-2.0/settings");
+    fs::create_dir_all(&settings).unwrap();
+
+    future::block_on(async {
+        let mut changes = watch_files(&dir).await;
+
+        // Only the directory's own watch sees it go, since the one above it isn't watched.
+        fs::remove_dir(&settings).unwrap();
+        assert_ne!(next_theme(&mut changes).await.name(), "First");
+
+        // So the directory is watched for again.
+        write(
+            &settings.join("keyfile"),
+            "[org/gnome/desktop/interface]\ngtk-theme='First'\n",
+        );
+        assert_eq!(next_theme(&mut changes).await.name(), "First");
+    });
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn reload_on_overflow() {
+    let dir = test_dir("overflow");
+    let root = dir.join("root");
+    write(&root.join("etc/dconf/profile/user"), "system-db:local\n");
+    write(
+        &root.join("usr/share/themes/First/gtk-3.0/gtk.css"),
+        "button { background-color: #102030; }\n",
+    );
+
+    // Only the one file is watched for in the directory.
+    let db = root.join("etc/dconf/db");
+    fs::create_dir_all(&db).unwrap();
+    let max_events = fs::read_to_string("/proc/sys/fs/inotify/max_queued_events")
+        .ok()
+        .and_then(|max| max.trim().parse::<usize>().ok())
+        .unwrap_or(16384);
+
+    future::block_on(async {
+        let mut changes = watch_files(&dir).await;
+
+        // Other files fill up the queue before the theme changes, so the change is lost.
+        for i in 0..=max_events {
+            fs::write(db.join(format!("other-{}", i)), "").unwrap();
+        }
+        write(
+            &db.join("local.d/00-theme"),
+            "[org/gnome/desktop/interface]\ngtk-theme='First'\n",
+        );
+        assert_eq!(next_theme(&mut changes).await.name(), "First");
+    });
+
+    fs::remove_dir_all(&dir).ok();
+}